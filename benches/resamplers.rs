@@ -275,6 +275,30 @@ bench_async_resampler!(
     "neon async nearest 64"
 );
 
+fn bench_scalar_async_linear_separable_64(c: &mut Criterion) {
+    let chunksize = 1024;
+    let resample_ratio = 192000.0 / 44100.0;
+    let interpolator = Box::new(ScalarInterpolator::<f64>::new_separable(
+        256,
+        256,
+        0.947_337_15,
+        WindowFunction::BlackmanHarris2,
+    ));
+    let mut resampler = SincFixedIn::<f64>::new_with_interpolator(
+        resample_ratio,
+        1.1,
+        SincInterpolationType::Linear,
+        interpolator,
+        chunksize,
+        1,
+    )
+    .unwrap();
+    let waveform = vec![vec![0.0_f64; chunksize]; 1];
+    c.bench_function("scalar async linear separable 64", |b| {
+        b.iter(|| resampler.process(black_box(&waveform), None).unwrap())
+    });
+}
+
 macro_rules! bench_fast_async_resampler {
     ($ft:ty, $ip:expr, $f:ident, $desc:literal) => {
         fn $f(c: &mut Criterion) {
@@ -374,6 +398,7 @@ criterion_group!(
     bench_scalar_async_nearest_32,
     bench_scalar_async_cubic_64,
     bench_scalar_async_linear_64,
+    bench_scalar_async_linear_separable_64,
     bench_scalar_async_nearest_64,
     bench_sse_async_cubic_32,
     bench_sse_async_linear_32,
@@ -407,6 +432,7 @@ criterion_group!(
     bench_scalar_async_nearest_32,
     bench_scalar_async_cubic_64,
     bench_scalar_async_linear_64,
+    bench_scalar_async_linear_separable_64,
     bench_scalar_async_nearest_64,
     bench_neon_async_cubic_32,
     bench_neon_async_linear_32,
@@ -434,6 +460,7 @@ criterion_group!(
     bench_scalar_async_nearest_32,
     bench_scalar_async_cubic_64,
     bench_scalar_async_linear_64,
+    bench_scalar_async_linear_separable_64,
     bench_scalar_async_nearest_64,
 );
 
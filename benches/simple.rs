@@ -0,0 +1,43 @@
+// Benchmarks for resampling complete clips with `resample_mono`.
+// The ratio 4 uses the cascade of halfband filters,
+// and the slightly different ratio 44101 / 11025 uses the general sinc resampler.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+extern crate rubato;
+
+use rubato::{resample_mono, Quality};
+
+macro_rules! bench_clip {
+    ($from:expr, $to:expr, $f:ident, $desc:literal) => {
+        fn $f(c: &mut Criterion) {
+            let input = vec![0.0f32; 11025];
+            c.bench_function($desc, |b| {
+                b.iter(|| resample_mono(black_box(&input), $from, $to, Quality::High))
+            });
+        }
+    };
+}
+
+bench_clip!(
+    11025,
+    44100,
+    bench_halfband_up_4,
+    "resample_mono halfband 4x"
+);
+bench_clip!(11025, 44101, bench_sinc_up_4, "resample_mono sinc 4x");
+bench_clip!(
+    44100,
+    11025,
+    bench_halfband_down_4,
+    "resample_mono halfband 1/4x"
+);
+bench_clip!(44101, 11025, bench_sinc_down_4, "resample_mono sinc 1/4x");
+
+criterion_group!(
+    benches,
+    bench_halfband_up_4,
+    bench_sinc_up_4,
+    bench_halfband_down_4,
+    bench_sinc_down_4,
+);
+
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+// Benchmarks for generating window functions.
+// Compare the scalar and SIMD versions by running once without and once with the `simd` feature:
+// cargo bench --bench windows
+// cargo bench --bench windows --features simd
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+extern crate rubato;
+
+use rubato::{bench_make_window, WindowFunction};
+
+macro_rules! bench_window {
+    ($ft:ty, $window:expr, $npoints:expr, $f:ident, $desc:literal) => {
+        fn $f(c: &mut Criterion) {
+            c.bench_function($desc, |b| {
+                b.iter(|| bench_make_window::<$ft>(black_box($npoints), $window))
+            });
+        }
+    };
+}
+
+bench_window!(
+    f32,
+    WindowFunction::BlackmanHarris,
+    1024,
+    bench_blackman_harris_1024_32,
+    "blackman-harris 1024 32"
+);
+bench_window!(
+    f32,
+    WindowFunction::BlackmanHarris,
+    8192,
+    bench_blackman_harris_8192_32,
+    "blackman-harris 8192 32"
+);
+bench_window!(
+    f64,
+    WindowFunction::BlackmanHarris,
+    1024,
+    bench_blackman_harris_1024_64,
+    "blackman-harris 1024 64"
+);
+bench_window!(
+    f64,
+    WindowFunction::BlackmanHarris,
+    8192,
+    bench_blackman_harris_8192_64,
+    "blackman-harris 8192 64"
+);
+bench_window!(
+    f64,
+    WindowFunction::Blackman,
+    1024,
+    bench_blackman_1024_64,
+    "blackman        1024 64"
+);
+bench_window!(
+    f64,
+    WindowFunction::Blackman,
+    8192,
+    bench_blackman_8192_64,
+    "blackman        8192 64"
+);
+bench_window!(
+    f64,
+    WindowFunction::Hann,
+    1024,
+    bench_hann_1024_64,
+    "hann            1024 64"
+);
+bench_window!(
+    f64,
+    WindowFunction::Hann,
+    8192,
+    bench_hann_8192_64,
+    "hann            8192 64"
+);
+
+criterion_group!(
+    benches,
+    bench_blackman_harris_1024_32,
+    bench_blackman_harris_8192_32,
+    bench_blackman_harris_1024_64,
+    bench_blackman_harris_8192_64,
+    bench_blackman_1024_64,
+    bench_blackman_8192_64,
+    bench_hann_1024_64,
+    bench_hann_8192_64,
+);
+
+criterion_main!(benches);
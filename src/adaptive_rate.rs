@@ -0,0 +1,364 @@
+use crate::error::{ResampleError, ResampleResult};
+use crate::{OverflowPolicy, Resampler, Sample, SincFixedIn};
+
+/// Weight of each new value in the smoothing of the buffer level.
+const LEVEL_SMOOTHING: f64 = 0.02;
+
+/// Time in seconds between the first and the last timestamp before the input rate is estimated.
+/// Over shorter times, the jitter of the timestamps gives large errors in the estimate.
+const MIN_ESTIMATE_DURATION: f64 = 0.5;
+
+/// A wrapper around [SincFixedIn] for resampling a live input, such as an audio capture device,
+/// where the nominal input sample rate may differ slightly from the true rate.
+///
+/// Input is pushed in blocks of any size together with the time when each block arrived,
+/// using [push_input](AdaptiveRateResampler::push_input).
+/// The arrival times are used to estimate the true input sample rate,
+/// and the resampling ratio is set to convert from the estimated rate to the output rate.
+/// The resampled frames are stored in an output queue, that is emptied by
+/// [pull_output](AdaptiveRateResampler::pull_output) at the output rate.
+///
+/// Any remaining error in the estimate makes the number of buffered frames drift.
+/// To compensate, the ratio is also adjusted by a PI controller
+/// that keeps the buffer level at the target level.
+/// The default gains are small, since the rate estimate takes care of the bulk of the correction,
+/// and the measured level fluctuates by up to one block as frames are pushed and pulled.
+///
+/// The wrapped resampler must be created with the nominal ratio,
+/// and with a large enough `max_resample_ratio_relative` to allow the needed adjustments.
+/// Its [OverflowPolicy] is set to [OverflowPolicy::Clamp], so that a ratio outside the allowed
+/// range is limited to the range instead of giving an error.
+pub struct AdaptiveRateResampler<T> {
+    resampler: SincFixedIn<T>,
+    output_rate: f64,
+    ratio: f64,
+    target_level: usize,
+    gain_p: f64,
+    gain_i: f64,
+    integral: f64,
+    smoothed_level: Option<f64>,
+    first_timestamp: Option<f64>,
+    last_timestamp: f64,
+    frames_since_first: usize,
+    input_queue: Vec<Vec<T>>,
+    output_queue: Vec<Vec<T>>,
+    output_buffer: Vec<Vec<T>>,
+}
+
+impl<T> AdaptiveRateResampler<T>
+where
+    T: Sample,
+{
+    /// Create a new AdaptiveRateResampler.
+    ///
+    /// Parameters are:
+    /// - `resampler`: The resampler to wrap, created with the ratio `output_rate / nominal_input_rate`.
+    /// - `nominal_input_rate`: The nominal input sample rate.
+    /// - `output_rate`: The output sample rate.
+    /// - `target_level`: The wanted number of frames in the buffers.
+    ///   This should be larger than the number of frames pulled in each call to
+    ///   [pull_output](AdaptiveRateResampler::pull_output), to prevent underruns.
+    pub fn new(
+        resampler: SincFixedIn<T>,
+        nominal_input_rate: f64,
+        output_rate: f64,
+        target_level: usize,
+    ) -> Self {
+        let mut resampler = resampler;
+        resampler.set_ratio_overflow_policy(OverflowPolicy::Clamp);
+        let channels = resampler.nbr_channels();
+        let capacity = 4 * (resampler.output_frames_max() + target_level);
+        let output_buffer = resampler.output_buffer_allocate(true);
+        AdaptiveRateResampler {
+            input_queue: vec![Vec::with_capacity(4 * resampler.input_frames_max()); channels],
+            output_queue: vec![Vec::with_capacity(capacity); channels],
+            resampler,
+            output_rate,
+            ratio: output_rate / nominal_input_rate,
+            target_level,
+            gain_p: 1.0e-4,
+            gain_i: 1.0e-8,
+            integral: 0.0,
+            smoothed_level: None,
+            first_timestamp: None,
+            last_timestamp: 0.0,
+            frames_since_first: 0,
+            output_buffer,
+        }
+    }
+
+    /// Set the gains of the PI controller.
+    /// The error is measured as the deviation of the buffer level from the target level,
+    /// relative to the target level. It is updated once per call to
+    /// [push_input](AdaptiveRateResampler::push_input).
+    /// The buffer level is smoothed by a first order low-pass filter before it is used,
+    /// to suppress the fluctuations caused by pushing and pulling frames in blocks.
+    /// The default values are 1.0e-4 for the proportional gain and 1.0e-8 for the integral gain.
+    pub fn set_controller_gains(&mut self, gain_p: f64, gain_i: f64) {
+        self.gain_p = gain_p;
+        self.gain_i = gain_i;
+    }
+
+    /// Add a block of input frames, that arrived at the given `timestamp` in seconds.
+    /// All channels must contain the same number of frames.
+    /// The timestamps must be increasing, and should be taken from a clock that
+    /// runs at the same rate as the output.
+    ///
+    /// All complete chunks of input are resampled, and the ratio is updated.
+    pub fn push_input<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        timestamp: f64,
+    ) -> ResampleResult<()> {
+        if wave_in.len() != self.input_queue.len() {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: self.input_queue.len(),
+                actual: wave_in.len(),
+            });
+        }
+        let frames = wave_in[0].as_ref().len();
+        for (channel, wave) in wave_in.iter().enumerate() {
+            if wave.as_ref().len() != frames {
+                return Err(ResampleError::InsufficientInputBufferSize {
+                    channel,
+                    expected: frames,
+                    actual: wave.as_ref().len(),
+                });
+            }
+        }
+        for (chan, wave) in self.input_queue.iter_mut().zip(wave_in.iter()) {
+            chan.extend_from_slice(wave.as_ref());
+        }
+        // The frames of the first block arrived before the first timestamp,
+        // and are not included in the estimate.
+        if self.first_timestamp.is_none() {
+            self.first_timestamp = Some(timestamp);
+        } else {
+            self.frames_since_first += frames;
+        }
+        self.update_ratio(timestamp)?;
+
+        while self.input_queue[0].len() >= self.resampler.input_frames_next() {
+            let (frames_in, frames_out) = self.resampler.process_into_buffer(
+                &self.input_queue,
+                &mut self.output_buffer,
+                None,
+            )?;
+            for (queue, (chan_in, chan_out)) in self
+                .output_queue
+                .iter_mut()
+                .zip(self.input_queue.iter_mut().zip(self.output_buffer.iter()))
+            {
+                chan_in.drain(..frames_in);
+                queue.extend_from_slice(&chan_out[..frames_out]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Take `wave_out[0].len()` frames of output from the output queue.
+    /// All channels must have the same length.
+    /// Returns [ResampleError::InputUnderrun] if the queue holds too few frames,
+    /// and nothing is consumed.
+    pub fn pull_output<V: AsMut<[T]>>(&mut self, wave_out: &mut [V]) -> ResampleResult<usize> {
+        if wave_out.len() != self.output_queue.len() {
+            return Err(ResampleError::WrongNumberOfOutputChannels {
+                expected: self.output_queue.len(),
+                actual: wave_out.len(),
+            });
+        }
+        let needed = wave_out[0].as_mut().len();
+        for (channel, wave) in wave_out.iter_mut().enumerate() {
+            if wave.as_mut().len() != needed {
+                return Err(ResampleError::InsufficientOutputBufferSize {
+                    channel,
+                    expected: needed,
+                    actual: wave.as_mut().len(),
+                });
+            }
+        }
+        let available = self.output_queue[0].len();
+        if available < needed {
+            return Err(ResampleError::InputUnderrun { needed, available });
+        }
+        for (queue, wave) in self.output_queue.iter_mut().zip(wave_out.iter_mut()) {
+            wave.as_mut().copy_from_slice(&queue[..needed]);
+            queue.drain(..needed);
+        }
+        Ok(needed)
+    }
+
+    /// Get the number of buffered frames, as the frames in the output queue
+    /// plus the frames in the input queue converted to the output rate.
+    pub fn buffer_level(&self) -> f64 {
+        self.output_queue[0].len() as f64 + self.input_queue[0].len() as f64 * self.ratio
+    }
+
+    /// Get the estimated input sample rate,
+    /// or `None` if the input has not been pushed for long enough for making an estimate.
+    /// The estimate is made once the timestamps span at least half a second,
+    /// and until then the ratio is kept at the nominal value.
+    pub fn estimated_input_rate(&self) -> Option<f64> {
+        let first = self.first_timestamp?;
+        if self.frames_since_first == 0 {
+            return None;
+        }
+        let elapsed = self.last_timestamp - first;
+        if elapsed < MIN_ESTIMATE_DURATION {
+            return None;
+        }
+        Some(self.frames_since_first as f64 / elapsed)
+    }
+
+    /// Get the current resampling ratio.
+    pub fn resample_ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Clear the buffers, the rate estimate and the controller state,
+    /// and reset the wrapped resampler.
+    pub fn reset(&mut self) {
+        self.input_queue.iter_mut().for_each(|chan| chan.clear());
+        self.output_queue.iter_mut().for_each(|chan| chan.clear());
+        self.integral = 0.0;
+        self.smoothed_level = None;
+        self.first_timestamp = None;
+        self.frames_since_first = 0;
+        self.resampler.reset();
+    }
+
+    /// Get a reference to the wrapped resampler.
+    pub fn resampler(&self) -> &SincFixedIn<T> {
+        &self.resampler
+    }
+
+    /// Update the ratio from the rate estimate and the buffer level.
+    /// The controller state is only updated once the resampler has accepted the new ratio.
+    fn update_ratio(&mut self, timestamp: f64) -> ResampleResult<()> {
+        self.last_timestamp = timestamp;
+        let input_rate = match self.estimated_input_rate() {
+            Some(rate) => rate,
+            None => return Ok(()),
+        };
+        let level = self.buffer_level();
+        let smoothed = match self.smoothed_level {
+            Some(smoothed) => smoothed + LEVEL_SMOOTHING * (level - smoothed),
+            None => level,
+        };
+        let error = (smoothed - self.target_level as f64) / self.target_level as f64;
+        let integral = self.integral + error;
+        let correction = self.gain_p * error + self.gain_i * integral;
+        let ratio = self.output_rate / input_rate * (1.0 - correction);
+        trace!(
+            "Estimated input rate: {}, buffer level error: {}, new ratio: {}",
+            input_rate,
+            error,
+            ratio
+        );
+        self.resampler.set_resample_ratio(ratio, true)?;
+        // The ratio may have been clamped to the allowed range.
+        self.ratio = self.resampler.target_ratio();
+        self.smoothed_level = Some(smoothed);
+        self.integral = integral;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdaptiveRateResampler;
+    use crate::{
+        ResampleError, SincFixedIn, SincInterpolationParameters, SincInterpolationType,
+        WindowFunction,
+    };
+    use rand::Rng;
+    use test_log::test;
+
+    #[test]
+    fn converges_to_true_rate() {
+        let params = SincInterpolationParameters {
+            sinc_len: 32,
+            f_cutoff: 0.9,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let resampler = SincFixedIn::<f64>::new(1.0, 1.1, params, 480, 1).unwrap();
+        let mut adaptive = AdaptiveRateResampler::new(resampler, 48000.0, 48000.0, 2000);
+        // The input runs 0.1% fast, the output runs exactly at the nominal rate.
+        let input_rate = 48048.0;
+        let block = vec![vec![0.0; 480]; 1];
+        let mut wave_out = vec![vec![0.0; 480]; 1];
+        let mut pushed = 0;
+        let mut pulled = 0;
+        // Start pulling output once the target level has been reached.
+        let mut next_pull = 2000.0 / input_rate;
+        while next_pull < 60.0 {
+            let next_push = (pushed + 1) as f64 * 480.0 / input_rate;
+            if next_push < next_pull {
+                adaptive.push_input(&block, next_push).unwrap();
+                pushed += 1;
+            } else {
+                adaptive.pull_output(&mut wave_out).unwrap();
+                pulled += 1;
+                next_pull += 0.01;
+            }
+        }
+        assert!(pulled > 5900);
+        let estimated = adaptive.estimated_input_rate().unwrap();
+        assert!((estimated - input_rate).abs() < 0.1, "{}", estimated);
+        let expected_ratio = 48000.0 / input_rate;
+        assert!(
+            (adaptive.resample_ratio() - expected_ratio).abs() < 1.0e-5,
+            "ratio {} expected {}",
+            adaptive.resample_ratio(),
+            expected_ratio
+        );
+        assert!((adaptive.buffer_level() - 2000.0).abs() < 600.0);
+    }
+
+    #[test]
+    fn jittered_timestamps() {
+        let params = SincInterpolationParameters {
+            sinc_len: 32,
+            f_cutoff: 0.9,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let resampler = SincFixedIn::<f64>::new(1.0, 1.01, params, 480, 2).unwrap();
+        let mut adaptive = AdaptiveRateResampler::new(resampler, 48000.0, 48000.0, 2000);
+        let mut rng = rand::thread_rng();
+        let input_rate = 48048.0;
+        let block = vec![vec![0.0; 480]; 2];
+        let mut wave_out = vec![vec![0.0; 480]; 2];
+        let mut pushed = 0;
+        let mut next_pull = 2000.0 / input_rate;
+        while next_pull < 30.0 {
+            let next_push = (pushed + 1) as f64 * 480.0 / input_rate;
+            if next_push < next_pull {
+                // Blocks of 10 ms arrive with up to 2 ms of jitter.
+                let jitter = 0.002 * (2.0 * rng.gen::<f64>() - 1.0);
+                adaptive.push_input(&block, next_push + jitter).unwrap();
+                pushed += 1;
+                let ratio = adaptive.resample_ratio();
+                assert!((1.0 / 1.01..=1.01).contains(&ratio), "ratio {}", ratio);
+            } else {
+                adaptive.pull_output(&mut wave_out).unwrap();
+                next_pull += 0.01;
+            }
+        }
+        let estimated = adaptive.estimated_input_rate().unwrap();
+        assert!((estimated - input_rate).abs() < 10.0, "{}", estimated);
+
+        let mut uneven = vec![vec![0.0; 480], vec![0.0; 479]];
+        assert!(matches!(
+            adaptive.pull_output(&mut uneven),
+            Err(ResampleError::InsufficientOutputBufferSize {
+                channel: 1,
+                expected: 480,
+                actual: 479
+            })
+        ));
+    }
+}
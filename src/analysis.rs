@@ -0,0 +1,103 @@
+//! Helpers for analyzing signals before resampling them.
+
+use crate::Sample;
+
+/// Helper function. Calculate the autocorrelation of a signal at a lag,
+/// normalized by the number of overlapping samples.
+fn autocorrelation_at<T: Sample>(signal: &[T], lag: usize) -> f64 {
+    let overlap = signal.len() - lag;
+    let sum = signal[..overlap]
+        .iter()
+        .zip(signal[lag..].iter())
+        .fold(0.0, |acc, (a, b)| acc + a.to_f64() * b.to_f64());
+    sum / overlap as f64
+}
+
+/// Helper function. Find the lag with the highest autocorrelation between `first` and `last`,
+/// and refine it to a fraction of a sample by fitting a parabola through the neighboring lags.
+fn autocorrelation_peak<T: Sample>(signal: &[T], first: usize, last: usize) -> f64 {
+    let (peak, _) = (first..=last)
+        .map(|lag| (lag, autocorrelation_at(signal, lag)))
+        .fold((first, f64::NEG_INFINITY), |(best, max), (lag, value)| {
+            if value > max {
+                (lag, value)
+            } else {
+                (best, max)
+            }
+        });
+    if peak == 0 || peak + 1 >= signal.len() {
+        return peak as f64;
+    }
+    let before = autocorrelation_at(signal, peak - 1);
+    let center = autocorrelation_at(signal, peak);
+    let after = autocorrelation_at(signal, peak + 1);
+    let curvature = before - 2.0 * center + after;
+    if curvature >= 0.0 {
+        return peak as f64;
+    }
+    peak as f64 + 0.5 * (before - after) / curvature
+}
+
+/// Estimate the actual sample rate of a signal containing a reference tone of a known frequency,
+/// for example a pilot tone recorded by a device with an inaccurate clock.
+/// The estimate can be used as the input rate when creating a resampler,
+/// to correct for the clock error.
+///
+/// The period of the tone in samples is measured from the autocorrelation of the signal,
+/// starting from the period expected at the `nominal_rate`.
+/// The estimate is then refined with the autocorrelation at multiples of the period,
+/// up to half the length of the signal, so that longer signals give more accurate estimates.
+/// The measured period may differ from the expected one by up to a quarter of a period.
+///
+/// Returns `None` if the signal is shorter than two periods of the tone,
+/// or if the reference frequency is not between zero and half the nominal rate.
+pub fn infer_sample_rate<T>(
+    signal: &[T],
+    nominal_rate: u32,
+    reference_frequency: f64,
+) -> Option<f64>
+where
+    T: Sample,
+{
+    if !(reference_frequency > 0.0 && reference_frequency < nominal_rate as f64 / 2.0) {
+        return None;
+    }
+    let expected_period = nominal_rate as f64 / reference_frequency;
+    if (signal.len() as f64) < 2.0 * expected_period {
+        return None;
+    }
+    let max_lag = signal.len() / 2;
+    let mut period = expected_period;
+    let mut periods = 1;
+    while (periods as f64 * period) < max_lag as f64 {
+        // Search within a quarter of a period around the lag predicted by the current estimate.
+        let predicted = periods as f64 * period;
+        let first = (predicted - 0.25 * period).floor().max(1.0) as usize;
+        let last = ((predicted + 0.25 * period).ceil() as usize).min(max_lag);
+        period = autocorrelation_peak(signal, first, last) / periods as f64;
+        periods *= 2;
+    }
+    Some(period * reference_frequency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::infer_sample_rate;
+    use crate::testing::signals::sine;
+    use test_log::test;
+
+    #[test]
+    fn infer_rate_of_tone() {
+        // A 1 kHz tone recorded by a device that runs at 48030 Hz instead of 48000 Hz.
+        let signal = sine::<f64>(1000.0, 48030.0, 48000, 1).remove(0);
+        let rate = infer_sample_rate(&signal, 48000, 1000.0).unwrap();
+        assert!((rate - 48030.0).abs() < 1.0, "Estimated rate {}", rate);
+
+        let signal = sine::<f32>(997.0, 44080.0, 20000, 1).remove(0);
+        let rate = infer_sample_rate(&signal, 44100, 997.0).unwrap();
+        assert!((rate - 44080.0).abs() < 2.0, "Estimated rate {}", rate);
+
+        assert_eq!(infer_sample_rate(&signal[..50], 44100, 997.0), None);
+        assert_eq!(infer_sample_rate(&signal, 44100, 30000.0), None);
+    }
+}
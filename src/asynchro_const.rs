@@ -0,0 +1,312 @@
+use crate::error::ResamplerConstructionError;
+use crate::interpolation::get_nearest_time;
+use crate::sinc::sinc;
+use crate::windows::{window_value, WindowFunction};
+use crate::Sample;
+use std::mem::MaybeUninit;
+use std::ptr::addr_of_mut;
+
+/// An asynchronous sinc resampler where all sizes are given at compile time,
+/// intended for targets such as microcontrollers where dynamic allocation should be avoided.
+///
+/// The sinc filters and the internal buffers are stored in fixed size arrays,
+/// so that the complete resampler can be placed in a static variable without using any `Vec`:
+/// - `SINC_LEN`: Length of the windowed sinc interpolation filter.
+/// - `N_PHASES`: The number of intermediate points, like the `oversampling_factor`
+///   of [SincInterpolationParameters](crate::SincInterpolationParameters).
+/// - `CHANNELS`: Number of channels in input/output.
+///
+/// The audio data is processed as frames, stored as arrays with one sample per channel.
+/// Each input frame is stored in a history buffer of `SINC_LEN` frames,
+/// and the output frames are calculated as soon as the history holds the needed input.
+/// The output uses the intermediate point nearest to the wanted time,
+/// meaning that a large number of phases, such as 256 or more, is needed for good quality.
+///
+/// The resampling ratio is fixed at creation.
+/// Neither creating nor processing allocates.
+/// The sinc table holds `SINC_LEN * N_PHASES` samples, and for large tables
+/// [new_in_place](SincFixedInConst::new_in_place) avoids building the resampler on the stack.
+pub struct SincFixedInConst<T, const SINC_LEN: usize, const N_PHASES: usize, const CHANNELS: usize>
+{
+    sincs: [[T; SINC_LEN]; N_PHASES],
+    history: [[T; SINC_LEN]; CHANNELS],
+    write_pos: usize,
+    offset: f64,
+    step: f64,
+    resample_ratio: f64,
+}
+
+impl<T, const SINC_LEN: usize, const N_PHASES: usize, const CHANNELS: usize>
+    SincFixedInConst<T, SINC_LEN, N_PHASES, CHANNELS>
+where
+    T: Sample,
+{
+    /// Create a new SincFixedInConst.
+    /// The resampler is built on the stack and returned by value,
+    /// use [new_in_place](SincFixedInConst::new_in_place) to build it directly in its final location.
+    ///
+    /// Parameters are:
+    /// - `resample_ratio`: Ratio between output and input sample rates, must be > 0.
+    /// - `f_cutoff`: Relative cutoff frequency of the sinc interpolation filter,
+    ///   see [SincInterpolationParameters](crate::SincInterpolationParameters).
+    /// - `window`: Window function to use.
+    ///
+    /// Panics if `SINC_LEN` or `N_PHASES` is zero.
+    pub fn new_const(
+        resample_ratio: f64,
+        f_cutoff: f32,
+        window: WindowFunction,
+    ) -> Result<Self, ResamplerConstructionError> {
+        let mut slot = MaybeUninit::uninit();
+        Self::new_in_place(&mut slot, resample_ratio, f_cutoff, window)?;
+        // SAFETY: new_in_place initializes all fields when it returns Ok.
+        Ok(unsafe { slot.assume_init() })
+    }
+
+    /// Create a new SincFixedInConst in the given uninitialized memory,
+    /// for example a `static` or a field of a larger structure.
+    /// The sinc table is calculated directly in place, without allocating
+    /// and without any temporary copy of the resampler on the stack.
+    /// A reference to the initialized resampler is returned.
+    ///
+    /// The parameters are the same as for [new_const](SincFixedInConst::new_const).
+    /// On error, the slot is left uninitialized.
+    ///
+    /// Panics if `SINC_LEN` or `N_PHASES` is zero.
+    pub fn new_in_place(
+        slot: &mut MaybeUninit<Self>,
+        resample_ratio: f64,
+        f_cutoff: f32,
+        window: WindowFunction,
+    ) -> Result<&mut Self, ResamplerConstructionError> {
+        assert!(
+            SINC_LEN > 0 && N_PHASES > 0,
+            "The sinc length and number of phases must be larger than zero"
+        );
+        if resample_ratio <= 0.0 {
+            return Err(ResamplerConstructionError::InvalidRatio(resample_ratio));
+        }
+        debug!(
+            "Create new SincFixedInConst, ratio: {}, sinc_len: {}, phases: {}, channels: {}",
+            resample_ratio, SINC_LEN, N_PHASES, CHANNELS
+        );
+        let f_cutoff = if resample_ratio >= 1.0 {
+            f_cutoff
+        } else {
+            f_cutoff * resample_ratio as f32
+        };
+        let ptr = slot.as_mut_ptr();
+        // SAFETY: All fields are written through raw pointers before the reference is created.
+        // The arrays are contiguous, and all elements are written before any of them is read.
+        // The sample types are Copy, so there are no destructors to skip.
+        unsafe {
+            let sincs = addr_of_mut!((*ptr).sincs) as *mut T;
+            Self::fill_sincs(sincs, f_cutoff, window);
+            let history = addr_of_mut!((*ptr).history) as *mut T;
+            for n in 0..SINC_LEN * CHANNELS {
+                history.add(n).write(T::zero());
+            }
+            addr_of_mut!((*ptr).write_pos).write(0);
+            addr_of_mut!((*ptr).offset).write(1.0);
+            addr_of_mut!((*ptr).step).write(1.0 / resample_ratio);
+            addr_of_mut!((*ptr).resample_ratio).write(resample_ratio);
+            Ok(&mut *ptr)
+        }
+    }
+
+    /// Helper function. Write the `N_PHASES` sincs of `SINC_LEN` points to `sincs`,
+    /// in the same way as [make_sincs](crate::sinc::make_sincs) but without allocating.
+    /// The oversampled sinc is written first, and then normalized in a second pass.
+    ///
+    /// # Safety
+    /// `sincs` must be valid for writing `SINC_LEN * N_PHASES` values.
+    unsafe fn fill_sincs(sincs: *mut T, f_cutoff: f32, window: WindowFunction) {
+        let totpoints = SINC_LEN * N_PHASES;
+        let mut sum = 0.0;
+        for p in 0..SINC_LEN {
+            for n in 0..N_PHASES {
+                let x = N_PHASES * p + n;
+                let value = window_value(totpoints, x, window)
+                    * sinc((x as f64 - (totpoints / 2) as f64) * f_cutoff as f64 / N_PHASES as f64);
+                sum += value;
+                sincs
+                    .add((N_PHASES - n - 1) * SINC_LEN + p)
+                    .write(T::coerce(value));
+            }
+        }
+        let scale = T::coerce(N_PHASES as f64 / sum);
+        for n in 0..totpoints {
+            let value = sincs.add(n);
+            value.write(value.read() * scale);
+        }
+    }
+
+    /// Resample as many input frames as possible into the output.
+    /// All input frames are consumed, unless the output is full.
+    /// Use [output_frames_max](SincFixedInConst::output_frames_max) to get an output size
+    /// that is large enough to always consume the complete input.
+    ///
+    /// The number of input frames consumed and the number of output frames written
+    /// is returned in a tuple, `(input_frames, output_frames)`.
+    pub fn process(
+        &mut self,
+        wave_in: &[[T; CHANNELS]],
+        wave_out: &mut [[T; CHANNELS]],
+    ) -> (usize, usize) {
+        let mut frames_out = 0;
+        let mut frames_in = 0;
+        loop {
+            while frames_out < wave_out.len() && self.next_output_ready() {
+                self.write_output_frame(&mut wave_out[frames_out]);
+                frames_out += 1;
+            }
+            // A pending output frame needs the oldest frame in the history,
+            // which would be overwritten by the next input frame.
+            if self.next_output_ready() || frames_in == wave_in.len() {
+                break;
+            }
+            for (history, value) in self.history.iter_mut().zip(wave_in[frames_in].iter()) {
+                history[self.write_pos] = *value;
+            }
+            self.write_pos = (self.write_pos + 1) % SINC_LEN;
+            self.offset -= 1.0;
+            frames_in += 1;
+        }
+        trace!(
+            "Resampling const, {} frames in, {} frames out",
+            frames_in,
+            frames_out
+        );
+        (frames_in, frames_out)
+    }
+
+    /// Get the maximum number of output frames that a call to
+    /// [process](SincFixedInConst::process) can produce for `input_frames` frames of input.
+    pub fn output_frames_max(&self, input_frames: usize) -> usize {
+        (input_frames as f64 * self.resample_ratio).ceil() as usize + 1
+    }
+
+    /// Get the delay for the resampler, reported as a number of output frames.
+    pub fn output_delay(&self) -> usize {
+        (SINC_LEN as f64 * self.resample_ratio / 2.0) as usize
+    }
+
+    /// Get the resampling ratio.
+    pub fn resample_ratio(&self) -> f64 {
+        self.resample_ratio
+    }
+
+    /// Reset the resampler state and clear the history buffer.
+    pub fn reset(&mut self) {
+        self.history = [[T::zero(); SINC_LEN]; CHANNELS];
+        self.write_pos = 0;
+        self.offset = 1.0;
+    }
+
+    /// Check if all input frames needed for the next output frame are in the history.
+    fn next_output_ready(&self) -> bool {
+        get_nearest_time(self.offset, N_PHASES as isize).0 <= 0
+    }
+
+    /// Calculate the next output frame, and step to the following one.
+    fn write_output_frame(&mut self, frame: &mut [T; CHANNELS]) {
+        let (_, subindex) = get_nearest_time(self.offset, N_PHASES as isize);
+        let sinc = &self.sincs[subindex as usize];
+        // The oldest frame in the history is at the write position.
+        let (sinc_first, sinc_second) = sinc.split_at(SINC_LEN - self.write_pos);
+        for (value, history) in frame.iter_mut().zip(self.history.iter()) {
+            let (history_second, history_first) = history.split_at(self.write_pos);
+            let mut sum = T::zero();
+            for (s, h) in sinc_first.iter().zip(history_first.iter()) {
+                sum += *s * *h;
+            }
+            for (s, h) in sinc_second.iter().zip(history_second.iter()) {
+                sum += *s * *h;
+            }
+            *value = sum;
+        }
+        self.offset += self.step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SincFixedInConst;
+    use crate::sinc::make_sincs;
+    use crate::WindowFunction;
+    use std::mem::MaybeUninit;
+    use test_log::test;
+
+    #[test]
+    fn const_resample_sine() {
+        let mut resampler = SincFixedInConst::<f64, 64, 256, 2>::new_const(
+            1.5,
+            0.9,
+            WindowFunction::BlackmanHarris2,
+        )
+        .unwrap();
+        let input = (0..1000)
+            .map(|n| {
+                let value = (n as f64 * 0.05).sin();
+                [value, -value]
+            })
+            .collect::<Vec<_>>();
+        let mut output = vec![[0.0; 2]; resampler.output_frames_max(input.len())];
+        let (frames_in, frames_out) = resampler.process(&input, &mut output);
+        assert_eq!(frames_in, 1000);
+        assert!((1499..=1501).contains(&frames_out), "{}", frames_out);
+        // Compare with the expected sine, delayed by half the sinc length.
+        for (n, frame) in output[200..frames_out].iter().enumerate() {
+            let time = (n + 200) as f64 / 1.5 - 32.0 + 1.0 / 256.0;
+            let expected = (time * 0.05).sin();
+            assert!(
+                (frame[0] - expected).abs() < 0.01,
+                "{} {}",
+                frame[0],
+                expected
+            );
+            assert_eq!(frame[0], -frame[1]);
+        }
+    }
+
+    #[test]
+    fn const_in_place() {
+        let mut slot = MaybeUninit::uninit();
+        let resampler = SincFixedInConst::<f64, 64, 256, 2>::new_in_place(
+            &mut slot,
+            1.5,
+            0.9,
+            WindowFunction::BlackmanHarris2,
+        )
+        .unwrap();
+        // The table matches the one used by the other sinc resamplers.
+        let expected = make_sincs::<f64>(64, 256, 0.9, WindowFunction::BlackmanHarris2);
+        for (sinc, expected) in resampler.sincs.iter().zip(expected.iter()) {
+            for (value, expected) in sinc.iter().zip(expected.iter()) {
+                assert!((value - expected).abs() < 1.0e-12, "{} {}", value, expected);
+            }
+        }
+        assert_eq!(resampler.resample_ratio(), 1.5);
+        let input = vec![[1.0, -1.0]; 100];
+        let mut output = vec![[0.0; 2]; resampler.output_frames_max(100)];
+        let (frames_in, _) = resampler.process(&input, &mut output);
+        assert_eq!(frames_in, 100);
+
+        let mut slot = MaybeUninit::<SincFixedInConst<f64, 16, 32, 1>>::uninit();
+        assert!(SincFixedInConst::new_in_place(&mut slot, 0.0, 0.9, WindowFunction::Hann).is_err());
+    }
+
+    #[test]
+    fn const_stops_when_output_is_full() {
+        let mut resampler =
+            SincFixedInConst::<f32, 16, 32, 1>::new_const(2.0, 0.9, WindowFunction::Hann).unwrap();
+        let input = vec![[1.0]; 100];
+        let mut output = vec![[0.0]; 50];
+        let (frames_in, frames_out) = resampler.process(&input, &mut output);
+        assert_eq!(frames_in, 26);
+        assert_eq!(frames_out, 50);
+        let (frames_in, frames_out) = resampler.process(&input[26..], &mut output);
+        assert_eq!(frames_in, 25);
+        assert_eq!(frames_out, 50);
+    }
+}
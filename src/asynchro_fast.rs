@@ -1,5 +1,8 @@
-use crate::error::{ResampleError, ResampleResult, ResamplerConstructionError};
-use crate::{update_mask_from_buffers, validate_buffers, Resampler, Sample};
+use crate::error::{ResampleResult, ResamplerConstructionError};
+use crate::{
+    bounded_ratio, count_clipped, stepping_schedule, update_mask_from_buffers, validate_buffers,
+    FilterType, OverflowPolicy, QualityInfo, Resampler, Sample,
+};
 
 const POLYNOMIAL_LEN_U: usize = 8;
 const POLYNOMIAL_LEN_I: isize = 8;
@@ -13,7 +16,7 @@ macro_rules! t {
 
 /// Degree of the polynomial used for interpolation.
 /// A higher degree gives a higher quality result, while taking longer to compute.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PolynomialDegree {
     /// Septic polynomial, fitted using 8 sample points.
     Septic,
@@ -27,6 +30,31 @@ pub enum PolynomialDegree {
     Nearest,
 }
 
+impl PolynomialDegree {
+    /// Attenuation in dB of the first image of a tone at a quarter of the input sample rate,
+    /// calculated from the frequency response of the interpolation polynomials.
+    fn image_attenuation_db(&self) -> f64 {
+        match self {
+            PolynomialDegree::Septic => 42.3,
+            PolynomialDegree::Quintic => 35.2,
+            PolynomialDegree::Cubic => 27.7,
+            PolynomialDegree::Linear => 19.1,
+            PolynomialDegree::Nearest => 9.5,
+        }
+    }
+
+    /// The number of input samples that are weighted together for each output sample.
+    fn nbr_points(&self) -> usize {
+        match self {
+            PolynomialDegree::Septic => 8,
+            PolynomialDegree::Quintic => 6,
+            PolynomialDegree::Cubic => 4,
+            PolynomialDegree::Linear => 2,
+            PolynomialDegree::Nearest => 0,
+        }
+    }
+}
+
 /// An asynchronous resampler that accepts a fixed number of audio frames for input
 /// and returns a variable number of frames.
 ///
@@ -51,6 +79,11 @@ pub struct FastFixedIn<T> {
     buffer: Vec<Vec<T>>,
     interpolation: PolynomialDegree,
     channel_mask: Vec<bool>,
+    max_block_frames: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    clip_ceiling: Option<T>,
+    clipped_samples: usize,
+    flush_remaining: Option<usize>,
 }
 
 /// An asynchronous resampler that returns a fixed number of audio frames.
@@ -81,6 +114,11 @@ pub struct FastFixedOut<T> {
     buffer: Vec<Vec<T>>,
     interpolation: PolynomialDegree,
     channel_mask: Vec<bool>,
+    max_block_frames: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    clip_ceiling: Option<T>,
+    clipped_samples: usize,
+    flush_remaining: Option<usize>,
 }
 
 /// Perform septic polynomial interpolation to get value at x.
@@ -237,6 +275,11 @@ where
             buffer,
             interpolation: interpolation_type,
             channel_mask,
+            max_block_frames: None,
+            overflow_policy: OverflowPolicy::Error,
+            clip_ceiling: None,
+            clipped_samples: 0,
+            flush_remaining: None,
         })
     }
 }
@@ -251,6 +294,7 @@ where
         wave_out: &mut [Vout],
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<(usize, usize)> {
+        self.flush_remaining = None;
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -269,6 +313,7 @@ where
             self.nbr_channels,
             self.chunk_size,
             needed_len,
+            self.max_block_frames,
         )?;
 
         // Update buffer with new data.
@@ -437,6 +482,7 @@ where
             self.chunk_size,
             n,
         );
+        self.clipped_samples += count_clipped(wave_out, &self.channel_mask, n, self.clip_ceiling);
         Ok((self.chunk_size, n))
     }
 
@@ -469,21 +515,17 @@ where
 
     fn set_resample_ratio(&mut self, new_ratio: f64, ramp: bool) -> ResampleResult<()> {
         trace!("Change resample ratio to {}", new_ratio);
-        if (new_ratio / self.resample_ratio_original >= 1.0 / self.max_relative_ratio)
-            && (new_ratio / self.resample_ratio_original <= self.max_relative_ratio)
-        {
-            if !ramp {
-                self.resample_ratio = new_ratio;
-            }
-            self.target_ratio = new_ratio;
-            Ok(())
-        } else {
-            Err(ResampleError::RatioOutOfBounds {
-                provided: new_ratio,
-                original: self.resample_ratio_original,
-                max_relative_ratio: self.max_relative_ratio,
-            })
+        let new_ratio = bounded_ratio(
+            new_ratio,
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+            self.overflow_policy,
+        )?;
+        if !ramp {
+            self.resample_ratio = new_ratio;
         }
+        self.target_ratio = new_ratio;
+        Ok(())
     }
 
     fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()> {
@@ -491,7 +533,61 @@ where
         self.set_resample_ratio(new_ratio, ramp)
     }
 
+    fn target_ratio(&self) -> f64 {
+        self.target_ratio
+    }
+
+    fn flush_remaining(&self) -> Option<usize> {
+        self.flush_remaining
+    }
+
+    fn set_flush_remaining(&mut self, remaining: Option<usize>) {
+        self.flush_remaining = remaining;
+    }
+
+    fn output_schedule(&self, nblocks: usize) -> Vec<usize> {
+        stepping_schedule(
+            self.last_index,
+            self.resample_ratio,
+            self.target_ratio,
+            self.chunk_size,
+            POLYNOMIAL_LEN_U,
+            nblocks,
+        )
+    }
+
+    fn quality_info(&self) -> QualityInfo {
+        QualityInfo {
+            filter: FilterType::Polynomial(self.interpolation),
+            attenuation_db: Some(self.interpolation.image_attenuation_db()),
+            transition_bandwidth: Some(1.0),
+            latency: self.output_delay(),
+        }
+    }
+
+    fn estimated_mac_per_block(&self) -> u64 {
+        (self.interpolation.nbr_points() * self.output_frames_max() * self.nbr_channels) as u64
+    }
+
+    fn set_ratio_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    fn set_max_block_frames(&mut self, max_frames: Option<usize>) {
+        self.max_block_frames = max_frames;
+    }
+
+    fn set_clip_ceiling(&mut self, ceiling: Option<T>) {
+        self.clip_ceiling = ceiling;
+    }
+
+    fn clipped_sample_count(&self) -> usize {
+        self.clipped_samples
+    }
+
     fn reset(&mut self) {
+        self.clipped_samples = 0;
+        self.flush_remaining = None;
         self.buffer
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
@@ -548,6 +644,11 @@ where
             buffer,
             interpolation: interpolation_type,
             channel_mask,
+            max_block_frames: None,
+            overflow_policy: OverflowPolicy::Error,
+            clip_ceiling: None,
+            clipped_samples: 0,
+            flush_remaining: None,
         })
     }
 }
@@ -562,6 +663,7 @@ where
         wave_out: &mut [Vout],
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<(usize, usize)> {
+        self.flush_remaining = None;
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -575,6 +677,7 @@ where
             self.nbr_channels,
             self.needed_input_size,
             self.chunk_size,
+            self.max_block_frames,
         )?;
         for buf in self.buffer.iter_mut() {
             buf.copy_within(
@@ -735,6 +838,12 @@ where
             self.needed_input_size,
             self.last_index
         );
+        self.clipped_samples += count_clipped(
+            wave_out,
+            &self.channel_mask,
+            self.chunk_size,
+            self.clip_ceiling,
+        );
         Ok((input_frames_used, self.chunk_size))
     }
 
@@ -767,26 +876,22 @@ where
 
     fn set_resample_ratio(&mut self, new_ratio: f64, ramp: bool) -> ResampleResult<()> {
         trace!("Change resample ratio to {}", new_ratio);
-        if (new_ratio / self.resample_ratio_original >= 1.0 / self.max_relative_ratio)
-            && (new_ratio / self.resample_ratio_original <= self.max_relative_ratio)
-        {
-            if !ramp {
-                self.resample_ratio = new_ratio;
-            }
-            self.target_ratio = new_ratio;
-            self.needed_input_size = (self.last_index as f32
-                + self.chunk_size as f32
-                    / (0.5 * self.resample_ratio as f32 + 0.5 * self.target_ratio as f32))
-                .ceil() as usize
-                + POLYNOMIAL_LEN_U;
-            Ok(())
-        } else {
-            Err(ResampleError::RatioOutOfBounds {
-                provided: new_ratio,
-                original: self.resample_ratio_original,
-                max_relative_ratio: self.max_relative_ratio,
-            })
+        let new_ratio = bounded_ratio(
+            new_ratio,
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+            self.overflow_policy,
+        )?;
+        if !ramp {
+            self.resample_ratio = new_ratio;
         }
+        self.target_ratio = new_ratio;
+        self.needed_input_size = (self.last_index as f32
+            + self.chunk_size as f32
+                / (0.5 * self.resample_ratio as f32 + 0.5 * self.target_ratio as f32))
+            .ceil() as usize
+            + POLYNOMIAL_LEN_U;
+        Ok(())
     }
 
     fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()> {
@@ -794,7 +899,50 @@ where
         self.set_resample_ratio(new_ratio, ramp)
     }
 
+    fn target_ratio(&self) -> f64 {
+        self.target_ratio
+    }
+
+    fn flush_remaining(&self) -> Option<usize> {
+        self.flush_remaining
+    }
+
+    fn set_flush_remaining(&mut self, remaining: Option<usize>) {
+        self.flush_remaining = remaining;
+    }
+
+    fn quality_info(&self) -> QualityInfo {
+        QualityInfo {
+            filter: FilterType::Polynomial(self.interpolation),
+            attenuation_db: Some(self.interpolation.image_attenuation_db()),
+            transition_bandwidth: Some(1.0),
+            latency: self.output_delay(),
+        }
+    }
+
+    fn estimated_mac_per_block(&self) -> u64 {
+        (self.interpolation.nbr_points() * self.output_frames_max() * self.nbr_channels) as u64
+    }
+
+    fn set_ratio_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    fn set_max_block_frames(&mut self, max_frames: Option<usize>) {
+        self.max_block_frames = max_frames;
+    }
+
+    fn set_clip_ceiling(&mut self, ceiling: Option<T>) {
+        self.clip_ceiling = ceiling;
+    }
+
+    fn clipped_sample_count(&self) -> usize {
+        self.clipped_samples
+    }
+
     fn reset(&mut self) {
+        self.clipped_samples = 0;
+        self.flush_remaining = None;
         self.buffer
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
@@ -811,11 +959,16 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::ChannelLabel;
+    use crate::OverflowPolicy;
     use crate::PolynomialDegree;
+    use crate::ResampleError;
     use crate::Resampler;
+    use crate::StereoFormat;
     use crate::{check_output, check_ratio};
     use crate::{FastFixedIn, FastFixedOut};
     use rand::Rng;
+    use std::collections::HashMap;
     use test_log::test;
 
     #[test]
@@ -1228,4 +1381,349 @@ mod tests {
             FastFixedIn::<f32>::new(ratio, 100.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
         check_ratio!(resampler, ratio, 1000);
     }
+
+    #[test]
+    fn max_block_frames_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let waves = vec![vec![0.0f64; 1024]; 2];
+        resampler.set_max_block_frames(Some(1024));
+        assert!(matches!(
+            resampler.process(&waves, None),
+            Err(ResampleError::BlockTooLarge { max: 1024, .. })
+        ));
+        resampler.set_max_block_frames(Some(2048));
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2);
+        resampler.set_max_block_frames(None);
+        assert!(resampler.process(&waves, None).is_ok());
+    }
+
+    #[test]
+    fn clipped_sample_count_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let quiet = vec![
+            (0..1024)
+                .map(|n| 0.5 * (n as f64 * 0.1).sin())
+                .collect::<Vec<_>>();
+            2
+        ];
+        let hot = vec![
+            (0..1024)
+                .map(|n| 1.5 * (n as f64 * 0.1).sin())
+                .collect::<Vec<_>>();
+            2
+        ];
+        resampler.set_clip_ceiling(Some(1.0));
+        resampler.process(&quiet, None).unwrap();
+        resampler.process(&quiet, None).unwrap();
+        assert_eq!(resampler.clipped_sample_count(), 0);
+
+        resampler.process(&hot, None).unwrap();
+        let clipped = resampler.clipped_sample_count();
+        assert!(clipped > 0);
+
+        resampler.set_clip_ceiling(Some(2.0));
+        resampler.process(&hot, None).unwrap();
+        assert_eq!(resampler.clipped_sample_count(), clipped);
+
+        // Without a ceiling, the output is not scanned.
+        resampler.set_clip_ceiling(None);
+        resampler.process(&hot, None).unwrap();
+        assert_eq!(resampler.clipped_sample_count(), clipped);
+
+        resampler.reset();
+        assert_eq!(resampler.clipped_sample_count(), 0);
+    }
+
+    // Run with and without the `smallvec` feature, which changes the container for the channels
+    // of each chunk. Three channels don't fit inline and use the heap also with the feature.
+    #[test]
+    fn process_chunked_channel_counts_fi() {
+        for channels in 1..=3 {
+            let mut resampler =
+                FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 256, channels).unwrap();
+            let waves = (0..channels)
+                .map(|chan| {
+                    (0..1000)
+                        .map(|n| (0.01 * (chan + 1) as f64 * n as f64).sin())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let output = resampler.process_chunked(&waves, None).unwrap();
+
+            resampler.reset();
+            let mut expected = vec![Vec::new(); channels];
+            for chunk in 0..4 {
+                let start = 256 * chunk;
+                let chunk_in = waves
+                    .iter()
+                    .map(|wave| wave[start..(start + 256).min(1000)].to_vec())
+                    .collect::<Vec<_>>();
+                let chunk_out = if chunk < 3 {
+                    resampler.process(&chunk_in, None).unwrap()
+                } else {
+                    resampler.process_partial(Some(&chunk_in), None).unwrap()
+                };
+                for (wave, chunk) in expected.iter_mut().zip(chunk_out.iter()) {
+                    wave.extend_from_slice(chunk);
+                }
+            }
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn process_strided_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let mut reference =
+            FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let in_stride = 1100;
+        let out_stride = 1700;
+        let mut rng = rand::thread_rng();
+        for _ in 0..3 {
+            let waves = (0..2)
+                .map(|_| (0..1024).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let mut strided_in = vec![0.0; 2 * in_stride];
+            for (chan, wave) in waves.iter().enumerate() {
+                strided_in[chan * in_stride..chan * in_stride + 1024].copy_from_slice(wave);
+            }
+            let mut strided_out = vec![0.0; 2 * out_stride];
+            let (frames_in, frames_out) = resampler
+                .process_strided(&strided_in, in_stride, &mut strided_out, out_stride, 2)
+                .unwrap();
+            let expected = reference.process(&waves, None).unwrap();
+            assert_eq!(frames_in, 1024);
+            assert_eq!(frames_out, expected[0].len());
+            for (chan, wave) in expected.iter().enumerate() {
+                assert_eq!(
+                    &strided_out[chan * out_stride..chan * out_stride + frames_out],
+                    &wave[..]
+                );
+            }
+        }
+        let mut short_out = vec![0.0; 2 * 100];
+        assert!(matches!(
+            resampler.process_strided(&vec![0.0; 2 * in_stride], in_stride, &mut short_out, 100, 2),
+            Err(ResampleError::InsufficientOutputBufferSize { .. })
+        ));
+    }
+
+    #[test]
+    fn process_labelled_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 3).unwrap();
+        let omni = vec![0.5; 1024];
+        let front = (0..1024)
+            .map(|n| (n as f64 * 0.1).sin())
+            .collect::<Vec<_>>();
+        let side = vec![-0.25; 1024];
+        let mut wave_in: HashMap<ChannelLabel, &[f64]> = HashMap::new();
+        wave_in.insert(ChannelLabel::Y, &side);
+        wave_in.insert(ChannelLabel::W, &omni);
+        wave_in.insert(ChannelLabel::X, &front);
+        let mut wave_out = HashMap::new();
+        for _ in 0..2 {
+            wave_out = resampler.process_labelled(&wave_in).unwrap();
+        }
+        assert_eq!(wave_out.len(), 3);
+        assert!(wave_out[&ChannelLabel::W]
+            .iter()
+            .all(|v| (v - 0.5).abs() < 1.0e-9));
+        assert!(wave_out[&ChannelLabel::Y]
+            .iter()
+            .all(|v| (v + 0.25).abs() < 1.0e-9));
+        assert!(wave_out[&ChannelLabel::X].iter().any(|v| v.abs() > 0.9));
+
+        wave_in.remove(&ChannelLabel::Y);
+        assert!(matches!(
+            resampler.process_labelled(&wave_in),
+            Err(ResampleError::WrongNumberOfInputChannels {
+                expected: 3,
+                actual: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn process_with_envelope_fo() {
+        let mut resampler =
+            FastFixedOut::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let mut reference =
+            FastFixedOut::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let envelope = (0..1024).map(|n| n as f64 / 1024.0).collect::<Vec<_>>();
+        let mut rng = rand::thread_rng();
+        for _ in 0..3 {
+            let frames = resampler.input_frames_next();
+            let waves = (0..2)
+                .map(|_| (0..frames).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let mut wave_out = resampler.output_buffer_allocate(true);
+            let (_, frames_out) = resampler
+                .process_with_envelope(&waves, &mut wave_out, &envelope, None)
+                .unwrap();
+            let expected = reference.process(&waves, None).unwrap();
+            assert_eq!(frames_out, 1024);
+            for (wave, expected_wave) in wave_out.iter().zip(expected.iter()) {
+                for (n, (value, expected_value)) in
+                    wave.iter().zip(expected_wave.iter()).enumerate()
+                {
+                    assert_eq!(*value, expected_value * envelope[n]);
+                }
+            }
+        }
+        let mut wave_out = resampler.output_buffer_allocate(true);
+        let waves = resampler.input_buffer_allocate(true);
+        assert!(matches!(
+            resampler.process_with_envelope(&waves, &mut wave_out, &envelope[..100], None),
+            Err(ResampleError::InsufficientEnvelopeLength {
+                expected: 1024,
+                actual: 100
+            })
+        ));
+    }
+
+    #[test]
+    fn process_into_subblocks_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let mut reference =
+            FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        let waves = (0..2)
+            .map(|_| (0..1024).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let subblocks = resampler.process_into_subblocks(&waves, 100).unwrap();
+        let expected = reference.process(&waves, None).unwrap();
+        assert_eq!(subblocks.len(), (expected[0].len() + 99) / 100);
+        for subblock in subblocks[..subblocks.len() - 1].iter() {
+            assert_eq!(subblock.len(), 2);
+            assert_eq!(subblock[0].len(), 100);
+        }
+        for (chan, wave) in expected.iter().enumerate() {
+            let joined = subblocks
+                .iter()
+                .flat_map(|subblock| subblock[chan].iter().copied())
+                .collect::<Vec<_>>();
+            assert_eq!(&joined, wave);
+        }
+    }
+
+    #[test]
+    fn is_rational_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(48000.0 / 44100.0, 2.0, PolynomialDegree::Cubic, 1024, 2)
+                .unwrap();
+        assert_eq!(resampler.is_rational(), Some((160, 147)));
+        resampler.set_resample_ratio(2.0f64.sqrt(), false).unwrap();
+        assert_eq!(resampler.is_rational(), None);
+        resampler.set_resample_ratio(0.625, true).unwrap();
+        assert_eq!(resampler.target_ratio(), 0.625);
+        assert_eq!(resampler.is_rational(), Some((5, 8)));
+    }
+
+    #[test]
+    fn ratio_overflow_policy() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.0, 2.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        assert!(matches!(
+            resampler.set_resample_ratio(3.0, false),
+            Err(ResampleError::RatioOutOfBounds { .. })
+        ));
+        assert_eq!(resampler.is_rational(), Some((1, 1)));
+        resampler.set_ratio_overflow_policy(OverflowPolicy::Clamp);
+        resampler.set_resample_ratio(3.0, false).unwrap();
+        assert_eq!(resampler.is_rational(), Some((2, 1)));
+        resampler.set_resample_ratio_relative(0.1, false).unwrap();
+        assert_eq!(resampler.is_rational(), Some((1, 2)));
+
+        let mut resampler =
+            FastFixedOut::<f64>::new(1.0, 2.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let mut reference =
+            FastFixedOut::<f64>::new(1.0, 2.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        reference.set_resample_ratio(0.5, false).unwrap();
+        resampler.set_ratio_overflow_policy(OverflowPolicy::Clamp);
+        resampler.set_resample_ratio(0.25, false).unwrap();
+        assert_eq!(resampler.input_frames_next(), reference.input_frames_next());
+        resampler.set_ratio_overflow_policy(OverflowPolicy::Error);
+        assert!(resampler.set_resample_ratio(0.25, false).is_err());
+    }
+
+    #[test]
+    fn process_block_with_feedback_fi() {
+        let waves = vec![vec![0.0f64; 1024]; 2];
+        let mut frames_out = Vec::new();
+        for fill in [0, 1000, 2000] {
+            let mut resampler =
+                FastFixedIn::<f64>::new(1.0, 1.01, PolynomialDegree::Cubic, 1024, 2).unwrap();
+            let mut total = 0;
+            for _ in 0..100 {
+                let out = resampler
+                    .process_block_with_feedback(&waves, fill, 1000, 1000.0)
+                    .unwrap();
+                total += out[0].len();
+            }
+            frames_out.push(total);
+        }
+        // A correction of 1000 ppm gives about 102 frames more or less over 102400 frames.
+        assert!((100..=104).contains(&(frames_out[0] - frames_out[1])));
+        assert!((100..=104).contains(&(frames_out[1] - frames_out[2])));
+    }
+
+    #[test]
+    fn achieved_output_rate_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(48000.0 / 44100.0, 2.0, PolynomialDegree::Cubic, 1024, 2)
+                .unwrap();
+        assert_eq!(
+            resampler.achieved_output_rate(44100.0),
+            44100.0 * 160.0 / 147.0
+        );
+        assert_eq!(resampler.achieved_output_rate(44100.0), 48000.0);
+        resampler.set_resample_ratio(2.0f64.sqrt(), false).unwrap();
+        assert_eq!(
+            resampler.achieved_output_rate(44100.0),
+            44100.0 * 2.0f64.sqrt()
+        );
+    }
+
+    #[test]
+    fn process_mid_side_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.2, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let mut resampler_ms =
+            FastFixedIn::<f64>::new(1.2, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let mut reference =
+            FastFixedIn::<f64>::new(1.2, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        for _ in 0..3 {
+            let waves = (0..2)
+                .map(|_| (0..1024).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let expected = reference.process(&waves, None).unwrap();
+            let output = resampler
+                .process_mid_side(&waves, StereoFormat::LeftRight)
+                .unwrap();
+            let output_ms = resampler_ms
+                .process_mid_side(&waves, StereoFormat::MidSide)
+                .unwrap();
+            assert_eq!(output[0].len(), expected[0].len());
+            for n in 0..expected[0].len() {
+                let (left, right) = (expected[0][n], expected[1][n]);
+                assert!((output[0][n] - left).abs() < 1.0e-12);
+                assert!((output[1][n] - right).abs() < 1.0e-12);
+                assert!((output_ms[0][n] - 0.5 * (left + right)).abs() < 1.0e-12);
+                assert!((output_ms[1][n] - 0.5 * (left - right)).abs() < 1.0e-12);
+            }
+        }
+        let mut mono = FastFixedIn::<f64>::new(1.2, 1.0, PolynomialDegree::Cubic, 1024, 1).unwrap();
+        let waves = vec![vec![0.0; 1024]; 1];
+        assert!(matches!(
+            mono.process_mid_side(&waves, StereoFormat::LeftRight),
+            Err(ResampleError::WrongNumberOfInputChannels { .. })
+        ));
+    }
 }
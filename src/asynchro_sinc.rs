@@ -1,5 +1,13 @@
+use std::sync::Arc;
+
+use crate::bark::{BarkSincConfig, BarkSincTable};
 use crate::error::{ResampleError, ResampleResult, ResamplerConstructionError};
+#[cfg(feature = "fft_resampler")]
+use crate::fft::SpectrumAnalyzer;
 use crate::interpolation::*;
+use crate::metrics::ResamplerMetrics;
+#[cfg(feature = "profiling")]
+use crate::profiling::{LatencyHistogram, ProfilingReport};
 #[cfg(target_arch = "x86_64")]
 use crate::sinc_interpolator::sinc_interpolator_avx::AvxInterpolator;
 #[cfg(target_arch = "aarch64")]
@@ -7,11 +15,15 @@ use crate::sinc_interpolator::sinc_interpolator_neon::NeonInterpolator;
 #[cfg(target_arch = "x86_64")]
 use crate::sinc_interpolator::sinc_interpolator_sse::SseInterpolator;
 use crate::sinc_interpolator::{ScalarInterpolator, SincInterpolator};
-use crate::windows::WindowFunction;
-use crate::{update_mask_from_buffers, validate_buffers, Resampler, Sample};
+use crate::windows::{approximate_attenuation, WindowFunction};
+use crate::{
+    bounded_ratio, calculate_cutoff, count_clipped, flush_delayed_frames, stepping_schedule,
+    update_mask_from_buffers, validate_buffers, FilterType, OverflowPolicy, QualityInfo, Resampler,
+    Sample,
+};
 
 /// A struct holding the parameters for sinc interpolation.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SincInterpolationParameters {
     /// Length of the windowed sinc interpolation filter.
     /// Higher values can allow a higher cut-off frequency leading to less high frequency roll-off
@@ -33,6 +45,136 @@ pub struct SincInterpolationParameters {
     pub window: WindowFunction,
 }
 
+/// The longest sinc considered by [SincInterpolationParameters::from_band_edges].
+pub(crate) const MAX_BAND_EDGE_SINC_LEN: usize = 2048;
+
+/// The smallest level of a transient, see [SincFixedIn::set_transient_mode].
+const TRANSIENT_MIN_LEVEL: f64 = 0.001;
+
+/// The factor by which a transient must exceed the average level of the preceding frames.
+const TRANSIENT_LEVEL_RATIO: f64 = 4.0;
+
+impl SincInterpolationParameters {
+    /// Design the parameters from the edges of the passband and the stopband,
+    /// instead of from a single cutoff frequency.
+    /// The edges are given relative to the lowest one of fs_in/2 or fs_out/2,
+    /// in the same way as `f_cutoff`.
+    ///
+    /// The filter uses the [WindowFunction::BlackmanHarris2] window,
+    /// which has the highest attenuation, with the cutoff in the middle of the transition band.
+    /// The shortest sinc length that is a multiple of 8 and gives a transition band
+    /// no wider than the requested one is then selected,
+    /// using the same fit of the window main lobe width as [calculate_cutoff](crate::calculate_cutoff).
+    /// The stopband starts at the first null of the response,
+    /// and the response in the passband is within the same small deviation from unity.
+    ///
+    /// Returns [ResamplerConstructionError::InvalidBandEdges] unless `0 < passband_edge < stopband_edge <= 1`,
+    /// or if the transition band is too narrow to be reached with a sinc length of at most 2048.
+    pub fn from_band_edges(
+        passband_edge: f32,
+        stopband_edge: f32,
+        interpolation: SincInterpolationType,
+        oversampling_factor: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        let error = ResamplerConstructionError::InvalidBandEdges {
+            passband: passband_edge,
+            stopband: stopband_edge,
+        };
+        if !(passband_edge > 0.0 && passband_edge < stopband_edge && stopband_edge <= 1.0) {
+            return Err(error);
+        }
+        let window = WindowFunction::BlackmanHarris2;
+        // The cutoff from calculate_cutoff places the first null at 1.0,
+        // so the distance from the cutoff to the first null is one minus the cutoff.
+        let half_width = (stopband_edge - passband_edge) / 2.0;
+        let sinc_len = (32..=MAX_BAND_EDGE_SINC_LEN)
+            .step_by(8)
+            .find(|len| 1.0 - calculate_cutoff::<f32>(*len, window) <= half_width)
+            .ok_or(error)?;
+        Ok(SincInterpolationParameters {
+            sinc_len,
+            f_cutoff: (passband_edge + stopband_edge) / 2.0,
+            oversampling_factor,
+            interpolation,
+            window,
+        })
+    }
+
+    /// Estimate the signal-to-noise ratio in dB that a [SincFixedIn] with these parameters
+    /// gives at the given ratio, for comparing settings offline.
+    ///
+    /// This runs a measurement with a set of sine tones spread over the passband,
+    /// from 10% to 70% of the cutoff frequency relative to the lower Nyquist frequency.
+    /// Each tone is resampled, and a sine at the expected output frequency is fitted
+    /// to the output after the start-up transient. Anything that differs from the fitted sine,
+    /// such as images and interpolation errors, is counted as noise,
+    /// while gain and phase errors are not.
+    /// When downsampling, tones between the output and input Nyquist frequencies are also
+    /// resampled, and all their output is counted as aliasing noise.
+    /// The result is the ratio of the total signal to the total noise.
+    ///
+    /// This creates a resampler and processes a few hundred thousand frames,
+    /// and is meant to be run offline. Panics if the parameters or the ratio are not valid.
+    pub fn estimated_snr_db(&self, ratio: f64) -> f64 {
+        const CHUNK_SIZE: usize = 1024;
+        const NBR_CHUNKS: usize = 16;
+        let mut resampler = SincFixedIn::<f64>::new(ratio, 1.0, self.clone(), CHUNK_SIZE, 1)
+            .expect("The parameters and ratio must be valid");
+        // Frequencies in cycles per input frame.
+        let nyquist = 0.5 * ratio.min(1.0);
+        let passband_tones = [0.1, 0.25, 0.4, 0.55, 0.7]
+            .iter()
+            .map(|fraction| (fraction * self.f_cutoff as f64 * nyquist, false));
+        let alias_tones = [0.3, 0.6, 0.9]
+            .iter()
+            .filter(|_| ratio < 1.0)
+            .map(|fraction| (nyquist + fraction * (0.5 - nyquist), true));
+        let mut total_signal = 0.0;
+        let mut total_noise = 0.0;
+        for (freq, aliased) in passband_tones.chain(alias_tones) {
+            resampler.reset();
+            let omega_in = 2.0 * std::f64::consts::PI * freq;
+            let mut output: Vec<f64> = Vec::new();
+            for chunk in 0..NBR_CHUNKS {
+                let wave = (chunk * CHUNK_SIZE..(chunk + 1) * CHUNK_SIZE)
+                    .map(|n| (omega_in * n as f64).sin())
+                    .collect::<Vec<_>>();
+                output.extend(resampler.process(&[wave], None).expect("Valid buffers")[0].iter());
+            }
+            // Skip the start-up transient.
+            let skip = (2 * self.sinc_len) as f64 * ratio + CHUNK_SIZE as f64 * ratio;
+            let segment = &output[skip.ceil() as usize..];
+            if aliased {
+                // The tone should be removed, and all output is aliasing.
+                total_signal += 0.5 * segment.len() as f64;
+                total_noise += segment.iter().map(|value| value * value).sum::<f64>();
+                continue;
+            }
+            // Fit a sine at the output frequency by least squares.
+            let omega_out = omega_in / ratio;
+            let (mut ss, mut sc, mut cc, mut ys, mut yc) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for (n, value) in segment.iter().enumerate() {
+                let (sin, cos) = (omega_out * n as f64).sin_cos();
+                ss += sin * sin;
+                sc += sin * cos;
+                cc += cos * cos;
+                ys += value * sin;
+                yc += value * cos;
+            }
+            let det = ss * cc - sc * sc;
+            let a = (ys * cc - yc * sc) / det;
+            let b = (yc * ss - ys * sc) / det;
+            for (n, value) in segment.iter().enumerate() {
+                let (sin, cos) = (omega_out * n as f64).sin_cos();
+                let fitted = a * sin + b * cos;
+                total_signal += fitted * fitted;
+                total_noise += (value - fitted).powi(2);
+            }
+        }
+        10.0 * (total_signal / total_noise).log10()
+    }
+}
+
 /// Interpolation methods that can be selected. For asynchronous interpolation where the
 /// ratio between input and output sample rates can be any number, it's not possible to
 /// pre-calculate all the needed interpolation filters.
@@ -41,7 +183,7 @@ pub struct SincInterpolationParameters {
 /// It's more efficient to combine the sinc filters with some other interpolation technique.
 /// Then, sinc filters are used to provide a fixed number of interpolated points between input samples,
 /// and then, the new value is calculated by interpolation between those points.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SincInterpolationType {
     /// For cubic interpolation, the four nearest intermediate points are calculated
     /// using sinc interpolation.
@@ -71,6 +213,85 @@ pub enum SincInterpolationType {
     Nearest,
 }
 
+/// Statistics from [SincFixedIn::process_with_soft_clip], with one value per channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftClipStats<T> {
+    /// The largest absolute sample value of each channel, before clipping.
+    pub peaks: Vec<T>,
+    /// The number of samples of each channel that were modified by the clipper.
+    pub clip_events: Vec<usize>,
+}
+
+/// Signal-to-noise ratios from [SincFixedIn::process_with_reference],
+/// measuring the difference between the output and the resampled reference.
+/// The ratios are given in dB, and are infinite when the two signals are identical.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityMetrics {
+    /// The signal-to-noise ratio of each channel.
+    pub channel_snr_db: Vec<f64>,
+    /// The signal-to-noise ratio of all channels together.
+    pub snr_db: f64,
+}
+
+/// The state saved by [SincFixedIn::checkpoint],
+/// for resuming processing with [SincFixedIn::reconstruct_from_phase].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SincCheckpoint<T> {
+    /// The position of the next output frame relative to the delay line.
+    pub phase: f64,
+    /// The current resampling ratio.
+    pub resample_ratio: f64,
+    /// The resampling ratio that the current ratio is ramping towards.
+    /// This equals `resample_ratio` when no ramp is in progress.
+    pub target_ratio: f64,
+    /// The last `2 * sinc_len` input frames for each channel.
+    pub delay_line: Vec<Vec<T>>,
+}
+
+/// The interpolation of one output frame, from [SincFixedIn::process_with_interp_debug].
+/// The output frame is the dot product of the coefficients with the input frames
+/// starting at `phase_int - 1`, where the frames are counted from the start of the processed chunk.
+/// Negative frames are the last frames of the previous chunks, or zeros at the start of the stream.
+/// Only available with the `debug-interp` feature.
+#[cfg(feature = "debug-interp")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterpolationDebugFrame<T> {
+    /// The integer part of the interpolation position, in input frames.
+    pub phase_int: isize,
+    /// The fractional part of the interpolation position, between 0 and 1.
+    pub phase_frac: T,
+    /// The effective filter coefficients, with `sinc_len + 2` values.
+    /// These combine the sinc tables with the weights of the interpolation between them,
+    /// and coefficient `k` is applied to input frame `phase_int - 1 + k`.
+    pub phase_coefficients: Vec<T>,
+    /// The output value of the first channel.
+    pub output_sample: T,
+}
+
+/// Helper function. Soft-knee clipper with a cubic saturation curve.
+/// Values up to half the threshold pass unchanged. Above that, the cubic curve
+/// `1.5 * x - 0.5 * x^3` is used to approach the threshold with a continuous slope,
+/// and values above 1.25 times the threshold are limited to the threshold.
+/// Returns the clipped value, and a flag telling if the value was modified.
+fn soft_clip<T: Sample>(value: T, threshold: T) -> (T, bool) {
+    let half = T::coerce(0.5);
+    let knee = threshold * half;
+    let magnitude = value.abs();
+    if magnitude.to_f64() <= knee.to_f64() {
+        return (value, false);
+    }
+    // The cubic goes from slope 1 at the knee, to slope 0 at the threshold.
+    let width = T::coerce(1.5) * (threshold - knee);
+    // The curve reaches the threshold at s = 1, and stays there for larger values.
+    let s = ((magnitude - knee) / width).min(T::one());
+    let clipped = knee + (threshold - knee) * (T::coerce(1.5) * s - half * s.powi(3));
+    if value.to_f64() < 0.0 {
+        (-clipped, true)
+    } else {
+        (clipped, true)
+    }
+}
+
 /// An asynchronous resampler that accepts a fixed number of audio frames for input
 /// and returns a variable number of frames.
 /// The number of input frames is determined by the chunk size argument to the constructor.
@@ -89,6 +310,9 @@ pub enum SincInterpolationType {
 /// However, when decreasing more than a few percent (or speeding up the output),
 /// the filters can no longer suppress all aliasing and this may lead to some artefacts.
 /// Higher maximum ratios require more memory to be allocated by [Resampler::output_buffer_allocate].
+/// Changing the ratio never changes the number of input frames,
+/// [input_frames_next](Resampler::input_frames_next) always returns the current chunk size.
+/// Only the number of output frames varies.
 pub struct SincFixedIn<T> {
     nbr_channels: usize,
     chunk_size: usize,
@@ -98,10 +322,34 @@ pub struct SincFixedIn<T> {
     resample_ratio_original: f64,
     target_ratio: f64,
     max_relative_ratio: f64,
-    interpolator: Box<dyn SincInterpolator<T>>,
+    interpolator: Arc<dyn SincInterpolator<T>>,
     buffer: Vec<Vec<T>>,
+    sidechain_buffer: Vec<T>,
     interpolation: SincInterpolationType,
+    window: Option<WindowFunction>,
     channel_mask: Vec<bool>,
+    max_block_frames: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    clip_ceiling: Option<T>,
+    clipped_samples: usize,
+    flush_remaining: Option<usize>,
+    discard_tail: bool,
+    discarded_frames: usize,
+    mute_gains: Vec<T>,
+    transient_interpolator: Option<Arc<dyn SincInterpolator<T>>>,
+    transient_onsets: Vec<usize>,
+    at_stream_start: bool,
+    metrics: ResamplerMetrics,
+    reference_resampler: Option<Box<SincFixedIn<T>>>,
+    reference_in_step: bool,
+    #[cfg(any(feature = "profiling", feature = "prometheus"))]
+    chunk_start: std::time::Instant,
+    #[cfg(feature = "fft_resampler")]
+    spectrum_analyzer: Option<SpectrumAnalyzer<T>>,
+    #[cfg(feature = "fft_resampler")]
+    spectra: Vec<Vec<f32>>,
+    #[cfg(feature = "profiling")]
+    profiler: LatencyHistogram,
 }
 
 /// An asynchronous resampler that returns a fixed number of audio frames.
@@ -136,10 +384,16 @@ pub struct SincFixedOut<T> {
     resample_ratio_original: f64,
     target_ratio: f64,
     max_relative_ratio: f64,
-    interpolator: Box<dyn SincInterpolator<T>>,
+    interpolator: Arc<dyn SincInterpolator<T>>,
     buffer: Vec<Vec<T>>,
     interpolation: SincInterpolationType,
+    window: Option<WindowFunction>,
     channel_mask: Vec<bool>,
+    max_block_frames: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    clip_ceiling: Option<T>,
+    clipped_samples: usize,
+    flush_remaining: Option<usize>,
 }
 
 pub fn make_interpolator<T>(
@@ -188,6 +442,42 @@ where
     ))
 }
 
+/// Helper for estimating the number of multiply-accumulate operations for a block.
+/// Each interpolated point is a dot product of the sinc with the input,
+/// and the points are combined by a polynomial that is cheap in comparison.
+fn sinc_mac_per_block(
+    sinc_len: usize,
+    interpolation: &SincInterpolationType,
+    output_frames: usize,
+    nbr_channels: usize,
+) -> u64 {
+    let points = match interpolation {
+        SincInterpolationType::Cubic => 4,
+        SincInterpolationType::Quadratic => 3,
+        SincInterpolationType::Linear => 2,
+        SincInterpolationType::Nearest => 1,
+    };
+    (points * sinc_len * output_frames * nbr_channels) as u64
+}
+
+/// Helper to describe the filter of a sinc resampler, see [Resampler::quality_info].
+fn sinc_quality_info<T>(
+    interpolator: &dyn SincInterpolator<T>,
+    window: Option<WindowFunction>,
+    latency: usize,
+) -> QualityInfo {
+    let sinc_len = interpolator.len();
+    QualityInfo {
+        filter: FilterType::Sinc {
+            sinc_len,
+            oversampling_factor: interpolator.nbr_sincs(),
+        },
+        attenuation_db: window.map(approximate_attenuation),
+        transition_bandwidth: window.map(|window| 1.0 - calculate_cutoff::<f64>(sinc_len, window)),
+        latency,
+    }
+}
+
 /// Perform cubic polynomial interpolation to get value at x.
 /// Input points are assumed to be at x = -1, 0, 1, 2.
 fn interp_cubic<T>(x: T, yvals: &[T; 4]) -> T
@@ -226,6 +516,171 @@ where
     yvals[0] + x * (yvals[1] - yvals[0])
 }
 
+/// The points in the sinc tables that are interpolated to get the output value
+/// at a time index, and the fractional position between them.
+/// The number of points is given by the [SincInterpolationType],
+/// and selects the polynomial used for combining the values at the points.
+struct InterpolationPoints<T> {
+    nearest: [(isize, isize); 4],
+    nbr_points: usize,
+    frac: T,
+}
+
+impl<T> InterpolationPoints<T>
+where
+    T: Sample,
+{
+    #[inline]
+    fn new(interpolation: &SincInterpolationType, idx: f64, oversampling_factor: usize) -> Self {
+        let factor = oversampling_factor as isize;
+        let mut nearest = [(0isize, 0isize); 4];
+        let nbr_points = match interpolation {
+            SincInterpolationType::Cubic => {
+                get_nearest_times_4(idx, factor, &mut nearest);
+                4
+            }
+            SincInterpolationType::Quadratic => {
+                let mut points = [(0isize, 0isize); 3];
+                get_nearest_times_3(idx, factor, &mut points);
+                nearest[..3].copy_from_slice(&points);
+                3
+            }
+            SincInterpolationType::Linear => {
+                let mut points = [(0isize, 0isize); 2];
+                get_nearest_times_2(idx, factor, &mut points);
+                nearest[..2].copy_from_slice(&points);
+                2
+            }
+            SincInterpolationType::Nearest => {
+                nearest[0] = get_nearest_time(idx, factor);
+                1
+            }
+        };
+        let frac = idx * oversampling_factor as f64 - (idx * oversampling_factor as f64).floor();
+        InterpolationPoints {
+            nearest,
+            nbr_points,
+            frac: T::coerce(frac),
+        }
+    }
+
+    /// Helper function. Combine the values at the points into the output value.
+    #[inline]
+    fn combine(&self, values: &[T; 4]) -> T {
+        match self.nbr_points {
+            4 => interp_cubic(self.frac, values),
+            3 => interp_quad(self.frac, &[values[0], values[1], values[2]]),
+            2 => interp_lin(self.frac, &[values[0], values[1]]),
+            _ => values[0],
+        }
+    }
+
+    /// Interpolate the output value from a buffer that holds the input data
+    /// after `2 * sinc_len` frames of history.
+    #[inline]
+    fn interpolate(&self, interpolator: &dyn SincInterpolator<T>, buf: &[T]) -> T {
+        let offset = 2 * interpolator.len() as isize;
+        let get = |near: &(isize, isize)| {
+            interpolator.get_sinc_interpolated(buf, (near.0 + offset) as usize, near.1 as usize)
+        };
+        match self.nbr_points {
+            1 => get(&self.nearest[0]),
+            2 => interpolator.get_sinc_interpolated_lin(
+                buf,
+                (self.nearest[0].0 + offset) as usize,
+                self.nearest[0].1 as usize,
+                self.frac,
+            ),
+            _ => {
+                let mut values = [T::zero(); 4];
+                for (value, near) in values.iter_mut().zip(&self.nearest[..self.nbr_points]) {
+                    *value = get(near);
+                }
+                self.combine(&values)
+            }
+        }
+    }
+
+    /// Get the points together with their weights in the output value.
+    #[cfg(feature = "debug-interp")]
+    fn weights(&self) -> Vec<((isize, isize), T)> {
+        (0..self.nbr_points)
+            .map(|k| {
+                let mut unit = [T::zero(); 4];
+                unit[k] = T::one();
+                (self.nearest[k], self.combine(&unit))
+            })
+            .collect()
+    }
+
+    /// Interpolate the output values of two buffers like [interpolate](Self::interpolate),
+    /// reading each sinc coefficient once for both.
+    fn interpolate_pair(
+        &self,
+        interpolator: &dyn SincInterpolator<T>,
+        buf_a: &[T],
+        buf_b: &[T],
+    ) -> (T, T) {
+        let offset = 2 * interpolator.len() as isize;
+        let mut values_a = [T::zero(); 4];
+        let mut values_b = [T::zero(); 4];
+        for (k, near) in self.nearest[..self.nbr_points].iter().enumerate() {
+            (values_a[k], values_b[k]) = interpolator.get_sinc_interpolated_pair(
+                buf_a,
+                buf_b,
+                (near.0 + offset) as usize,
+                near.1 as usize,
+            );
+        }
+        (self.combine(&values_a), self.combine(&values_b))
+    }
+}
+
+/// Iterator over the time indices of the output frames of a chunk.
+/// The step between the indices follows a ramp from the current to the target ratio.
+struct IndexSteps {
+    idx: f64,
+    t_ratio: f64,
+    t_ratio_increment: f64,
+    end_idx: f64,
+}
+
+impl Iterator for IndexSteps {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        if self.idx < self.end_idx {
+            self.t_ratio += self.t_ratio_increment;
+            self.idx += self.t_ratio;
+            Some(self.idx)
+        } else {
+            None
+        }
+    }
+}
+
+/// The weight of the short transient filter in an output frame,
+/// and the point in its sinc tables, see [SincFixedIn::set_transient_mode].
+struct TransientBlend<T> {
+    gain: T,
+    index: usize,
+    subindex: usize,
+    frac: T,
+}
+
+impl<T> TransientBlend<T>
+where
+    T: Sample,
+{
+    /// Crossfade an output value of a buffer towards the output of the short filter.
+    #[inline]
+    fn apply(&self, short_interpolator: &dyn SincInterpolator<T>, buf: &[T], value: T) -> T {
+        let short_value =
+            short_interpolator.get_sinc_interpolated_lin(buf, self.index, self.subindex, self.frac);
+        value + self.gain * (short_value - value)
+    }
+}
+
 fn validate_ratios(
     resample_ratio: f64,
     max_resample_ratio_relative: f64,
@@ -264,6 +719,14 @@ where
             "Create new SincFixedIn, ratio: {}, chunk_size: {}, channels: {}, parameters: {:?}",
             resample_ratio, chunk_size, nbr_channels, parameters
         );
+        construction_span!(
+            "SincFixedIn::new",
+            channels = nbr_channels,
+            ratio = resample_ratio,
+            window = ?parameters.window,
+            sinc_len = parameters.sinc_len,
+            f_cutoff = parameters.f_cutoff,
+        );
 
         let interpolator = make_interpolator(
             parameters.sinc_len,
@@ -273,13 +736,55 @@ where
             parameters.window,
         );
 
-        Self::new_with_interpolator(
+        let mut resampler = Self::new_with_interpolator(
             resample_ratio,
             max_resample_ratio_relative,
             parameters.interpolation,
             interpolator,
             chunk_size,
             nbr_channels,
+        )?;
+        resampler.window = Some(parameters.window);
+        Ok(resampler)
+    }
+
+    /// Create a new SincFixedIn using sincs calculated on a frequency-warped grid,
+    /// see [BarkSincTable].
+    ///
+    /// Parameters are:
+    /// - `resample_ratio`: Starting ratio between output and input sample rates, must be > 0.
+    /// - `max_resample_ratio_relative`: Maximum ratio that can be set with [Resampler::set_resample_ratio] relative to `resample_ratio`, must be >= 1.0.
+    /// - `interpolation_type`: Parameters for interpolation, see `SincInterpolationType`.
+    /// - `config`: Parameters for the warped sinc table, see `BarkSincConfig`.
+    /// - `chunk_size`: Size of input data in frames.
+    /// - `nbr_channels`: Number of channels in input/output.
+    pub fn new_bark(
+        resample_ratio: f64,
+        max_resample_ratio_relative: f64,
+        interpolation_type: SincInterpolationType,
+        config: BarkSincConfig,
+        chunk_size: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        let sinc_len = 8 * (((config.sinc_len as f32) / 8.0).ceil() as usize);
+        let f_cutoff = if resample_ratio >= 1.0 {
+            config.f_cutoff
+        } else {
+            config.f_cutoff * resample_ratio as f32
+        };
+        let table = BarkSincTable::new(BarkSincConfig {
+            sinc_len,
+            f_cutoff,
+            ..config
+        })?;
+
+        Self::new_with_interpolator(
+            resample_ratio,
+            max_resample_ratio_relative,
+            interpolation_type,
+            Box::new(table),
+            chunk_size,
+            nbr_channels,
         )
     }
 
@@ -299,9 +804,29 @@ where
         interpolator: Box<dyn SincInterpolator<T>>,
         chunk_size: usize,
         nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        Self::new_with_shared_interpolator(
+            resample_ratio,
+            max_resample_ratio_relative,
+            interpolation_type,
+            Arc::from(interpolator),
+            chunk_size,
+            nbr_channels,
+        )
+    }
+
+    /// Create a new resampler using an interpolator that may be shared with other resamplers.
+    fn new_with_shared_interpolator(
+        resample_ratio: f64,
+        max_resample_ratio_relative: f64,
+        interpolation_type: SincInterpolationType,
+        interpolator: Arc<dyn SincInterpolator<T>>,
+        chunk_size: usize,
+        nbr_channels: usize,
     ) -> Result<Self, ResamplerConstructionError> {
         validate_ratios(resample_ratio, max_resample_ratio_relative)?;
         let buffer = vec![vec![T::zero(); chunk_size + 2 * interpolator.len()]; nbr_channels];
+        let sidechain_buffer = vec![T::zero(); chunk_size + 2 * interpolator.len()];
 
         let channel_mask = vec![true; nbr_channels];
 
@@ -316,581 +841,1596 @@ where
             max_relative_ratio: max_resample_ratio_relative,
             interpolator,
             buffer,
+            sidechain_buffer,
             interpolation: interpolation_type,
+            window: None,
             channel_mask,
+            max_block_frames: None,
+            overflow_policy: OverflowPolicy::Error,
+            clip_ceiling: None,
+            clipped_samples: 0,
+            flush_remaining: None,
+            discard_tail: false,
+            discarded_frames: 0,
+            mute_gains: vec![T::one(); nbr_channels],
+            transient_interpolator: None,
+            transient_onsets: Vec::new(),
+            at_stream_start: true,
+            metrics: ResamplerMetrics::new(nbr_channels),
+            reference_resampler: None,
+            reference_in_step: false,
+            #[cfg(any(feature = "profiling", feature = "prometheus"))]
+            chunk_start: std::time::Instant::now(),
+            #[cfg(feature = "fft_resampler")]
+            spectrum_analyzer: None,
+            #[cfg(feature = "fft_resampler")]
+            spectra: vec![Vec::new(); nbr_channels],
+            #[cfg(feature = "profiling")]
+            profiler: LatencyHistogram::new(&LatencyHistogram::default_limits()),
         })
     }
 
+    /// Create a new SincFixedIn that continues from a saved state,
+    /// as returned by [checkpoint](SincFixedIn::checkpoint).
+    /// This allows resuming a long resampling job without feeding the resampler
+    /// with the input preceding the checkpoint again.
+    ///
+    /// Parameters are:
+    /// - `resample_ratio`: Ratio between output and input sample rates, must be > 0.
+    /// - `max_resample_ratio_relative`: Maximum ratio that can be set with [Resampler::set_resample_ratio] relative to `resample_ratio`, must be >= 1.0.
+    /// - `parameters`: Parameters for interpolation, see `SincInterpolationParameters`.
+    /// - `chunk_size`: Size of input data in frames.
+    /// - `checkpoint`: The saved phase, ratios and delay line.
+    ///   The number of channels is given by the number of vectors in the delay line,
+    ///   and each vector must hold twice as many frames as the sinc length.
+    ///
+    /// The parameters must be the same as for the resampler that the checkpoint was taken from.
+    /// The current and target ratios are restored from the checkpoint,
+    /// so that a ramp started with [Resampler::set_resample_ratio] continues where it was.
+    /// The sinc length is rounded up to a multiple of 8, in the same way as for [SincFixedIn::new].
+    /// If any vector in the delay line has the wrong length,
+    /// [ResamplerConstructionError::InvalidDelayLineLength] is returned.
+    pub fn reconstruct_from_phase(
+        resample_ratio: f64,
+        max_resample_ratio_relative: f64,
+        parameters: SincInterpolationParameters,
+        chunk_size: usize,
+        checkpoint: SincCheckpoint<T>,
+    ) -> Result<Self, ResamplerConstructionError> {
+        let SincCheckpoint {
+            phase,
+            resample_ratio: current_ratio,
+            target_ratio,
+            delay_line,
+        } = checkpoint;
+        let mut resampler = Self::new(
+            resample_ratio,
+            max_resample_ratio_relative,
+            parameters,
+            chunk_size,
+            delay_line.len(),
+        )?;
+        let history_len = 2 * resampler.interpolator.len();
+        for (channel, (buf, saved)) in resampler
+            .buffer
+            .iter_mut()
+            .zip(delay_line.iter())
+            .enumerate()
+        {
+            if saved.len() != history_len {
+                return Err(ResamplerConstructionError::InvalidDelayLineLength {
+                    channel,
+                    expected: history_len,
+                    actual: saved.len(),
+                });
+            }
+            // The start of the next call moves this part to the beginning of the buffer.
+            buf[chunk_size..chunk_size + history_len].copy_from_slice(saved);
+        }
+        resampler.last_index = phase;
+        resampler.resample_ratio = current_ratio;
+        resampler.target_ratio = target_ratio;
+        resampler.at_stream_start = false;
+        Ok(resampler)
+    }
+
+    /// Save the state needed for resuming processing with
+    /// [reconstruct_from_phase](SincFixedIn::reconstruct_from_phase).
+    /// The checkpoint holds the phase, the current and target resampling ratios,
+    /// and the delay line holding the last `2 * sinc_len` input frames for each channel.
+    pub fn checkpoint(&self) -> SincCheckpoint<T> {
+        let history_len = 2 * self.interpolator.len();
+        let delay_line = self
+            .buffer
+            .iter()
+            .map(|buf| buf[self.chunk_size..self.chunk_size + history_len].to_vec())
+            .collect();
+        SincCheckpoint {
+            phase: self.last_index,
+            resample_ratio: self.resample_ratio,
+            target_ratio: self.target_ratio,
+            delay_line,
+        }
+    }
+
+    /// Get the delay line of a channel, for example for visualizing it.
+    /// This holds the last `2 * sinc_len` input frames, with the most recent frame last.
+    /// Panics if `channel` is not smaller than the number of channels.
+    pub fn history(&self, channel: usize) -> &[T] {
+        let history_len = 2 * self.interpolator.len();
+        &self.buffer[channel][self.chunk_size..self.chunk_size + history_len]
+    }
+
+    /// Fill the delay line from the end of the output of a previous stream,
+    /// for example the previous track of a gapless playlist.
+    /// Without this, the delay line holds silence and the first output frames
+    /// fade in from zero, which gives an audible transient when the streams are joined.
+    ///
+    /// The previous output is at the output sample rate, and it is converted back to the input
+    /// sample rate by linear interpolation, using the current resampling ratio.
+    /// Its last frame is placed just before the first input frame of the new stream.
+    /// The output should therefore end with regular output frames, and not with frames
+    /// resulting from zero padding such as from [process_partial_last](SincFixedIn::process_partial_last).
+    /// The delay line holds `2 * sinc_len` input frames,
+    /// and any part that can't be filled from a short previous output is set to zero.
+    /// Call this on a new or reset resampler, before processing the first chunk of the new stream.
+    ///
+    /// The previous output must have one vector per channel,
+    /// otherwise [ResampleError::WrongNumberOfInputChannels] is returned.
+    pub fn warm_start(&mut self, previous_output: &[Vec<T>]) -> ResampleResult<()> {
+        if previous_output.len() != self.nbr_channels {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: self.nbr_channels,
+                actual: previous_output.len(),
+            });
+        }
+        let history_len = 2 * self.interpolator.len();
+        let end = self.chunk_size + history_len;
+        for (buf, previous) in self.buffer.iter_mut().zip(previous_output.iter()) {
+            for (k, value) in buf[self.chunk_size..end].iter_mut().rev().enumerate() {
+                // The last input frame of the delay line matches the last output frame.
+                let position = previous.len() as f64 - 1.0 - k as f64 * self.resample_ratio;
+                *value = if position < 0.0 {
+                    T::zero()
+                } else {
+                    let index = position.floor() as usize;
+                    let frac = T::coerce(position - index as f64);
+                    let next = previous.get(index + 1).copied().unwrap_or(previous[index]);
+                    previous[index] + frac * (next - previous[index])
+                };
+            }
+        }
+        self.at_stream_start = false;
+        Ok(())
+    }
+
+    /// Create a new resampler that shares the sinc interpolation filters with this one.
+    /// This avoids recalculating the filters, and only allocates the internal buffers,
+    /// which is O(sinc_len × channels) instead of O(oversampling_factor × sinc_len).
+    ///
+    /// The new resampler is in the same state as a newly created one, using the ratio
+    /// and chunk size given when this resampler was created.
+    /// The shared filters are never modified, so changing the ratio or any other setting
+    /// of the clone does not affect the original, and vice versa.
+    pub fn try_clone(&self) -> SincFixedIn<T> {
+        let mut resampler = SincFixedIn::new_with_shared_interpolator(
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+            self.interpolation.clone(),
+            Arc::clone(&self.interpolator),
+            self.max_chunk_size,
+            self.nbr_channels,
+        )
+        .expect("Ratios were already validated");
+        resampler.window = self.window;
+        resampler.discard_tail = self.discard_tail;
+        if let Some(interpolator) = &self.transient_interpolator {
+            resampler.transient_interpolator = Some(Arc::clone(interpolator));
+            resampler.transient_onsets = Vec::with_capacity(self.transient_onsets.capacity());
+        }
+        resampler
+    }
+
+    /// Change the interpolation type used between the points of the oversampled sinc table.
+    /// The table is shared by all interpolation types, so this does not allocate,
+    /// and the internal buffers are kept so that the stream continues without a discontinuity.
+    /// The new type is used from the next call to
+    /// [process_into_buffer](Resampler::process_into_buffer).
+    pub fn set_interpolation_type(&mut self, interpolation: SincInterpolationType) {
+        debug!("Change interpolation type to {:?}", interpolation);
+        self.interpolation = interpolation;
+    }
+
+    /// Estimate the number of bytes a new SincFixedIn will use for its sinc tables
+    /// and internal buffers, when created by [SincFixedIn::new] with the given parameters.
+    /// Only the sample data is counted, not the small fixed size parts of the struct.
+    /// The estimate assumes that the sinc table is stored once, which is the case
+    /// for all interpolators created by [SincFixedIn::new].
+    pub fn estimate_memory_usage(
+        parameters: &SincInterpolationParameters,
+        chunk_size: usize,
+        nbr_channels: usize,
+    ) -> usize {
+        let sinc_len = 8 * (((parameters.sinc_len as f32) / 8.0).ceil() as usize);
+        let table = sinc_len * parameters.oversampling_factor;
+        let buffers = (nbr_channels + 1) * (chunk_size + 2 * sinc_len);
+        std::mem::size_of::<T>() * (table + buffers)
+    }
+
+    /// Get the number of bytes allocated for the sinc tables and internal buffers,
+    /// including any unused capacity of the buffers.
+    /// Only the sample data is counted, not the small fixed size parts of the struct.
+    pub fn peak_memory_usage(&self) -> usize {
+        let buffers = self.buffer.iter().map(|buf| buf.capacity()).sum::<usize>()
+            + self.sidechain_buffer.capacity();
+        self.interpolator.table_memory_usage() + std::mem::size_of::<T>() * buffers
+    }
+
+    /// Get the number of bytes used for the sinc tables and internal buffers.
+    /// This is like [peak_memory_usage](SincFixedIn::peak_memory_usage),
+    /// but excludes any unused capacity of the buffers.
+    pub fn current_memory_usage(&self) -> usize {
+        let buffers =
+            self.buffer.iter().map(|buf| buf.len()).sum::<usize>() + self.sidechain_buffer.len();
+        self.interpolator.table_memory_usage() + std::mem::size_of::<T>() * buffers
+    }
+
     fn calc_needed_len(&self) -> usize {
         (self.chunk_size as f64 * (0.5 * self.resample_ratio + 0.5 * self.target_ratio) + 10.0)
             as usize
     }
-}
 
-impl<T> Resampler<T> for SincFixedIn<T>
-where
-    T: Sample,
-{
-    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    /// Helper function. Read a chunk of input from the active channels into the buffer,
+    /// where `channel_input` gives the input of a channel, and resample it into `wave_out`.
+    /// The buffers must have been validated before calling.
+    /// Returns the number of output frames.
+    fn resample_chunk<'a, F, Vout>(&mut self, channel_input: F, wave_out: &mut [Vout]) -> usize
+    where
+        F: Fn(usize) -> &'a [T],
+        Vout: AsMut<[T]>,
+        T: 'a,
+    {
+        for (chan, active) in self.channel_mask.iter().enumerate() {
+            if *active {
+                debug_assert!(self.calc_needed_len() <= wave_out[chan].as_mut().len());
+            }
+        }
+        self.begin_chunk(channel_input);
+        let oversampling_factor = self.interpolator.nbr_sincs();
+        let mut idx = self.last_index;
+        let mut n = 0;
+        for next_idx in self.index_steps() {
+            idx = next_idx;
+            let points = InterpolationPoints::new(&self.interpolation, idx, oversampling_factor);
+            let blend = self.transient_blend(idx);
+            for (chan, active) in self.channel_mask.iter().enumerate() {
+                if *active {
+                    wave_out[chan].as_mut()[n] =
+                        self.interpolate_frame(&points, &blend, &self.buffer[chan]);
+                }
+            }
+            n += 1;
+        }
+        self.end_chunk(idx);
+        self.finish_output(wave_out, None, n);
+        n
+    }
+
+    /// Helper function. Validate the buffers for processing a chunk with the channel mask,
+    /// see [validate_buffers], and count a too short buffer in the metrics.
+    fn validate_chunk<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
         &mut self,
         wave_in: &[Vin],
         wave_out: &mut [Vout],
-        active_channels_mask: Option<&[bool]>,
-    ) -> ResampleResult<(usize, usize)> {
-        if let Some(mask) = active_channels_mask {
-            self.channel_mask.copy_from_slice(mask);
-        } else {
-            update_mask_from_buffers(&mut self.channel_mask);
-        };
-
-        // Set length to chunksize*ratio plus a safety margin of 10 elements.
-        let needed_len = self.calc_needed_len();
-
-        validate_buffers(
+        min_input_frames: usize,
+        min_output_frames: usize,
+        max_block_frames: Option<usize>,
+    ) -> ResampleResult<()> {
+        let result = validate_buffers(
             wave_in,
             wave_out,
             &self.channel_mask,
             self.nbr_channels,
-            self.chunk_size,
-            needed_len,
-        )?;
+            min_input_frames,
+            min_output_frames,
+            max_block_frames,
+        );
+        if matches!(
+            result,
+            Err(ResampleError::InsufficientInputBufferSize { .. }
+                | ResampleError::InsufficientOutputBufferSize { .. })
+        ) {
+            self.metrics.record_xrun();
+        }
+        result
+    }
 
-        let sinc_len = self.interpolator.len();
-        let oversampling_factor = self.interpolator.nbr_sincs();
-        let mut t_ratio = 1.0 / self.resample_ratio;
-        let t_ratio_end = 1.0 / self.target_ratio;
-        let approximate_nbr_frames =
-            self.chunk_size as f64 * (0.5 * self.resample_ratio + 0.5 * self.target_ratio);
-        let t_ratio_increment = (t_ratio_end - t_ratio) / approximate_nbr_frames;
-        let end_idx =
-            self.chunk_size as isize - (sinc_len as isize + 1) - t_ratio_end.ceil() as isize;
+    /// Helper function. Start processing a chunk, by reading the input of the active channels
+    /// into the buffer and searching it for transients.
+    /// Every method that processes a chunk must call this after validating the buffers,
+    /// and then [end_chunk](SincFixedIn::end_chunk) after the interpolation.
+    fn begin_chunk<'a, F>(&mut self, channel_input: F)
+    where
+        F: Fn(usize) -> &'a [T],
+        T: 'a,
+    {
+        #[cfg(any(feature = "profiling", feature = "prometheus"))]
+        {
+            self.chunk_start = std::time::Instant::now();
+        }
+        self.at_stream_start = false;
+        self.flush_remaining = None;
+        self.reference_in_step = false;
+        self.load_chunk(channel_input);
+        self.find_transients();
+    }
 
-        // Update buffer with new data.
-        for buf in self.buffer.iter_mut() {
-            buf.copy_within(self.chunk_size..self.chunk_size + 2 * sinc_len, 0);
+    /// Helper function. Finish processing a chunk, where `idx` is the time index
+    /// of its last output frame, and update the metrics.
+    fn end_chunk(&mut self, idx: f64) {
+        self.advance(idx);
+        self.metrics.record(self.chunk_size, &self.channel_mask);
+        #[cfg(any(feature = "profiling", feature = "prometheus"))]
+        self.record_duration(self.chunk_start);
+    }
+
+    /// Helper function. Count the clipped samples of a block of output,
+    /// and store its spectra if capturing is enabled.
+    /// The output channels are selected by `output_mask`, or by the channel mask if it is `None`.
+    fn finish_output<Vout: AsMut<[T]>>(
+        &mut self,
+        wave_out: &mut [Vout],
+        output_mask: Option<&[bool]>,
+        nbr_frames: usize,
+    ) {
+        let output_mask = output_mask.unwrap_or(&self.channel_mask);
+        #[cfg(feature = "fft_resampler")]
+        if let Some(analyzer) = self.spectrum_analyzer.as_mut() {
+            // The gain matrix may give a different number of output channels.
+            if self.spectra.len() != wave_out.len() {
+                self.spectra
+                    .resize(wave_out.len(), vec![0.0; analyzer.nbr_bins()]);
+            }
+            for (chan, active) in output_mask.iter().enumerate() {
+                if *active {
+                    analyzer.magnitudes(
+                        &wave_out[chan].as_mut()[..nbr_frames],
+                        &mut self.spectra[chan],
+                    );
+                }
+            }
         }
+        self.clipped_samples += count_clipped(wave_out, output_mask, nbr_frames, self.clip_ceiling);
+    }
 
-        for (chan, active) in self.channel_mask.iter().enumerate() {
-            if *active {
-                debug_assert!(needed_len <= wave_out[chan].as_mut().len());
-                self.buffer[chan][2 * sinc_len..2 * sinc_len + self.chunk_size]
-                    .copy_from_slice(&wave_in[chan].as_ref()[..self.chunk_size]);
+    /// Helper function. Shift the history in the buffer, and read a chunk of input
+    /// into it for the active channels, where `channel_input` gives the input of a channel.
+    fn load_chunk<'a, F>(&mut self, channel_input: F)
+    where
+        F: Fn(usize) -> &'a [T],
+        T: 'a,
+    {
+        let sinc_len = self.interpolator.len();
+        for (chan, buf) in self.buffer.iter_mut().enumerate() {
+            buf.copy_within(self.chunk_size..self.chunk_size + 2 * sinc_len, 0);
+            if self.channel_mask[chan] {
+                buf[2 * sinc_len..2 * sinc_len + self.chunk_size]
+                    .copy_from_slice(&channel_input(chan)[..self.chunk_size]);
             }
         }
+    }
 
-        let mut idx = self.last_index;
+    /// Helper function. Store the time index of the last output frame for the next chunk,
+    /// where `idx` is the index of the last frame of the current one,
+    /// and complete any ramp of the ratio.
+    fn advance(&mut self, idx: f64) {
+        self.last_index = idx - self.chunk_size as f64;
+        self.resample_ratio = self.target_ratio;
+    }
 
-        let mut n = 0;
+    /// Helper function. Find the transients in the buffer when the transient mode is enabled.
+    fn find_transients(&mut self) {
+        let short_len = match &self.transient_interpolator {
+            Some(interpolator) => interpolator.len(),
+            None => return,
+        };
+        let sinc_len = self.interpolator.len();
 
-        match self.interpolation {
-            SincInterpolationType::Cubic => {
-                let mut points = [T::zero(); 4];
-                let mut nearest = [(0isize, 0isize); 4];
-                while idx < end_idx as f64 {
-                    t_ratio += t_ratio_increment;
-                    idx += t_ratio;
-                    get_nearest_times_4(idx, oversampling_factor as isize, &mut nearest);
-                    let frac = idx * oversampling_factor as f64
-                        - (idx * oversampling_factor as f64).floor();
-                    let frac_offset = T::coerce(frac);
-                    for (chan, active) in self.channel_mask.iter().enumerate() {
-                        if *active {
-                            let buf = &self.buffer[chan];
-                            for (n, p) in nearest.iter().zip(points.iter_mut()) {
-                                *p = self.interpolator.get_sinc_interpolated(
-                                    buf,
-                                    (n.0 + 2 * sinc_len as isize) as usize,
-                                    n.1 as usize,
-                                );
-                            }
-                            wave_out[chan].as_mut()[n] = interp_cubic(frac_offset, &points);
-                        }
-                    }
-                    n += 1;
-                }
+        // A transient is a frame where the largest absolute value of the active channels
+        // exceeds the average of the preceding `short_len` frames by a large factor.
+        let buffer = &self.buffer;
+        let channel_mask = &self.channel_mask;
+        let envelope = |frame: usize| {
+            buffer
+                .iter()
+                .zip(channel_mask.iter())
+                .filter(|(_, active)| **active)
+                .fold(0.0f64, |max, (buf, _)| max.max(buf[frame].to_f64().abs()))
+        };
+        self.transient_onsets.clear();
+        let mut sum = (0..short_len).map(envelope).sum::<f64>();
+        for frame in short_len..self.chunk_size + 2 * sinc_len {
+            let value = envelope(frame);
+            if value > TRANSIENT_MIN_LEVEL && value * short_len as f64 > TRANSIENT_LEVEL_RATIO * sum
+            {
+                self.transient_onsets.push(frame);
             }
-            SincInterpolationType::Quadratic => {
-                let mut points = [T::zero(); 3];
-                let mut nearest = [(0isize, 0isize); 3];
-                while idx < end_idx as f64 {
-                    t_ratio += t_ratio_increment;
-                    idx += t_ratio;
-                    get_nearest_times_3(idx, oversampling_factor as isize, &mut nearest);
-                    let frac = idx * oversampling_factor as f64
-                        - (idx * oversampling_factor as f64).floor();
-                    let frac_offset = T::coerce(frac);
-                    for (chan, active) in self.channel_mask.iter().enumerate() {
-                        if *active {
-                            let buf = &self.buffer[chan];
-                            for (n, p) in nearest.iter().zip(points.iter_mut()) {
-                                *p = self.interpolator.get_sinc_interpolated(
-                                    buf,
-                                    (n.0 + 2 * sinc_len as isize) as usize,
-                                    n.1 as usize,
-                                );
-                            }
-                            wave_out[chan].as_mut()[n] = interp_quad(frac_offset, &points);
-                        }
-                    }
-                    n += 1;
-                }
+            sum += value - envelope(frame - short_len);
+        }
+    }
+
+    /// Helper function. Get the blend of the short transient filter for the output frame
+    /// at the time index `idx`, or `None` when there is no transient near the frame.
+    #[inline]
+    fn transient_blend(&self, idx: f64) -> Option<TransientBlend<T>> {
+        if self.transient_onsets.is_empty() {
+            return None;
+        }
+        let short_len = self.transient_interpolator.as_ref()?.len();
+        let sinc_len = self.interpolator.len();
+        let oversampling_factor = self.interpolator.nbr_sincs();
+        let onsets = &self.transient_onsets;
+        // Both filters are centered at the middle of the long one.
+        let center = idx + (2 * sinc_len + sinc_len / 2) as f64;
+        let after = onsets.partition_point(|frame| (*frame as f64) < center);
+        let distance = onsets[after.saturating_sub(1)..(after + 1).min(onsets.len())]
+            .iter()
+            .fold(f64::INFINITY, |min, frame| {
+                min.min((*frame as f64 - center).abs())
+            });
+        // Use only the short filter while the transient is well within the long one,
+        // and fade over half the length of the short filter near the ends.
+        let gain = ((0.5 * sinc_len as f64 - distance) / (0.5 * short_len as f64)).clamp(0.0, 1.0);
+        if gain == 0.0 {
+            return None;
+        }
+        let mut nearest = [(0isize, 0isize); 2];
+        get_nearest_times_2(idx, oversampling_factor as isize, &mut nearest);
+        let frac = idx * oversampling_factor as f64 - (idx * oversampling_factor as f64).floor();
+        Some(TransientBlend {
+            gain: T::coerce(gain),
+            index: (nearest[0].0 + (2 * sinc_len + (sinc_len - short_len) / 2) as isize) as usize,
+            subindex: nearest[0].1 as usize,
+            frac: T::coerce(frac),
+        })
+    }
+
+    /// Helper function. Interpolate the output value of a channel buffer at the points,
+    /// and apply the blend of the short transient filter if there is one.
+    #[inline]
+    fn interpolate_frame(
+        &self,
+        points: &InterpolationPoints<T>,
+        blend: &Option<TransientBlend<T>>,
+        buf: &[T],
+    ) -> T {
+        let value = points.interpolate(self.interpolator.as_ref(), buf);
+        match (blend, &self.transient_interpolator) {
+            (Some(blend), Some(short_interpolator)) => {
+                blend.apply(short_interpolator.as_ref(), buf, value)
             }
-            SincInterpolationType::Linear => {
-                let mut points = [T::zero(); 2];
-                let mut nearest = [(0isize, 0isize); 2];
-                while idx < end_idx as f64 {
-                    t_ratio += t_ratio_increment;
-                    idx += t_ratio;
-                    get_nearest_times_2(idx, oversampling_factor as isize, &mut nearest);
-                    let frac = idx * oversampling_factor as f64
-                        - (idx * oversampling_factor as f64).floor();
-                    let frac_offset = T::coerce(frac);
-                    for (chan, active) in self.channel_mask.iter().enumerate() {
-                        if *active {
-                            let buf = &self.buffer[chan];
-                            for (n, p) in nearest.iter().zip(points.iter_mut()) {
-                                *p = self.interpolator.get_sinc_interpolated(
-                                    buf,
-                                    (n.0 + 2 * sinc_len as isize) as usize,
-                                    n.1 as usize,
-                                );
-                            }
-                            wave_out[chan].as_mut()[n] = interp_lin(frac_offset, &points);
-                        }
-                    }
-                    n += 1;
+            _ => value,
+        }
+    }
+
+    /// Calculate the time indices that will be used for the output frames of the next call to
+    /// [process_into_buffer](Resampler::process_into_buffer).
+    /// This uses the same stepping as processing, without touching the state.
+    fn next_indices(&self) -> Vec<f64> {
+        self.index_steps().collect()
+    }
+
+    /// Helper function. Get an iterator over the time indices of the output frames
+    /// of the next chunk, starting from the last index.
+    fn index_steps(&self) -> IndexSteps {
+        let sinc_len = self.interpolator.len();
+        let t_ratio = 1.0 / self.resample_ratio;
+        let t_ratio_end = 1.0 / self.target_ratio;
+        let approximate_nbr_frames =
+            self.chunk_size as f64 * (0.5 * self.resample_ratio + 0.5 * self.target_ratio);
+        let end_idx =
+            self.chunk_size as isize - (sinc_len as isize + 1) - t_ratio_end.ceil() as isize;
+        IndexSteps {
+            idx: self.last_index,
+            t_ratio,
+            t_ratio_increment: (t_ratio_end - t_ratio) / approximate_nbr_frames,
+            end_idx: end_idx as f64,
+        }
+    }
+
+    /// Calculate the positions of the output frames that will be produced by the next call to
+    /// [process_into_buffer](Resampler::process_into_buffer).
+    /// The positions are given in input frames, relative to the start of the next input chunk.
+    fn next_output_positions(&self) -> Vec<f64> {
+        // The sinc filters are centered at one sample before the middle,
+        // offset by the distance between the intermediate points.
+        let offset =
+            (self.interpolator.len() / 2) as f64 - 1.0 + 1.0 / self.interpolator.nbr_sincs() as f64;
+        self.next_indices().iter().map(|idx| idx + offset).collect()
+    }
+
+    /// Process the same input with several resamplers, and return the weighted average
+    /// of their outputs. This is mainly intended for comparing different settings,
+    /// for example in listening tests.
+    ///
+    /// The weights are normalized by their sum.
+    /// If the resamplers produce different numbers of frames,
+    /// the output is truncated to the shortest one.
+    /// Note that resamplers with different sinc lengths also have different delays,
+    /// and this is not compensated for.
+    ///
+    /// Panics if `resamplers` is empty, or if the number of weights
+    /// does not match the number of resamplers.
+    pub fn process_averaged<V: AsRef<[T]>>(
+        resamplers: &mut [SincFixedIn<T>],
+        wave_in: &[V],
+        weights: &[f64],
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        assert!(!resamplers.is_empty(), "At least one resampler is needed");
+        assert_eq!(
+            resamplers.len(),
+            weights.len(),
+            "The number of weights must match the number of resamplers"
+        );
+        let weight_sum: f64 = weights.iter().sum();
+        let mut averaged: Option<Vec<Vec<T>>> = None;
+        for (resampler, weight) in resamplers.iter_mut().zip(weights.iter()) {
+            let wave_out = resampler.process(wave_in, None)?;
+            let scale = T::coerce(weight / weight_sum);
+            match averaged.as_mut() {
+                None => {
+                    averaged = Some(
+                        wave_out
+                            .iter()
+                            .map(|chan| chan.iter().map(|value| *value * scale).collect())
+                            .collect(),
+                    );
                 }
-            }
-            SincInterpolationType::Nearest => {
-                let mut point;
-                let mut nearest;
-                while idx < end_idx as f64 {
-                    t_ratio += t_ratio_increment;
-                    idx += t_ratio;
-                    nearest = get_nearest_time(idx, oversampling_factor as isize);
-                    for (chan, active) in self.channel_mask.iter().enumerate() {
-                        if *active {
-                            let buf = &self.buffer[chan];
-                            point = self.interpolator.get_sinc_interpolated(
-                                buf,
-                                (nearest.0 + 2 * sinc_len as isize) as usize,
-                                nearest.1 as usize,
-                            );
-                            wave_out[chan].as_mut()[n] = point;
+                Some(averaged) => {
+                    for (avg_chan, chan) in averaged.iter_mut().zip(wave_out.iter()) {
+                        avg_chan.truncate(chan.len());
+                        for (avg, value) in avg_chan.iter_mut().zip(chan.iter()) {
+                            *avg += *value * scale;
                         }
                     }
-                    n += 1;
                 }
             }
         }
-
-        // Store last index for next iteration.
-        self.last_index = idx - self.chunk_size as f64;
-        self.resample_ratio = self.target_ratio;
-        trace!(
-            "Resampling channels {:?}, {} frames in, {} frames out",
-            active_channels_mask,
-            self.chunk_size,
-            n,
-        );
-        Ok((self.chunk_size, n))
+        Ok(averaged.unwrap_or_default())
     }
 
-    fn output_frames_max(&self) -> usize {
-        // Set length to chunksize*ratio plus a safety margin of 10 elements.
-        (self.max_chunk_size as f64 * self.resample_ratio_original * self.max_relative_ratio + 10.0)
-            as usize
-    }
+    /// Run the resampler on a chunk of input, but discard the output.
+    /// This is intended only for benchmarking, to measure the speed of the sinc interpolation
+    /// without the time needed for writing the output.
+    /// It performs the same stepping and sinc interpolation as
+    /// [process_into_buffer](Resampler::process_into_buffer) for all channels,
+    /// including the transient mode, and updates the internal state and the metrics
+    /// in the same way. Normal processing can therefore continue after a benchmark run,
+    /// with the benchmark input as the history.
+    ///
+    /// # Safety
+    /// No checks are performed on the input. The caller must ensure that it contains
+    /// one slice per channel, and that each slice holds at least
+    /// [input_frames_next](Resampler::input_frames_next) frames.
+    pub unsafe fn process_benchmark_mode_unchecked(&mut self, wave_in: &[&[T]]) {
+        self.channel_mask.iter_mut().for_each(|val| *val = true);
+        self.begin_chunk(|chan| *wave_in.get_unchecked(chan));
 
-    fn output_frames_next(&self) -> usize {
-        self.calc_needed_len()
-    }
+        let oversampling_factor = self.interpolator.nbr_sincs();
+        let mut sink = T::zero();
+        let mut idx = self.last_index;
+        for next_idx in self.index_steps() {
+            idx = next_idx;
+            let points = InterpolationPoints::new(&self.interpolation, idx, oversampling_factor);
+            let blend = self.transient_blend(idx);
+            for buf in self.buffer.iter() {
+                sink += self.interpolate_frame(&points, &blend, buf);
+            }
+        }
+        // Make sure the interpolation is not optimized away.
+        std::ptr::write_volatile(&mut sink, sink);
 
-    fn output_delay(&self) -> usize {
-        (self.interpolator.len() as f64 * self.resample_ratio / 2.0) as usize
+        self.end_chunk(idx);
     }
 
-    fn nbr_channels(&self) -> usize {
-        self.nbr_channels
+    /// Get the factor needed to correct the passband gain of the sinc filter to 0 dB.
+    /// The DC gain of the filter is given by the sum of the coefficients of a single phase,
+    /// and this returns the reciprocal of the sum for the first phase.
+    /// The deviation from unity gain is small, and is largest when the ratio
+    /// differs significantly from 1.0.
+    pub fn auto_gain_compensation(&self) -> T {
+        let ones = vec![T::one(); self.interpolator.len() + 1];
+        T::one() / self.interpolator.get_sinc_interpolated(&ones, 0, 0)
     }
 
-    fn input_frames_max(&self) -> usize {
-        self.max_chunk_size
+    /// Get the DC gain of each phase of the sinc filter, meaning the sum of the coefficients
+    /// of each of the [oversampling_factor](SincInterpolationParameters::oversampling_factor)
+    /// intermediate sincs.
+    /// This is useful for validating the normalization of the filter,
+    /// where all values should be close to 1.0.
+    pub fn phase_dc_gains(&self) -> Vec<T> {
+        let ones = vec![T::one(); self.interpolator.len() + 1];
+        (0..self.interpolator.nbr_sincs())
+            .map(|subindex| self.interpolator.get_sinc_interpolated(&ones, 0, subindex))
+            .collect()
     }
 
-    fn input_frames_next(&self) -> usize {
-        self.chunk_size
+    /// This is a convenience wrapper for [process](Resampler::process) that multiplies
+    /// the output by the factor given by [auto_gain_compensation](SincFixedIn::auto_gain_compensation),
+    /// to give 0 dB gain in the passband.
+    /// Note that this method allocates space for the output.
+    pub fn process_with_auto_gain<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        let gain = self.auto_gain_compensation();
+        let mut wave_out = self.process(wave_in, active_channels_mask)?;
+        for chan in wave_out.iter_mut() {
+            for value in chan.iter_mut() {
+                *value *= gain;
+            }
+        }
+        Ok(wave_out)
     }
 
-    fn set_resample_ratio(&mut self, new_ratio: f64, ramp: bool) -> ResampleResult<()> {
-        trace!("Change resample ratio to {}", new_ratio);
-        if (new_ratio / self.resample_ratio_original >= 1.0 / self.max_relative_ratio)
-            && (new_ratio / self.resample_ratio_original <= self.max_relative_ratio)
+    /// Resample a chunk of audio like [process](Resampler::process), and mute or unmute
+    /// individual channels without clicks.
+    /// For each channel, `mute[chan]` tells if the channel should be muted.
+    /// The gain of a channel moves linearly between one and zero over `ramp_frames` output frames,
+    /// starting from the current gain of the channel, so that a ramp can continue over
+    /// several calls, and a change of direction in the middle of a ramp is smooth.
+    /// A `ramp_frames` of zero changes the gain immediately.
+    ///
+    /// All channels start unmuted, and are unmuted again by [reset](Resampler::reset).
+    /// Note that this method allocates space for the output.
+    pub fn process_with_mute<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        mute: &[bool],
+        ramp_frames: usize,
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        if mute.len() != self.nbr_channels {
+            return Err(ResampleError::WrongNumberOfMaskChannels {
+                expected: self.nbr_channels,
+                actual: mute.len(),
+            });
+        }
+        let mut wave_out = self.process(wave_in, None)?;
+        let step = T::one() / T::coerce(ramp_frames.max(1) as f64);
+        for ((wave, gain), muted) in wave_out
+            .iter_mut()
+            .zip(self.mute_gains.iter_mut())
+            .zip(mute.iter())
         {
-            if !ramp {
-                self.resample_ratio = new_ratio;
+            for value in wave.iter_mut() {
+                *gain = match (*muted, ramp_frames) {
+                    (true, 0) => T::zero(),
+                    (false, 0) => T::one(),
+                    (true, _) => (*gain - step).max(T::zero()),
+                    (false, _) => (*gain + step).min(T::one()),
+                };
+                *value *= *gain;
             }
-            self.target_ratio = new_ratio;
-            Ok(())
-        } else {
-            Err(ResampleError::RatioOutOfBounds {
-                provided: new_ratio,
-                original: self.resample_ratio_original,
-                max_relative_ratio: self.max_relative_ratio,
-            })
         }
+        Ok(wave_out)
     }
 
-    fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()> {
-        let new_ratio = self.resample_ratio_original * rel_ratio;
-        self.set_resample_ratio(new_ratio, ramp)
+    /// Export the sinc table as a C header, for use in embedded ports where the table
+    /// is generated offline and stored in the firmware.
+    /// The result is a declaration of a `const float` array called `name`,
+    /// containing the coefficients of all the intermediate sincs, one sinc after the other.
+    /// Each sinc is preceded by a comment giving its index.
+    ///
+    /// The coefficients are read back from the interpolator, which takes some time for long sincs,
+    /// so this is intended to be used as a build-time or offline utility.
+    pub fn export_c_header(&self, name: &str) -> String {
+        let len = self.interpolator.len();
+        let nbr_sincs = self.interpolator.nbr_sincs();
+        let mut header = format!(
+            "const float {}[] = {{\n    /* {} sincs with {} coefficients each */\n",
+            name, nbr_sincs, len
+        );
+        // Read each coefficient by interpolating a unit impulse.
+        // The extra zero at the end is needed by the AVX interpolator.
+        let mut impulse = vec![T::zero(); len + 1];
+        for subindex in 0..nbr_sincs {
+            header.push_str(&format!("    /* sinc {} */\n   ", subindex));
+            for n in 0..len {
+                impulse[n] = T::one();
+                let value = self
+                    .interpolator
+                    .get_sinc_interpolated(&impulse, 0, subindex);
+                impulse[n] = T::zero();
+                header.push_str(&format!(" {:?}f,", value));
+            }
+            header.push('\n');
+        }
+        header.push_str("};\n");
+        header
     }
 
-    fn reset(&mut self) {
-        self.buffer
-            .iter_mut()
-            .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
-        self.channel_mask.iter_mut().for_each(|val| *val = true);
-        self.last_index = -((self.interpolator.len() / 2) as f64);
-        self.resample_ratio = self.resample_ratio_original;
-        self.target_ratio = self.resample_ratio_original;
-        self.chunk_size = self.max_chunk_size;
+    /// This is a convenience wrapper for [process](Resampler::process) that applies
+    /// a soft-knee clipper to the output, to prevent overs between the samples
+    /// from reaching the output when the signal is close to full scale.
+    /// Values below half of `clip_threshold` are not affected.
+    /// Above that, a cubic saturation curve smoothly limits the output to `clip_threshold`.
+    ///
+    /// The output is returned together with [SoftClipStats] holding the peak value
+    /// before clipping and the number of modified samples, for each channel.
+    /// Note that this method allocates space for the output.
+    pub fn process_with_soft_clip<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        clip_threshold: T,
+    ) -> ResampleResult<(Vec<Vec<T>>, SoftClipStats<T>)> {
+        let mut wave_out = self.process(wave_in, None)?;
+        let mut stats = SoftClipStats {
+            peaks: vec![T::zero(); wave_out.len()],
+            clip_events: vec![0; wave_out.len()],
+        };
+        for (chan, wave) in wave_out.iter_mut().enumerate() {
+            for value in wave.iter_mut() {
+                stats.peaks[chan] = stats.peaks[chan].max(value.abs());
+                let (clipped, modified) = soft_clip(*value, clip_threshold);
+                *value = clipped;
+                if modified {
+                    stats.clip_events[chan] += 1;
+                }
+            }
+        }
+        Ok((wave_out, stats))
     }
 
-    fn set_chunk_size(&mut self, chunksize: usize) -> ResampleResult<()> {
-        if chunksize > self.max_chunk_size || chunksize == 0 {
-            return Err(ResampleError::InvalidChunkSize {
-                max: self.max_chunk_size,
-                requested: chunksize,
-            });
+    /// This is a convenience wrapper for [process](Resampler::process) for monitoring
+    /// the quality of a signal path in real time.
+    /// The `reference` is a known-good version of the input, and is resampled
+    /// in the same way as the input, using a second set of internal buffers.
+    /// The output is returned together with [QualityMetrics] holding the
+    /// signal-to-noise ratio between the resampled reference and the output,
+    /// where any difference between them is counted as noise.
+    ///
+    /// The reference must have the same number of channels and frames as the input.
+    /// If either of them is invalid, an error is returned and neither is processed.
+    /// The buffers for the reference are allocated at the first call.
+    /// The reference follows the phase and ratio of the input stream.
+    /// At the first call, and when the input has been processed without a reference
+    /// since the previous call, the delay line of the reference is copied from the input,
+    /// since the reference for those frames is not known.
+    /// Note that this method also allocates space for both outputs.
+    pub fn process_with_reference<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        reference: &[V],
+    ) -> ResampleResult<(Vec<Vec<T>>, QualityMetrics)> {
+        // Check the reference before processing anything, so that an error
+        // leaves both resamplers in step.
+        validate_buffers(
+            reference,
+            &mut vec![Vec::<T>::new(); self.nbr_channels],
+            &vec![true; self.nbr_channels],
+            self.nbr_channels,
+            self.chunk_size,
+            0,
+            self.max_block_frames,
+        )?;
+        if self.reference_resampler.is_none() {
+            self.reference_resampler = Some(Box::new(self.try_clone()));
         }
-        self.chunk_size = chunksize;
-        Ok(())
+        let reference_resampler = self
+            .reference_resampler
+            .as_mut()
+            .expect("The reference resampler was just created");
+        if !self.reference_in_step {
+            for (reference_buf, buf) in reference_resampler.buffer.iter_mut().zip(&self.buffer) {
+                reference_buf.copy_from_slice(buf);
+            }
+        }
+        reference_resampler.last_index = self.last_index;
+        reference_resampler.resample_ratio = self.resample_ratio;
+        reference_resampler.target_ratio = self.target_ratio;
+        reference_resampler.chunk_size = self.chunk_size;
+        reference_resampler.interpolation = self.interpolation.clone();
+        reference_resampler.at_stream_start = self.at_stream_start;
+        let wave_out = self.process(wave_in, None)?;
+        let reference_out = self
+            .reference_resampler
+            .as_mut()
+            .expect("The reference resampler was just created")
+            .process(reference, None)?;
+        self.reference_in_step = true;
+
+        let snr_db = |signal: f64, noise: f64| {
+            if noise == 0.0 {
+                f64::INFINITY
+            } else {
+                10.0 * (signal / noise).log10()
+            }
+        };
+        let mut total_signal = 0.0;
+        let mut total_noise = 0.0;
+        let channel_snr_db = wave_out
+            .iter()
+            .zip(reference_out.iter())
+            .map(|(out, refr)| {
+                assert_eq!(
+                    out.len(),
+                    refr.len(),
+                    "The output and the resampled reference have different lengths"
+                );
+                let mut signal = 0.0;
+                let mut noise = 0.0;
+                for (value, expected) in out.iter().zip(refr.iter()) {
+                    let expected = expected.to_f64();
+                    signal += expected * expected;
+                    noise += (value.to_f64() - expected).powi(2);
+                }
+                total_signal += signal;
+                total_noise += noise;
+                snr_db(signal, noise)
+            })
+            .collect();
+        let metrics = QualityMetrics {
+            channel_snr_db,
+            snr_db: snr_db(total_signal, total_noise),
+        };
+        Ok((wave_out, metrics))
     }
-}
 
-impl<T> SincFixedOut<T>
-where
-    T: Sample,
-{
-    /// Create a new SincFixedOut.
+    /// This is a convenience method for processing the last frames at the end of a stream,
+    /// when fewer than `chunk_size` frames remain.
+    /// The input is padded with zeros to a full chunk and processed, and the delayed frames
+    /// are then pushed out by processing more chunks of zeros.
+    /// The output is trimmed to remove the frames that only result from the padding,
+    /// so that the output of the whole stream ends at the end of the input
+    /// and has the length of the input multiplied by the resampling ratio,
+    /// to within one frame.
     ///
-    /// Parameters are:
-    /// - `resample_ratio`: Starting ratio between output and input sample rates, must be > 0.
-    /// - `max_resample_ratio_relative`: Maximum ratio that can be set with [Resampler::set_resample_ratio] relative to `resample_ratio`, must be >= 1.0. The minimum relative ratio is the reciprocal of the maximum. For example, with `max_resample_ratio_relative` of 10.0, the ratio can be set between `resample_ratio * 10.0` and `resample_ratio / 10.0`.
-    /// - `parameters`: Parameters for interpolation, see `SincInterpolationParameters`.
-    /// - `chunk_size`: Size of output data in frames.
-    /// - `nbr_channels`: Number of channels in input/output.
-    pub fn new(
-        resample_ratio: f64,
-        max_resample_ratio_relative: f64,
-        parameters: SincInterpolationParameters,
-        chunk_size: usize,
-        nbr_channels: usize,
-    ) -> Result<Self, ResamplerConstructionError> {
-        debug!(
-            "Create new SincFixedIn, ratio: {}, chunk_size: {}, channels: {}, parameters: {:?}",
-            resample_ratio, chunk_size, nbr_channels, parameters
-        );
-        let interpolator = make_interpolator(
-            parameters.sinc_len,
-            resample_ratio,
-            parameters.f_cutoff,
-            parameters.oversampling_factor,
-            parameters.window,
-        );
+    /// At most `chunk_size` frames are used from the input.
+    /// Note that this method allocates space for both input and output.
+    pub fn process_partial_last<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        if wave_in.len() != self.nbr_channels {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: self.nbr_channels,
+                actual: wave_in.len(),
+            });
+        }
+        let frames_in = wave_in
+            .iter()
+            .map(|wave| wave.as_ref().len())
+            .min()
+            .unwrap_or_default()
+            .min(self.chunk_size);
+        // The output frames are valid as long as their positions are within the input.
+        // The end of the input is tracked relative to the start of the next chunk.
+        let mut input_end = frames_in as f64;
+        let mut positions = self.next_output_positions();
+        let mut wave_out = self.process_partial(Some(wave_in), None)?;
+        loop {
+            let valid_frames = positions.iter().filter(|pos| **pos < input_end).count();
+            if valid_frames < positions.len() {
+                let excess = positions.len() - valid_frames;
+                for wave in wave_out.iter_mut() {
+                    wave.truncate(wave.len() - excess);
+                }
+                break;
+            }
+            input_end -= self.chunk_size as f64;
+            positions = self.next_output_positions();
+            let flushed = self.process_partial(None::<&[V]>, None)?;
+            for (wave, chunk) in wave_out.iter_mut().zip(flushed.iter()) {
+                wave.extend_from_slice(chunk);
+            }
+        }
+        Ok(wave_out)
+    }
 
-        Self::new_with_interpolator(
-            resample_ratio,
-            max_resample_ratio_relative,
-            parameters.interpolation,
-            interpolator,
-            chunk_size,
-            nbr_channels,
-        )
+    /// This is a convenience method for resampling input of any length,
+    /// when the total number of output frames is known, for example the input length
+    /// multiplied by the resampling ratio.
+    /// The input is processed in chunks with [process_chunked](Resampler::process_chunked),
+    /// where the last incomplete chunk is padded with zeros.
+    /// If needed, more chunks of zeros are then processed until there are
+    /// `expected_output_len` frames, and the output is trimmed to this length.
+    ///
+    /// Note that the output is delayed by [output_delay](Resampler::output_delay) frames,
+    /// in the same way as for [process](Resampler::process).
+    /// This method allocates space for the complete output.
+    pub fn process_with_end_padding<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        expected_output_len: usize,
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        let mut wave_out = self.process_chunked(wave_in, None)?;
+        while wave_out[0].len() < expected_output_len {
+            let padding = self.process_partial(None::<&[V]>, None)?;
+            for (wave, chunk) in wave_out.iter_mut().zip(padding.iter()) {
+                wave.extend_from_slice(chunk);
+            }
+        }
+        for wave in wave_out.iter_mut() {
+            wave.truncate(expected_output_len);
+        }
+        Ok(wave_out)
     }
 
-    /// Create a new SincFixedOut using an existing Interpolator.
+    /// Resample a chunk of audio like [process](Resampler::process), but leave out the
+    /// output frames at the start of the stream that come before the first input frame.
+    /// These frames only result from the silence in the delay line of a new resampler.
+    /// With this method, the first output frame of the stream is aligned with
+    /// the first input frame, so that for example an impulse at the first input frame
+    /// appears at the first output frame.
+    /// The returned vectors hold only the valid frames, and the number of frames
+    /// is the length of the vectors.
     ///
-    /// Parameters are:
-    /// - `resample_ratio`: Starting ratio between output and input sample rates, must be > 0.
-    /// - `max_resample_ratio_relative`: Maximum ratio that can be set with [Resampler::set_resample_ratio] relative to `resample_ratio`, must be >= 1.0. The minimum relative ratio is the reciprocal of the maximum. For example, with `max_resample_ratio_relative` of 10.0, the ratio can be set between `resample_ratio` * 10.0 and `resample_ratio` / 10.0.
-    /// - `interpolation_type`: Parameters for interpolation, see `SincInterpolationParameters`.
-    /// - `interpolator`: The interpolator to use.
-    /// - `chunk_size`: Size of output data in frames.
-    /// - `nbr_channels`: Number of channels in input/output.
-    pub fn new_with_interpolator(
-        resample_ratio: f64,
-        max_resample_ratio_relative: f64,
-        interpolation_type: SincInterpolationType,
-        interpolator: Box<dyn SincInterpolator<T>>,
-        chunk_size: usize,
-        nbr_channels: usize,
-    ) -> Result<Self, ResamplerConstructionError> {
-        validate_ratios(resample_ratio, max_resample_ratio_relative)?;
-
-        let needed_input_size =
-            (chunk_size as f64 / resample_ratio).ceil() as usize + interpolator.len() / 2;
-        let buffer_channel_length = ((max_resample_ratio_relative + 1.0) * needed_input_size as f64)
-            as usize
-            + 2 * interpolator.len();
-        let buffer = vec![vec![T::zero(); buffer_channel_length]; nbr_channels];
-        let channel_mask = vec![true; nbr_channels];
-
-        Ok(SincFixedOut {
-            nbr_channels,
-            chunk_size,
-            max_chunk_size: chunk_size,
-            needed_input_size,
-            last_index: -((interpolator.len() / 2) as f64),
-            current_buffer_fill: needed_input_size,
-            resample_ratio,
-            resample_ratio_original: resample_ratio,
-            target_ratio: resample_ratio,
-            max_relative_ratio: max_resample_ratio_relative,
-            interpolator,
-            buffer,
-            interpolation: interpolation_type,
-            channel_mask,
-        })
+    /// Frames are only left out in the first call after the resampler was created or reset,
+    /// and later calls return all frames like [process](Resampler::process).
+    /// Note that this method allocates space for the output.
+    pub fn process_valid_only<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        let warm_up_frames = if self.at_stream_start {
+            self.next_output_positions()
+                .iter()
+                .filter(|pos| **pos < 0.0)
+                .count()
+        } else {
+            0
+        };
+        let mut wave_out = self.process(wave_in, active_channels_mask)?;
+        for wave in wave_out.iter_mut() {
+            wave.drain(..warm_up_frames.min(wave.len()));
+        }
+        Ok(wave_out)
     }
 
-    fn update_needed_len(&mut self) {
-        self.needed_input_size = (self.last_index as f32
-            + self.chunk_size as f32
-                / (0.5 * self.resample_ratio as f32 + 0.5 * self.target_ratio as f32)
-            + self.interpolator.len() as f32)
-            .ceil() as usize;
+    /// Get the number of samples needed in the `scratch` buffer
+    /// of [process_noalloc](SincFixedIn::process_noalloc).
+    /// This is `nbr_channels * chunk_size`.
+    pub fn scratch_len(&self) -> usize {
+        self.nbr_channels * self.chunk_size
     }
-}
 
-impl<T> Resampler<T> for SincFixedOut<T>
-where
-    T: Sample,
-{
-    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    /// Resample a chunk of audio without allocating, for systems where
+    /// allocations are not possible during processing.
+    /// All temporary storage is taken from the caller-provided buffers.
+    ///
+    /// This works like [process_into_buffer](Resampler::process_into_buffer),
+    /// but the input channels may be shorter than [input_frames_next](Resampler::input_frames_next),
+    /// like for [process_partial_into_buffer](Resampler::process_partial_into_buffer).
+    /// A short input channel is copied to the `scratch` buffer and padded with zeros.
+    /// Input channels of zero length can be used to flush the resampler at the end of a stream.
+    ///
+    /// The `scratch` buffer must hold at least `nbr_channels * chunk_size` samples,
+    /// as given by [scratch_len](SincFixedIn::scratch_len),
+    /// otherwise [ResampleError::InsufficientScratchSize] is returned.
+    /// The output channels must hold at least [output_frames_next](Resampler::output_frames_next) frames.
+    ///
+    /// Returns the number of input frames used, which is always the chunk size
+    /// like for [process_partial_into_buffer](Resampler::process_partial_into_buffer),
+    /// and the number of output frames written.
+    pub fn process_noalloc(
         &mut self,
-        wave_in: &[Vin],
-        wave_out: &mut [Vout],
-        active_channels_mask: Option<&[bool]>,
+        wave_in: &[&[T]],
+        wave_out: &mut [&mut [T]],
+        scratch: &mut [T],
     ) -> ResampleResult<(usize, usize)> {
-        if let Some(mask) = active_channels_mask {
-            self.channel_mask.copy_from_slice(mask);
-        } else {
-            update_mask_from_buffers(&mut self.channel_mask);
-        };
+        let scratch_len = self.scratch_len();
+        if scratch.len() < scratch_len {
+            return Err(ResampleError::InsufficientScratchSize {
+                expected: scratch_len,
+                actual: scratch.len(),
+            });
+        }
+        self.channel_mask.iter_mut().for_each(|val| *val = true);
+        let needed_len = self.calc_needed_len();
+        if let Some(max) = self.max_block_frames {
+            let requested = self.chunk_size.max(needed_len);
+            if requested > max {
+                return Err(ResampleError::BlockTooLarge { max, requested });
+            }
+        }
+        // The input may be short, and only the output length is validated.
+        self.validate_chunk(wave_in, wave_out, 0, needed_len, None)?;
 
-        validate_buffers(
-            wave_in,
+        let chunk_size = self.chunk_size;
+        for (wave, block) in wave_in.iter().zip(scratch.chunks_exact_mut(chunk_size)) {
+            if wave.len() < chunk_size {
+                block[..wave.len()].copy_from_slice(wave);
+                block[wave.len()..]
+                    .iter_mut()
+                    .for_each(|val| *val = T::zero());
+            }
+        }
+        let scratch = &*scratch;
+        let n = self.resample_chunk(
+            |chan| {
+                if wave_in[chan].len() >= chunk_size {
+                    wave_in[chan]
+                } else {
+                    &scratch[chan * chunk_size..(chan + 1) * chunk_size]
+                }
+            },
             wave_out,
-            &self.channel_mask,
-            self.nbr_channels,
-            self.needed_input_size,
+        );
+        Ok((self.chunk_size, n))
+    }
+
+    /// Resample a pair of mono streams, for example from two microphones that must be
+    /// processed identically. The resampler must have been created for two channels.
+    /// This gives the same result and updates the state in the same way as calling
+    /// [process](Resampler::process) with the two streams as channels,
+    /// but the interpolator reads each sinc coefficient once for both streams,
+    /// see [SincInterpolator::get_sinc_interpolated_pair].
+    ///
+    /// The output is returned as a tuple with one vector per stream.
+    pub fn process_pair(&mut self, a: &[T], b: &[T]) -> ResampleResult<(Vec<T>, Vec<T>)> {
+        let needed_len = self.calc_needed_len();
+        let mut wave_out = vec![vec![T::zero(); needed_len]; 2];
+        self.channel_mask.iter_mut().for_each(|val| *val = true);
+        self.validate_chunk(
+            &[a, b],
+            &mut wave_out,
             self.chunk_size,
+            needed_len,
+            self.max_block_frames,
         )?;
-        let sinc_len = self.interpolator.len();
-        let oversampling_factor = self.interpolator.nbr_sincs();
 
-        for buf in self.buffer.iter_mut() {
-            buf.copy_within(
-                self.current_buffer_fill..self.current_buffer_fill + 2 * sinc_len,
-                0,
+        self.begin_chunk(|chan| [a, b][chan]);
+        let oversampling_factor = self.interpolator.nbr_sincs();
+        let mut idx = self.last_index;
+        let mut nbr_frames = 0;
+        for next_idx in self.index_steps() {
+            idx = next_idx;
+            let points = InterpolationPoints::new(&self.interpolation, idx, oversampling_factor);
+            let (mut value_a, mut value_b) = points.interpolate_pair(
+                self.interpolator.as_ref(),
+                &self.buffer[0],
+                &self.buffer[1],
             );
+            if let (Some(blend), Some(short_interpolator)) =
+                (self.transient_blend(idx), &self.transient_interpolator)
+            {
+                value_a = blend.apply(short_interpolator.as_ref(), &self.buffer[0], value_a);
+                value_b = blend.apply(short_interpolator.as_ref(), &self.buffer[1], value_b);
+            }
+            wave_out[0][nbr_frames] = value_a;
+            wave_out[1][nbr_frames] = value_b;
+            nbr_frames += 1;
         }
-        self.current_buffer_fill = self.needed_input_size;
+        self.end_chunk(idx);
+        self.finish_output(&mut wave_out, None, nbr_frames);
+        let mut wave_out = wave_out.into_iter();
+        let (mut out_a, mut out_b) = (wave_out.next().unwrap(), wave_out.next().unwrap());
+        out_a.truncate(nbr_frames);
+        out_b.truncate(nbr_frames);
+        Ok((out_a, out_b))
+    }
 
-        for (chan, active) in self.channel_mask.iter().enumerate() {
-            if *active {
-                debug_assert!(self.chunk_size <= wave_out[chan].as_mut().len());
-                self.buffer[chan][2 * sinc_len..2 * sinc_len + self.needed_input_size]
-                    .copy_from_slice(&wave_in[chan].as_ref()[..self.needed_input_size]);
+    /// Resample a chunk of audio and mix the channels with a gain matrix,
+    /// for example for decoding ambisonics to a loudspeaker layout.
+    /// The gain for the routing from input channel `in_ch` to output channel `out_ch`
+    /// is given by `matrix[out_ch][in_ch]`, and the output has one channel per row of the matrix.
+    /// Each row must have one gain per input channel,
+    /// otherwise [ResampleError::WrongNumberOfMatrixColumns] is returned.
+    ///
+    /// The mixing is done for each output frame directly after the interpolation,
+    /// while the interpolated values of all input channels are still in the cache.
+    /// Apart from the mixing, the chunk is processed like with [process](Resampler::process),
+    /// including the transient mode and the metrics.
+    /// When capturing spectra, they are captured for the output channels of the matrix.
+    /// Note that this method allocates space for the output.
+    pub fn process_with_gain_matrix<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        matrix: &[Vec<T>],
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        for (row, gains) in matrix.iter().enumerate() {
+            if gains.len() != self.nbr_channels {
+                return Err(ResampleError::WrongNumberOfMatrixColumns {
+                    row,
+                    expected: self.nbr_channels,
+                    actual: gains.len(),
+                });
             }
         }
+        self.channel_mask.iter_mut().for_each(|val| *val = true);
+        // The output is allocated here, and may have a different number of channels.
+        self.validate_chunk(
+            wave_in,
+            &mut vec![Vec::<T>::new(); self.nbr_channels],
+            self.chunk_size,
+            0,
+            self.max_block_frames,
+        )?;
 
+        let needed_len = self.calc_needed_len();
+        let mut wave_out = (0..matrix.len())
+            .map(|_| Vec::with_capacity(needed_len))
+            .collect::<Vec<_>>();
+        self.begin_chunk(|chan| wave_in[chan].as_ref());
+        let oversampling_factor = self.interpolator.nbr_sincs();
+        let mut frame = vec![T::zero(); self.nbr_channels];
         let mut idx = self.last_index;
-        let mut t_ratio = 1.0 / self.resample_ratio;
-        let t_ratio_end = 1.0 / self.target_ratio;
-        let t_ratio_increment = (t_ratio_end - t_ratio) / self.chunk_size as f64;
-
-        match self.interpolation {
-            SincInterpolationType::Cubic => {
-                let mut points = [T::zero(); 4];
-                let mut nearest = [(0isize, 0isize); 4];
-                for frame in 0..self.chunk_size {
-                    t_ratio += t_ratio_increment;
-                    idx += t_ratio;
-                    get_nearest_times_4(idx, oversampling_factor as isize, &mut nearest);
-                    let frac = idx * oversampling_factor as f64
-                        - (idx * oversampling_factor as f64).floor();
-                    let frac_offset = T::coerce(frac);
-                    for (chan, active) in self.channel_mask.iter().enumerate() {
-                        if *active {
-                            let buf = &self.buffer[chan];
-                            for (n, p) in nearest.iter().zip(points.iter_mut()) {
-                                *p = self.interpolator.get_sinc_interpolated(
-                                    buf,
-                                    (n.0 + 2 * sinc_len as isize) as usize,
-                                    n.1 as usize,
-                                );
-                            }
-                            wave_out[chan].as_mut()[frame] = interp_cubic(frac_offset, &points);
-                        }
-                    }
-                }
-            }
-            SincInterpolationType::Quadratic => {
-                let mut points = [T::zero(); 3];
-                let mut nearest = [(0isize, 0isize); 3];
-                for frame in 0..self.chunk_size {
-                    t_ratio += t_ratio_increment;
-                    idx += t_ratio;
-                    get_nearest_times_3(idx, oversampling_factor as isize, &mut nearest);
-                    let frac = idx * oversampling_factor as f64
-                        - (idx * oversampling_factor as f64).floor();
-                    let frac_offset = T::coerce(frac);
-                    for (chan, active) in self.channel_mask.iter().enumerate() {
-                        if *active {
-                            let buf = &self.buffer[chan];
-                            for (n, p) in nearest.iter().zip(points.iter_mut()) {
-                                *p = self.interpolator.get_sinc_interpolated(
-                                    buf,
-                                    (n.0 + 2 * sinc_len as isize) as usize,
-                                    n.1 as usize,
-                                );
-                            }
-                            wave_out[chan].as_mut()[frame] = interp_quad(frac_offset, &points);
-                        }
-                    }
-                }
-            }
-            SincInterpolationType::Linear => {
-                let mut points = [T::zero(); 2];
-                let mut nearest = [(0isize, 0isize); 2];
-                for frame in 0..self.chunk_size {
-                    t_ratio += t_ratio_increment;
-                    idx += t_ratio;
-                    get_nearest_times_2(idx, oversampling_factor as isize, &mut nearest);
-                    let frac = idx * oversampling_factor as f64
-                        - (idx * oversampling_factor as f64).floor();
-                    let frac_offset = T::coerce(frac);
-                    for (chan, active) in self.channel_mask.iter().enumerate() {
-                        if *active {
-                            let buf = &self.buffer[chan];
-                            for (n, p) in nearest.iter().zip(points.iter_mut()) {
-                                *p = self.interpolator.get_sinc_interpolated(
-                                    buf,
-                                    (n.0 + 2 * sinc_len as isize) as usize,
-                                    n.1 as usize,
-                                );
-                            }
-                            wave_out[chan].as_mut()[frame] = interp_lin(frac_offset, &points);
-                        }
-                    }
-                }
+        for next_idx in self.index_steps() {
+            idx = next_idx;
+            let points = InterpolationPoints::new(&self.interpolation, idx, oversampling_factor);
+            let blend = self.transient_blend(idx);
+            for (value, buf) in frame.iter_mut().zip(self.buffer.iter()) {
+                *value = self.interpolate_frame(&points, &blend, buf);
             }
-            SincInterpolationType::Nearest => {
-                let mut point;
-                let mut nearest;
-                for frame in 0..self.chunk_size {
-                    t_ratio += t_ratio_increment;
-                    idx += t_ratio;
-                    nearest = get_nearest_time(idx, oversampling_factor as isize);
-                    for (chan, active) in self.channel_mask.iter().enumerate() {
-                        if *active {
-                            let buf = &self.buffer[chan];
-                            point = self.interpolator.get_sinc_interpolated(
-                                buf,
-                                (nearest.0 + 2 * sinc_len as isize) as usize,
-                                nearest.1 as usize,
-                            );
-                            wave_out[chan].as_mut()[frame] = point;
-                        }
-                    }
-                }
+            for (wave, gains) in wave_out.iter_mut().zip(matrix.iter()) {
+                wave.push(
+                    gains
+                        .iter()
+                        .zip(frame.iter())
+                        .fold(T::zero(), |acc, (gain, value)| acc + *gain * *value),
+                );
             }
         }
-
-        // Store last index for next iteration.
-        let input_frames_used = self.needed_input_size;
-        self.last_index = idx - self.current_buffer_fill as f64;
-        self.resample_ratio = self.target_ratio;
-        self.update_needed_len();
-        trace!(
-            "Resampling channels {:?}, {} frames in, {} frames out. Next needed length: {} frames, last index {}",
-            active_channels_mask,
-            self.current_buffer_fill,
-            self.chunk_size,
-            self.needed_input_size,
-            self.last_index
-        );
-        Ok((input_frames_used, self.chunk_size))
+        self.end_chunk(idx);
+        let nbr_frames = wave_out.first().map(|wave| wave.len()).unwrap_or(0);
+        self.finish_output(&mut wave_out, Some(&vec![true; matrix.len()]), nbr_frames);
+        Ok(wave_out)
     }
 
-    fn input_frames_max(&self) -> usize {
-        (self.max_chunk_size as f64 / self.resample_ratio_original * self.max_relative_ratio).ceil()
-            as usize
-            + 2
-            + self.interpolator.len() / 2
-    }
+    /// Resample a chunk of audio together with a control signal, such as an envelope,
+    /// that shares the timing of the audio.
+    /// The audio is resampled like with [process](Resampler::process),
+    /// while the control signal is resampled with linear interpolation
+    /// at exactly the same time points as the audio, including the delay through the filter.
+    /// Linear interpolation is much cheaper than the sinc interpolation used for the audio,
+    /// and is suitable for slowly varying signals.
+    ///
+    /// The control signal must contain at least as many frames as the audio input,
+    /// otherwise [ResampleError::InsufficientSidechainLength] is returned without processing any data.
+    /// The resampler keeps a history of the control signal,
+    /// so when a control signal is used it should be provided on every call.
+    /// The output is returned as a tuple of `(audio_out, control_out)`,
+    /// where the control output has the same number of frames as the audio output.
+    pub fn process_with_sidechain<V: AsRef<[T]>>(
+        &mut self,
+        audio_in: &[V],
+        control_in: &[T],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(Vec<Vec<T>>, Vec<T>)> {
+        if control_in.len() < self.chunk_size {
+            return Err(ResampleError::InsufficientSidechainLength {
+                expected: self.chunk_size,
+                actual: control_in.len(),
+            });
+        }
+        let positions = self.next_output_positions();
+        let audio_out = self.process(audio_in, active_channels_mask)?;
 
-    fn input_frames_next(&self) -> usize {
-        self.needed_input_size
+        let sinc_len = self.interpolator.len();
+        self.sidechain_buffer
+            .copy_within(self.chunk_size..self.chunk_size + 2 * sinc_len, 0);
+        self.sidechain_buffer[2 * sinc_len..2 * sinc_len + self.chunk_size]
+            .copy_from_slice(&control_in[..self.chunk_size]);
+        let control_out = positions
+            .iter()
+            .map(|pos| {
+                let buffer_pos = pos + (2 * sinc_len) as f64;
+                let index = buffer_pos.floor() as usize;
+                let frac = T::coerce(buffer_pos - buffer_pos.floor());
+                let yvals = [
+                    self.sidechain_buffer[index],
+                    self.sidechain_buffer[index + 1],
+                ];
+                interp_lin(frac, &yvals)
+            })
+            .collect();
+        Ok((audio_out, control_out))
     }
 
-    fn nbr_channels(&self) -> usize {
-        self.nbr_channels
+    /// Get a summary of the time spent per call to
+    /// [process_into_buffer](Resampler::process_into_buffer) since creation or the last
+    /// call to [set_profiling_buckets](SincFixedIn::set_profiling_buckets).
+    /// Only available with the `profiling` feature.
+    #[cfg(feature = "profiling")]
+    pub fn profiling_report(&self) -> ProfilingReport {
+        self.profiler.report()
     }
 
-    fn output_frames_max(&self) -> usize {
-        self.max_chunk_size
+    /// Replace the histogram buckets used for profiling, and clear any recorded data.
+    /// The values are the upper limits of the buckets, in microseconds.
+    /// Call this right after creating the resampler, since it allocates.
+    /// The default buckets are spaced logarithmically from 1 us to 1 s.
+    /// Only available with the `profiling` feature.
+    #[cfg(feature = "profiling")]
+    pub fn set_profiling_buckets(&mut self, bucket_limits_us: &[f64]) {
+        self.profiler = LatencyHistogram::new(bucket_limits_us);
     }
 
-    fn output_frames_next(&self) -> usize {
-        self.chunk_size
+    /// Choose to discard the tail at the end of a stream instead of flushing it.
+    /// This is meant for real-time streaming, where the stream is just stopped.
+    /// When enabled, [flush_into_buffer](Resampler::flush_into_buffer) writes no frames
+    /// and marks the resampler as drained, and the frames that would have been flushed
+    /// are added to the [discarded_frame_count](SincFixedIn::discarded_frame_count).
+    /// This is disabled by default.
+    pub fn set_discard_tail(&mut self, discard: bool) {
+        self.discard_tail = discard;
     }
 
-    fn output_delay(&self) -> usize {
-        (self.interpolator.len() as f64 * self.resample_ratio / 2.0) as usize
+    /// Enable or disable the transient mode, which reduces the pre-ringing of the filter
+    /// on percussive material.
+    /// In transient mode, the input is searched for transients, which are frames
+    /// that are much louder than the frames preceding them.
+    /// Output frames near a transient are calculated with a shorter sinc filter,
+    /// with a quarter of the length of the normal one,
+    /// that rings for a shorter time before and after the transient.
+    /// The output is crossfaded between the two filters when a transient
+    /// enters and leaves the normal filter, and steady-state material
+    /// is resampled with the normal filter only.
+    ///
+    /// Both filters are linear phase with the same delay, so that the crossfades don't cause
+    /// any phase shifts. The short filter has a wider transition band,
+    /// and lets through more aliasing near transients.
+    /// The sinc table of the short filter is calculated when the mode is first enabled,
+    /// and is kept until the mode is disabled. This is disabled by default.
+    pub fn set_transient_mode(&mut self, enable: bool) {
+        if !enable {
+            self.transient_interpolator = None;
+            self.transient_onsets = Vec::new();
+            return;
+        }
+        if self.transient_interpolator.is_some() {
+            return;
+        }
+        let sinc_len = self.interpolator.len();
+        let short_len = (8 * ((sinc_len / 4 + 7) / 8)).min(sinc_len);
+        let window = self.window.unwrap_or(WindowFunction::BlackmanHarris2);
+        let interpolator = make_interpolator(
+            short_len,
+            self.resample_ratio_original,
+            calculate_cutoff(short_len, window),
+            self.interpolator.nbr_sincs(),
+            window,
+        );
+        self.transient_interpolator = Some(Arc::from(interpolator));
+        self.transient_onsets = Vec::with_capacity(self.chunk_size + 2 * sinc_len);
     }
 
-    fn set_resample_ratio(&mut self, new_ratio: f64, ramp: bool) -> ResampleResult<()> {
-        trace!("Change resample ratio to {}", new_ratio);
-        if (new_ratio / self.resample_ratio_original >= 1.0 / self.max_relative_ratio)
-            && (new_ratio / self.resample_ratio_original <= self.max_relative_ratio)
-        {
-            if !ramp {
-                self.resample_ratio = new_ratio;
-            }
-            self.target_ratio = new_ratio;
+    /// Get the number of output frames discarded by flushing with
+    /// [set_discard_tail](SincFixedIn::set_discard_tail) enabled,
+    /// since creation or the last reset.
+    pub fn discarded_frame_count(&self) -> usize {
+        self.discarded_frames
+    }
 
-            self.update_needed_len();
-            Ok(())
+    /// Enable or disable capturing the magnitude spectrum of the output of each call to
+    /// [process_into_buffer](Resampler::process_into_buffer), for visualization.
+    /// When enabled, the output of each active channel is zero-padded to an FFT length of
+    /// [output_frames_max](Resampler::output_frames_max) rounded up to the next power of two,
+    /// and transformed. The FFT length is the same for every block,
+    /// so that the bins are at the same frequencies even though the number of output frames varies.
+    /// Enabling allocates the FFT and the storage for the spectra, and should be done
+    /// before processing starts. Capturing is disabled by default.
+    /// Only available with the `fft_resampler` feature.
+    #[cfg(feature = "fft_resampler")]
+    pub fn set_spectrum_capture(&mut self, enable: bool) {
+        if enable {
+            let analyzer = SpectrumAnalyzer::new(self.output_frames_max().next_power_of_two());
+            self.spectra = vec![vec![0.0; analyzer.nbr_bins()]; self.nbr_channels];
+            self.spectrum_analyzer = Some(analyzer);
         } else {
-            Err(ResampleError::RatioOutOfBounds {
-                provided: new_ratio,
-                original: self.resample_ratio_original,
-                max_relative_ratio: self.max_relative_ratio,
-            })
+            self.spectra = vec![Vec::new(); self.nbr_channels];
+            self.spectrum_analyzer = None;
         }
     }
 
-    fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()> {
+    /// Get the magnitude spectra of the output of the last processed block,
+    /// one vector per output channel.
+    /// For an FFT length `N`, see [set_spectrum_capture](SincFixedIn::set_spectrum_capture),
+    /// the spectrum has `N / 2 + 1` bins, where bin `k` is at `k * fs_out / N`
+    /// for an output sample rate of `fs_out`. The values are the linear magnitudes of the FFT.
+    /// The vectors are empty unless capturing is enabled.
+    /// Only available with the `fft_resampler` feature.
+    #[cfg(feature = "fft_resampler")]
+    pub fn last_block_spectrum(&self) -> Vec<Vec<f32>> {
+        self.spectra.clone()
+    }
+
+    /// Resample a chunk of audio like [process](Resampler::process), and calculate
+    /// the magnitude spectrum of each output channel, for example for a live spectrum monitor.
+    /// The output of each channel is zero-padded or truncated to `fft_size` frames,
+    /// and transformed with an FFT of that length.
+    /// The spectra have `fft_size / 2 + 1` bins, where bin `k` is at `k * fs_out / fft_size`
+    /// for an output sample rate of `fs_out`.
+    ///
+    /// The values are in dBFS, where 0 dB is the level of a full-scale sine
+    /// that fills the whole block. Silence gives negative infinity.
+    /// Returns a tuple with the output and the spectra, with one vector per channel in each.
+    ///
+    /// This method plans a new FFT and allocates on each call. For continuous monitoring,
+    /// see [set_spectrum_capture](SincFixedIn::set_spectrum_capture).
+    /// Only available with the `fft_resampler` feature.
+    #[cfg(feature = "fft_resampler")]
+    #[allow(clippy::type_complexity)]
+    pub fn process_with_spectrum<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        fft_size: usize,
+    ) -> ResampleResult<(Vec<Vec<T>>, Vec<Vec<T>>)> {
+        let wave_out = self.process(wave_in, None)?;
+        let mut analyzer = SpectrumAnalyzer::<T>::new(fft_size);
+        let mut magnitudes = vec![0.0; analyzer.nbr_bins()];
+        let spectra = wave_out
+            .iter()
+            .map(|wave| {
+                analyzer.magnitudes(wave, &mut magnitudes);
+                // A full-scale sine of L frames gives a peak of L / 2.
+                let full_scale = 0.5 * wave.len().min(fft_size) as f64;
+                magnitudes
+                    .iter()
+                    .map(|value| T::coerce(20.0 * (*value as f64 / full_scale).log10()))
+                    .collect()
+            })
+            .collect();
+        Ok((wave_out, spectra))
+    }
+
+    /// Resample a chunk of audio like [process](Resampler::process), and describe
+    /// how each output frame was interpolated, for example for visualizing the filter.
+    /// Returns a tuple with the output, and one [InterpolationDebugFrame] per output frame.
+    ///
+    /// The coefficients are read from the sinc tables by interpolating unit impulses,
+    /// and are cached per table for the duration of the call.
+    /// This is slow, and is meant for analysis only.
+    /// Only available with the `debug-interp` feature.
+    #[cfg(feature = "debug-interp")]
+    #[allow(clippy::type_complexity)]
+    pub fn process_with_interp_debug<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+    ) -> ResampleResult<(Vec<Vec<T>>, Vec<InterpolationDebugFrame<T>>)> {
+        let indices = self.next_indices();
+        let wave_out = self.process(wave_in, None)?;
+        let sinc_len = self.interpolator.len();
+        let oversampling_factor = self.interpolator.nbr_sincs();
+        let mut sincs: Vec<Option<Vec<T>>> = vec![None; oversampling_factor];
+        let mut impulse = vec![T::zero(); sinc_len + 1];
+        let mut frames = Vec::with_capacity(indices.len());
+        for (n, idx) in indices.iter().enumerate() {
+            let weighted_points =
+                InterpolationPoints::<T>::new(&self.interpolation, *idx, oversampling_factor)
+                    .weights();
+            let phase_int = idx.floor() as isize;
+            let mut phase_coefficients = vec![T::zero(); sinc_len + 2];
+            for ((index, subindex), weight) in weighted_points.iter() {
+                let sinc = sincs[*subindex as usize].get_or_insert_with(|| {
+                    // Interpolating a unit impulse at position k gives the k:th value of the sinc.
+                    (0..sinc_len)
+                        .map(|k| {
+                            impulse[k] = T::one();
+                            let value = self.interpolator.get_sinc_interpolated(
+                                &impulse,
+                                0,
+                                *subindex as usize,
+                            );
+                            impulse[k] = T::zero();
+                            value
+                        })
+                        .collect()
+                });
+                let start = (index - phase_int + 1) as usize;
+                for (coeff, value) in phase_coefficients[start..start + sinc_len]
+                    .iter_mut()
+                    .zip(sinc.iter())
+                {
+                    *coeff += *weight * *value;
+                }
+            }
+            frames.push(InterpolationDebugFrame {
+                phase_int,
+                phase_frac: T::coerce(idx - idx.floor()),
+                phase_coefficients,
+                output_sample: wave_out[0][n],
+            });
+        }
+        Ok((wave_out, frames))
+    }
+
+    /// Helper function. Add the time since `start` to the metrics, and to the profiling histogram.
+    #[cfg(any(feature = "profiling", feature = "prometheus"))]
+    fn record_duration(&mut self, start: std::time::Instant) {
+        let elapsed = start.elapsed();
+        self.metrics.record_duration(elapsed);
+        #[cfg(feature = "profiling")]
+        self.profiler.record(elapsed);
+    }
+
+    /// Get the counters for processed frames, processing time and rejected calls
+    /// since creation or the last reset.
+    pub fn metrics(&self) -> ResamplerMetrics {
+        self.metrics.clone()
+    }
+
+    /// Render the current [metrics](SincFixedIn::metrics) in the Prometheus text format,
+    /// see [ResamplerMetrics::export_prometheus].
+    /// Only available with the `prometheus` feature.
+    #[cfg(feature = "prometheus")]
+    pub fn export_prometheus(&self) -> String {
+        self.metrics.export_prometheus()
+    }
+}
+
+impl<T> Resampler<T> for SincFixedIn<T>
+where
+    T: Sample,
+{
+    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+        &mut self,
+        wave_in: &[Vin],
+        wave_out: &mut [Vout],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)> {
+        if let Some(mask) = active_channels_mask {
+            self.channel_mask.copy_from_slice(mask);
+        } else {
+            update_mask_from_buffers(&mut self.channel_mask);
+        };
+
+        // Set length to chunksize*ratio plus a safety margin of 10 elements.
+        let needed_len = self.calc_needed_len();
+
+        self.validate_chunk(
+            wave_in,
+            wave_out,
+            self.chunk_size,
+            needed_len,
+            self.max_block_frames,
+        )?;
+
+        let n = self.resample_chunk(|chan| wave_in[chan].as_ref(), wave_out);
+        trace!(
+            "Resampling channels {:?}, {} frames in, {} frames out",
+            active_channels_mask,
+            self.chunk_size,
+            n,
+        );
+        Ok((self.chunk_size, n))
+    }
+
+    fn output_frames_max(&self) -> usize {
+        // Set length to chunksize*ratio plus a safety margin of 10 elements.
+        (self.max_chunk_size as f64 * self.resample_ratio_original * self.max_relative_ratio + 10.0)
+            as usize
+    }
+
+    fn output_frames_next(&self) -> usize {
+        self.calc_needed_len()
+    }
+
+    fn output_delay(&self) -> usize {
+        (self.interpolator.len() as f64 * self.resample_ratio / 2.0) as usize
+    }
+
+    fn nbr_channels(&self) -> usize {
+        self.nbr_channels
+    }
+
+    fn input_frames_max(&self) -> usize {
+        self.max_chunk_size
+    }
+
+    fn input_frames_next(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn set_resample_ratio(&mut self, new_ratio: f64, ramp: bool) -> ResampleResult<()> {
+        trace!("Change resample ratio to {}", new_ratio);
+        let new_ratio = bounded_ratio(
+            new_ratio,
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+            self.overflow_policy,
+        )?;
+        if !ramp {
+            self.resample_ratio = new_ratio;
+        }
+        self.target_ratio = new_ratio;
+        Ok(())
+    }
+
+    fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()> {
         let new_ratio = self.resample_ratio_original * rel_ratio;
         self.set_resample_ratio(new_ratio, ramp)
     }
 
+    fn target_ratio(&self) -> f64 {
+        self.target_ratio
+    }
+
+    fn flush_into_buffer<Vout: AsMut<[T]>>(
+        &mut self,
+        wave_out: &mut [Vout],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize> {
+        if self.discard_tail {
+            self.discarded_frames += self.flush_remaining.unwrap_or_else(|| self.output_delay());
+            self.flush_remaining = Some(0);
+            return Ok(0);
+        }
+        flush_delayed_frames(self, wave_out, active_channels_mask)
+    }
+
+    fn flush_remaining(&self) -> Option<usize> {
+        self.flush_remaining
+    }
+
+    fn set_flush_remaining(&mut self, remaining: Option<usize>) {
+        self.flush_remaining = remaining;
+    }
+
+    fn output_schedule(&self, nblocks: usize) -> Vec<usize> {
+        stepping_schedule(
+            self.last_index,
+            self.resample_ratio,
+            self.target_ratio,
+            self.chunk_size,
+            self.interpolator.len(),
+            nblocks,
+        )
+    }
+
+    fn quality_info(&self) -> QualityInfo {
+        sinc_quality_info(self.interpolator.as_ref(), self.window, self.output_delay())
+    }
+
+    fn estimated_mac_per_block(&self) -> u64 {
+        sinc_mac_per_block(
+            self.interpolator.len(),
+            &self.interpolation,
+            self.output_frames_max(),
+            self.nbr_channels,
+        )
+    }
+
+    fn set_ratio_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    fn set_max_block_frames(&mut self, max_frames: Option<usize>) {
+        self.max_block_frames = max_frames;
+    }
+
+    fn set_clip_ceiling(&mut self, ceiling: Option<T>) {
+        self.clip_ceiling = ceiling;
+    }
+
+    fn clipped_sample_count(&self) -> usize {
+        self.clipped_samples
+    }
+
     fn reset(&mut self) {
+        self.clipped_samples = 0;
+        self.flush_remaining = None;
+        self.discarded_frames = 0;
+        self.mute_gains.iter_mut().for_each(|gain| *gain = T::one());
+        self.metrics.clear();
+        if let Some(reference) = self.reference_resampler.as_mut() {
+            reference.reset();
+        }
+        self.reference_in_step = false;
         self.buffer
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
-
+        self.sidechain_buffer
+            .iter_mut()
+            .for_each(|s| *s = T::zero());
+        self.channel_mask.iter_mut().for_each(|val| *val = true);
+        self.last_index = -((self.interpolator.len() / 2) as f64);
         self.resample_ratio = self.resample_ratio_original;
         self.target_ratio = self.resample_ratio_original;
-        self.last_index = -((self.interpolator.len() / 2) as f64);
         self.chunk_size = self.max_chunk_size;
-        self.update_needed_len();
-        self.current_buffer_fill = self.needed_input_size;
-        self.channel_mask.iter_mut().for_each(|val| *val = true);
+        self.at_stream_start = true;
     }
 
     fn set_chunk_size(&mut self, chunksize: usize) -> ResampleResult<()> {
@@ -901,511 +2441,2456 @@ where
             });
         }
         self.chunk_size = chunksize;
-        self.update_needed_len();
         Ok(())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{interp_cubic, interp_lin};
-    use crate::Resampler;
-    use crate::SincInterpolationParameters;
-    use crate::SincInterpolationType;
-    use crate::WindowFunction;
-    use crate::{check_output, check_ratio};
-    use crate::{SincFixedIn, SincFixedOut};
-    use rand::Rng;
-    use test_log::test;
+impl<T> SincFixedOut<T>
+where
+    T: Sample,
+{
+    /// Create a new SincFixedOut.
+    ///
+    /// Parameters are:
+    /// - `resample_ratio`: Starting ratio between output and input sample rates, must be > 0.
+    /// - `max_resample_ratio_relative`: Maximum ratio that can be set with [Resampler::set_resample_ratio] relative to `resample_ratio`, must be >= 1.0. The minimum relative ratio is the reciprocal of the maximum. For example, with `max_resample_ratio_relative` of 10.0, the ratio can be set between `resample_ratio * 10.0` and `resample_ratio / 10.0`.
+    /// - `parameters`: Parameters for interpolation, see `SincInterpolationParameters`.
+    /// - `chunk_size`: Size of output data in frames.
+    /// - `nbr_channels`: Number of channels in input/output.
+    pub fn new(
+        resample_ratio: f64,
+        max_resample_ratio_relative: f64,
+        parameters: SincInterpolationParameters,
+        chunk_size: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        debug!(
+            "Create new SincFixedOut, ratio: {}, chunk_size: {}, channels: {}, parameters: {:?}",
+            resample_ratio, chunk_size, nbr_channels, parameters
+        );
+        construction_span!(
+            "SincFixedOut::new",
+            channels = nbr_channels,
+            ratio = resample_ratio,
+            window = ?parameters.window,
+            sinc_len = parameters.sinc_len,
+            f_cutoff = parameters.f_cutoff,
+        );
+        let interpolator = make_interpolator(
+            parameters.sinc_len,
+            resample_ratio,
+            parameters.f_cutoff,
+            parameters.oversampling_factor,
+            parameters.window,
+        );
+
+        let mut resampler = Self::new_with_interpolator(
+            resample_ratio,
+            max_resample_ratio_relative,
+            parameters.interpolation,
+            interpolator,
+            chunk_size,
+            nbr_channels,
+        )?;
+        resampler.window = Some(parameters.window);
+        Ok(resampler)
+    }
+
+    /// Create a new SincFixedOut using an existing Interpolator.
+    ///
+    /// Parameters are:
+    /// - `resample_ratio`: Starting ratio between output and input sample rates, must be > 0.
+    /// - `max_resample_ratio_relative`: Maximum ratio that can be set with [Resampler::set_resample_ratio] relative to `resample_ratio`, must be >= 1.0. The minimum relative ratio is the reciprocal of the maximum. For example, with `max_resample_ratio_relative` of 10.0, the ratio can be set between `resample_ratio` * 10.0 and `resample_ratio` / 10.0.
+    /// - `interpolation_type`: Parameters for interpolation, see `SincInterpolationParameters`.
+    /// - `interpolator`: The interpolator to use.
+    /// - `chunk_size`: Size of output data in frames.
+    /// - `nbr_channels`: Number of channels in input/output.
+    pub fn new_with_interpolator(
+        resample_ratio: f64,
+        max_resample_ratio_relative: f64,
+        interpolation_type: SincInterpolationType,
+        interpolator: Box<dyn SincInterpolator<T>>,
+        chunk_size: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        Self::new_with_shared_interpolator(
+            resample_ratio,
+            max_resample_ratio_relative,
+            interpolation_type,
+            Arc::from(interpolator),
+            chunk_size,
+            nbr_channels,
+        )
+    }
+
+    /// Create a new resampler using an interpolator that may be shared with other resamplers.
+    fn new_with_shared_interpolator(
+        resample_ratio: f64,
+        max_resample_ratio_relative: f64,
+        interpolation_type: SincInterpolationType,
+        interpolator: Arc<dyn SincInterpolator<T>>,
+        chunk_size: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        validate_ratios(resample_ratio, max_resample_ratio_relative)?;
+
+        let needed_input_size =
+            (chunk_size as f64 / resample_ratio).ceil() as usize + interpolator.len() / 2;
+        let buffer_channel_length = ((max_resample_ratio_relative + 1.0) * needed_input_size as f64)
+            as usize
+            + 2 * interpolator.len();
+        let buffer = vec![vec![T::zero(); buffer_channel_length]; nbr_channels];
+        let channel_mask = vec![true; nbr_channels];
+
+        Ok(SincFixedOut {
+            nbr_channels,
+            chunk_size,
+            max_chunk_size: chunk_size,
+            needed_input_size,
+            last_index: -((interpolator.len() / 2) as f64),
+            current_buffer_fill: needed_input_size,
+            resample_ratio,
+            resample_ratio_original: resample_ratio,
+            target_ratio: resample_ratio,
+            max_relative_ratio: max_resample_ratio_relative,
+            interpolator,
+            buffer,
+            interpolation: interpolation_type,
+            window: None,
+            channel_mask,
+            max_block_frames: None,
+            overflow_policy: OverflowPolicy::Error,
+            clip_ceiling: None,
+            clipped_samples: 0,
+            flush_remaining: None,
+        })
+    }
+
+    /// Create a new resampler that shares the sinc interpolation filters with this one.
+    /// This avoids recalculating the filters, and only allocates the internal buffers,
+    /// which is O(sinc_len × channels) instead of O(oversampling_factor × sinc_len).
+    ///
+    /// The new resampler is in the same state as a newly created one, using the ratio
+    /// and chunk size given when this resampler was created.
+    /// The shared filters are never modified, so changing the ratio or any other setting
+    /// of the clone does not affect the original, and vice versa.
+    pub fn try_clone(&self) -> SincFixedOut<T> {
+        let mut resampler = SincFixedOut::new_with_shared_interpolator(
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+            self.interpolation.clone(),
+            Arc::clone(&self.interpolator),
+            self.max_chunk_size,
+            self.nbr_channels,
+        )
+        .expect("Ratios were already validated");
+        resampler.window = self.window;
+        resampler
+    }
+
+    /// Change the interpolation type used between the points of the oversampled sinc table.
+    /// The table is shared by all interpolation types, so this does not allocate,
+    /// and the internal buffers are kept so that the stream continues without a discontinuity.
+    /// The new type is used from the next call to
+    /// [process_into_buffer](Resampler::process_into_buffer).
+    pub fn set_interpolation_type(&mut self, interpolation: SincInterpolationType) {
+        debug!("Change interpolation type to {:?}", interpolation);
+        self.interpolation = interpolation;
+    }
+
+    fn update_needed_len(&mut self) {
+        self.needed_input_size = (self.last_index as f32
+            + self.chunk_size as f32
+                / (0.5 * self.resample_ratio as f32 + 0.5 * self.target_ratio as f32)
+            + self.interpolator.len() as f32)
+            .ceil() as usize;
+    }
+}
+
+/// Convert a [SincFixedIn] into a [SincFixedOut], keeping the interpolator,
+/// settings and the internal delay line, so that the audio stream continues
+/// without a discontinuity. The chunk size given when the [SincFixedIn] was created
+/// is used as the output chunk size of the [SincFixedOut].
+/// The count of clipped samples is reset.
+///
+/// The state of the features that only [SincFixedIn] supports is dropped,
+/// and is not restored when converting back:
+/// the [transient mode](SincFixedIn::set_transient_mode),
+/// [discarding the tail](SincFixedIn::set_discard_tail) and the count of discarded frames,
+/// the gains of [process_with_mute](SincFixedIn::process_with_mute),
+/// the [metrics](SincFixedIn::metrics) and the profiling histogram,
+/// the spectrum capture of the `fft_resampler` feature,
+/// and the histories of [process_with_reference](SincFixedIn::process_with_reference)
+/// and [process_with_sidechain](SincFixedIn::process_with_sidechain).
+///
+/// The conversion copies the delay line, which is O(sinc_len × channels).
+impl<T> From<SincFixedIn<T>> for SincFixedOut<T>
+where
+    T: Sample,
+{
+    fn from(resampler: SincFixedIn<T>) -> Self {
+        let sinc_len = resampler.interpolator.len();
+        let mut converted = SincFixedOut::new_with_shared_interpolator(
+            resampler.resample_ratio_original,
+            resampler.max_relative_ratio,
+            resampler.interpolation,
+            resampler.interpolator,
+            resampler.max_chunk_size,
+            resampler.nbr_channels,
+        )
+        .expect("Ratios were already validated");
+        for (buf_out, buf_in) in converted.buffer.iter_mut().zip(resampler.buffer.iter()) {
+            buf_out[..2 * sinc_len].copy_from_slice(
+                &buf_in[resampler.chunk_size..resampler.chunk_size + 2 * sinc_len],
+            );
+        }
+        converted.current_buffer_fill = 0;
+        converted.last_index = resampler.last_index;
+        converted.resample_ratio = resampler.resample_ratio;
+        converted.target_ratio = resampler.target_ratio;
+        converted.max_block_frames = resampler.max_block_frames;
+        converted.overflow_policy = resampler.overflow_policy;
+        converted.clip_ceiling = resampler.clip_ceiling;
+        converted.window = resampler.window;
+        converted.update_needed_len();
+        converted
+    }
+}
+
+/// Convert a [SincFixedOut] into a [SincFixedIn], keeping the interpolator,
+/// settings and the internal delay line, so that the audio stream continues
+/// without a discontinuity. The chunk size given when the [SincFixedOut] was created
+/// is used as the input chunk size of the [SincFixedIn].
+/// The count of clipped samples is reset.
+/// The features that only [SincFixedIn] supports start from their defaults,
+/// as for a new resampler, see the conversion in the other direction.
+///
+/// The conversion copies the delay line, which is O(sinc_len × channels).
+impl<T> From<SincFixedOut<T>> for SincFixedIn<T>
+where
+    T: Sample,
+{
+    fn from(resampler: SincFixedOut<T>) -> Self {
+        let sinc_len = resampler.interpolator.len();
+        let mut converted = SincFixedIn::new_with_shared_interpolator(
+            resampler.resample_ratio_original,
+            resampler.max_relative_ratio,
+            resampler.interpolation,
+            resampler.interpolator,
+            resampler.max_chunk_size,
+            resampler.nbr_channels,
+        )
+        .expect("Ratios were already validated");
+        let fill = resampler.current_buffer_fill;
+        let chunk_size = converted.chunk_size;
+        for (buf_in, buf_out) in converted.buffer.iter_mut().zip(resampler.buffer.iter()) {
+            buf_in[chunk_size..chunk_size + 2 * sinc_len]
+                .copy_from_slice(&buf_out[fill..fill + 2 * sinc_len]);
+        }
+        converted.last_index = resampler.last_index;
+        converted.resample_ratio = resampler.resample_ratio;
+        converted.target_ratio = resampler.target_ratio;
+        converted.max_block_frames = resampler.max_block_frames;
+        converted.overflow_policy = resampler.overflow_policy;
+        converted.clip_ceiling = resampler.clip_ceiling;
+        converted.window = resampler.window;
+        converted.at_stream_start = false;
+        converted
+    }
+}
+
+impl<T> Resampler<T> for SincFixedOut<T>
+where
+    T: Sample,
+{
+    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+        &mut self,
+        wave_in: &[Vin],
+        wave_out: &mut [Vout],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)> {
+        self.flush_remaining = None;
+        if let Some(mask) = active_channels_mask {
+            self.channel_mask.copy_from_slice(mask);
+        } else {
+            update_mask_from_buffers(&mut self.channel_mask);
+        };
+
+        validate_buffers(
+            wave_in,
+            wave_out,
+            &self.channel_mask,
+            self.nbr_channels,
+            self.needed_input_size,
+            self.chunk_size,
+            self.max_block_frames,
+        )?;
+        let sinc_len = self.interpolator.len();
+        let oversampling_factor = self.interpolator.nbr_sincs();
+
+        for buf in self.buffer.iter_mut() {
+            buf.copy_within(
+                self.current_buffer_fill..self.current_buffer_fill + 2 * sinc_len,
+                0,
+            );
+        }
+        self.current_buffer_fill = self.needed_input_size;
+
+        for (chan, active) in self.channel_mask.iter().enumerate() {
+            if *active {
+                debug_assert!(self.chunk_size <= wave_out[chan].as_mut().len());
+                self.buffer[chan][2 * sinc_len..2 * sinc_len + self.needed_input_size]
+                    .copy_from_slice(&wave_in[chan].as_ref()[..self.needed_input_size]);
+            }
+        }
+
+        let mut idx = self.last_index;
+        let mut t_ratio = 1.0 / self.resample_ratio;
+        let t_ratio_end = 1.0 / self.target_ratio;
+        let t_ratio_increment = (t_ratio_end - t_ratio) / self.chunk_size as f64;
+
+        match self.interpolation {
+            SincInterpolationType::Cubic => {
+                let mut points = [T::zero(); 4];
+                let mut nearest = [(0isize, 0isize); 4];
+                for frame in 0..self.chunk_size {
+                    t_ratio += t_ratio_increment;
+                    idx += t_ratio;
+                    get_nearest_times_4(idx, oversampling_factor as isize, &mut nearest);
+                    let frac = idx * oversampling_factor as f64
+                        - (idx * oversampling_factor as f64).floor();
+                    let frac_offset = T::coerce(frac);
+                    for (chan, active) in self.channel_mask.iter().enumerate() {
+                        if *active {
+                            let buf = &self.buffer[chan];
+                            for (n, p) in nearest.iter().zip(points.iter_mut()) {
+                                *p = self.interpolator.get_sinc_interpolated(
+                                    buf,
+                                    (n.0 + 2 * sinc_len as isize) as usize,
+                                    n.1 as usize,
+                                );
+                            }
+                            wave_out[chan].as_mut()[frame] = interp_cubic(frac_offset, &points);
+                        }
+                    }
+                }
+            }
+            SincInterpolationType::Quadratic => {
+                let mut points = [T::zero(); 3];
+                let mut nearest = [(0isize, 0isize); 3];
+                for frame in 0..self.chunk_size {
+                    t_ratio += t_ratio_increment;
+                    idx += t_ratio;
+                    get_nearest_times_3(idx, oversampling_factor as isize, &mut nearest);
+                    let frac = idx * oversampling_factor as f64
+                        - (idx * oversampling_factor as f64).floor();
+                    let frac_offset = T::coerce(frac);
+                    for (chan, active) in self.channel_mask.iter().enumerate() {
+                        if *active {
+                            let buf = &self.buffer[chan];
+                            for (n, p) in nearest.iter().zip(points.iter_mut()) {
+                                *p = self.interpolator.get_sinc_interpolated(
+                                    buf,
+                                    (n.0 + 2 * sinc_len as isize) as usize,
+                                    n.1 as usize,
+                                );
+                            }
+                            wave_out[chan].as_mut()[frame] = interp_quad(frac_offset, &points);
+                        }
+                    }
+                }
+            }
+            SincInterpolationType::Linear => {
+                let mut nearest = [(0isize, 0isize); 2];
+                for frame in 0..self.chunk_size {
+                    t_ratio += t_ratio_increment;
+                    idx += t_ratio;
+                    get_nearest_times_2(idx, oversampling_factor as isize, &mut nearest);
+                    let frac = idx * oversampling_factor as f64
+                        - (idx * oversampling_factor as f64).floor();
+                    let frac_offset = T::coerce(frac);
+                    for (chan, active) in self.channel_mask.iter().enumerate() {
+                        if *active {
+                            let buf = &self.buffer[chan];
+                            wave_out[chan].as_mut()[frame] =
+                                self.interpolator.get_sinc_interpolated_lin(
+                                    buf,
+                                    (nearest[0].0 + 2 * sinc_len as isize) as usize,
+                                    nearest[0].1 as usize,
+                                    frac_offset,
+                                );
+                        }
+                    }
+                }
+            }
+            SincInterpolationType::Nearest => {
+                let mut point;
+                let mut nearest;
+                for frame in 0..self.chunk_size {
+                    t_ratio += t_ratio_increment;
+                    idx += t_ratio;
+                    nearest = get_nearest_time(idx, oversampling_factor as isize);
+                    for (chan, active) in self.channel_mask.iter().enumerate() {
+                        if *active {
+                            let buf = &self.buffer[chan];
+                            point = self.interpolator.get_sinc_interpolated(
+                                buf,
+                                (nearest.0 + 2 * sinc_len as isize) as usize,
+                                nearest.1 as usize,
+                            );
+                            wave_out[chan].as_mut()[frame] = point;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Store last index for next iteration.
+        let input_frames_used = self.needed_input_size;
+        self.last_index = idx - self.current_buffer_fill as f64;
+        self.resample_ratio = self.target_ratio;
+        self.update_needed_len();
+        trace!(
+            "Resampling channels {:?}, {} frames in, {} frames out. Next needed length: {} frames, last index {}",
+            active_channels_mask,
+            self.current_buffer_fill,
+            self.chunk_size,
+            self.needed_input_size,
+            self.last_index
+        );
+        self.clipped_samples += count_clipped(
+            wave_out,
+            &self.channel_mask,
+            self.chunk_size,
+            self.clip_ceiling,
+        );
+        Ok((input_frames_used, self.chunk_size))
+    }
+
+    fn input_frames_max(&self) -> usize {
+        (self.max_chunk_size as f64 / self.resample_ratio_original * self.max_relative_ratio).ceil()
+            as usize
+            + 2
+            + self.interpolator.len() / 2
+    }
+
+    fn input_frames_next(&self) -> usize {
+        self.needed_input_size
+    }
+
+    fn nbr_channels(&self) -> usize {
+        self.nbr_channels
+    }
+
+    fn output_frames_max(&self) -> usize {
+        self.max_chunk_size
+    }
+
+    fn output_frames_next(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn output_delay(&self) -> usize {
+        (self.interpolator.len() as f64 * self.resample_ratio / 2.0) as usize
+    }
+
+    fn set_resample_ratio(&mut self, new_ratio: f64, ramp: bool) -> ResampleResult<()> {
+        trace!("Change resample ratio to {}", new_ratio);
+        let new_ratio = bounded_ratio(
+            new_ratio,
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+            self.overflow_policy,
+        )?;
+        if !ramp {
+            self.resample_ratio = new_ratio;
+        }
+        self.target_ratio = new_ratio;
+
+        self.update_needed_len();
+        Ok(())
+    }
+
+    fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()> {
+        let new_ratio = self.resample_ratio_original * rel_ratio;
+        self.set_resample_ratio(new_ratio, ramp)
+    }
+
+    fn target_ratio(&self) -> f64 {
+        self.target_ratio
+    }
+
+    fn flush_remaining(&self) -> Option<usize> {
+        self.flush_remaining
+    }
+
+    fn set_flush_remaining(&mut self, remaining: Option<usize>) {
+        self.flush_remaining = remaining;
+    }
+
+    fn quality_info(&self) -> QualityInfo {
+        sinc_quality_info(self.interpolator.as_ref(), self.window, self.output_delay())
+    }
+
+    fn estimated_mac_per_block(&self) -> u64 {
+        sinc_mac_per_block(
+            self.interpolator.len(),
+            &self.interpolation,
+            self.output_frames_max(),
+            self.nbr_channels,
+        )
+    }
+
+    fn set_ratio_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    fn set_max_block_frames(&mut self, max_frames: Option<usize>) {
+        self.max_block_frames = max_frames;
+    }
+
+    fn set_clip_ceiling(&mut self, ceiling: Option<T>) {
+        self.clip_ceiling = ceiling;
+    }
+
+    fn clipped_sample_count(&self) -> usize {
+        self.clipped_samples
+    }
+
+    fn reset(&mut self) {
+        self.clipped_samples = 0;
+        self.flush_remaining = None;
+        self.buffer
+            .iter_mut()
+            .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
+
+        self.resample_ratio = self.resample_ratio_original;
+        self.target_ratio = self.resample_ratio_original;
+        self.last_index = -((self.interpolator.len() / 2) as f64);
+        self.chunk_size = self.max_chunk_size;
+        self.update_needed_len();
+        self.current_buffer_fill = self.needed_input_size;
+        self.channel_mask.iter_mut().for_each(|val| *val = true);
+    }
+
+    fn set_chunk_size(&mut self, chunksize: usize) -> ResampleResult<()> {
+        if chunksize > self.max_chunk_size || chunksize == 0 {
+            return Err(ResampleError::InvalidChunkSize {
+                max: self.max_chunk_size,
+                requested: chunksize,
+            });
+        }
+        self.chunk_size = chunksize;
+        self.update_needed_len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{interp_cubic, interp_lin, soft_clip};
+    use crate::sinc::make_windowed_sinc;
+    use crate::PhaseStepper;
+    use crate::Resampler;
+    use crate::SincCheckpoint;
+    use crate::SincInterpolationParameters;
+    use crate::SincInterpolationType;
+    use crate::WindowFunction;
+    use crate::{check_output, check_ratio};
+    use crate::{ResampleError, ResamplerConstructionError};
+    use crate::{SincFixedIn, SincFixedOut};
+    use rand::Rng;
+    use std::sync::Arc;
+    use test_log::test;
+
+    fn basic_params() -> SincInterpolationParameters {
+        SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        }
+    }
+
+    #[test]
+    fn phase_stepper_matches_resampler() {
+        // At a constant ratio, a PhaseStepper started at the same position
+        // must visit the same input positions as the resampler.
+        let params = basic_params();
+        let chunk_size = 1024;
+        let ratio = 44100.0 / 48000.0;
+        let mut resampler = SincFixedIn::<f64>::new(ratio, 1.0, params, chunk_size, 1).unwrap();
+        let start = resampler.last_index;
+        let mut stepper = PhaseStepper::new(ratio, 16);
+        stepper.reset_to(start);
+        let mut base = start.floor();
+        let waves = vec![vec![0.0f64; chunk_size]; 1];
+        for chunk in 0..5 {
+            let indices = resampler.next_indices();
+            for idx in indices.iter() {
+                let (integer_advance, phase) = stepper.advance();
+                base += integer_advance as f64;
+                let expected = idx + (chunk * chunk_size) as f64;
+                assert!(
+                    (base + phase - expected).abs() < 1.0e-6,
+                    "chunk {}: stepper at {}, resampler at {}",
+                    chunk,
+                    base + phase,
+                    expected
+                );
+            }
+            let out = resampler.process(&waves, None).unwrap();
+            assert_eq!(out[0].len(), indices.len());
+        }
+    }
+
+    #[test]
+    fn int_cubic() {
+        let params = basic_params();
+        let _resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let yvals = [0.0f64, 2.0f64, 4.0f64, 6.0f64];
+        let interp = interp_cubic(0.5f64, &yvals);
+        assert_eq!(interp, 3.0f64);
+    }
+
+    #[test]
+    fn int_lin_32() {
+        let params = basic_params();
+        let _resampler = SincFixedIn::<f32>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let yvals = [1.0f32, 5.0f32];
+        let interp = interp_lin(0.25f32, &yvals);
+        assert_eq!(interp, 2.0f32);
+    }
+
+    #[test]
+    fn int_cubic_32() {
+        let params = basic_params();
+        let _resampler = SincFixedIn::<f32>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let yvals = [0.0f32, 2.0f32, 4.0f32, 6.0f32];
+        let interp = interp_cubic(0.5f32, &yvals);
+        assert_eq!(interp, 3.0f32);
+    }
+
+    #[test]
+    fn int_lin() {
+        let params = basic_params();
+        let _resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let yvals = [1.0f64, 5.0f64];
+        let interp = interp_lin(0.25f64, &yvals);
+        assert_eq!(interp, 2.0f64);
+    }
+
+    #[test]
+    fn make_resampler_fi() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let waves = vec![vec![0.0f64; 1024]; 2];
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
+        assert!(
+            out[0].len() > 1150 && out[0].len() < 1229,
+            "expected {} - {} samples, got {}",
+            1150,
+            1229,
+            out[0].len()
+        );
+        let out2 = resampler.process(&waves, None).unwrap();
+        assert_eq!(out2.len(), 2, "Expected {} channels, got {}", 2, out2.len());
+        assert!(
+            out2[0].len() > 1226 && out2[0].len() < 1232,
+            "expected {} - {} samples, got {}",
+            1226,
+            1232,
+            out2[0].len()
+        );
+    }
+
+    #[test]
+    fn reset_resampler_fi() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+
+        let mut rng = rand::thread_rng();
+        let mut waves = vec![vec![0.0f64; 1024]; 2];
+        waves
+            .iter_mut()
+            .for_each(|ch| ch.iter_mut().for_each(|s| *s = rng.gen()));
+        let out1 = resampler.process(&waves, None).unwrap();
+        resampler.reset();
+        let out2 = resampler.process(&waves, None).unwrap();
+        assert_eq!(
+            out1, out2,
+            "Resampler gives different output when new and after a reset."
+        );
+    }
+
+    #[test]
+    fn make_resampler_fi_32() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f32>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let waves = vec![vec![0.0f32; 1024]; 2];
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
+        assert!(
+            out[0].len() > 1150 && out[0].len() < 1229,
+            "expected {} - {} samples, got {}",
+            1150,
+            1229,
+            out[0].len()
+        );
+        let out2 = resampler.process(&waves, None).unwrap();
+        assert_eq!(out2.len(), 2, "Expected {} channels, got {}", 2, out2.len());
+        assert!(
+            out2[0].len() > 1226 && out2[0].len() < 1232,
+            "expected {} - {} samples, got {}",
+            1226,
+            1232,
+            out2[0].len()
+        );
+    }
+
+    #[test]
+    fn make_resampler_fi_skipped() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let waves = vec![vec![0.0f64; 1024], Vec::new()];
+        let mask = vec![true, false];
+        let out = resampler.process(&waves, Some(&mask)).unwrap();
+        assert_eq!(out.len(), 2);
+        assert!(out[0].len() > 1150 && out[0].len() < 1250);
+        assert!(out[1].is_empty());
+        let waves = vec![Vec::new(), vec![0.0f64; 1024]];
+        let mask = vec![false, true];
+        let out = resampler.process(&waves, Some(&mask)).unwrap();
+        assert_eq!(out.len(), 2);
+        assert!(out[1].len() > 1150 && out[0].len() < 1250);
+        assert!(out[0].is_empty());
+    }
+
+    #[test]
+    fn make_resampler_fi_downsample() {
+        // Replicate settings from reported issue
+        let params = SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 160,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler =
+            SincFixedIn::<f64>::new(16000 as f64 / 96000 as f64, 1.0, params, 1024, 2).unwrap();
+        let waves = vec![vec![0.0f64; 1024]; 2];
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
+        assert!(
+            out[0].len() > 140 && out[0].len() < 200,
+            "expected {} - {} samples, got {}",
+            140,
+            200,
+            out[0].len()
+        );
+        let out2 = resampler.process(&waves, None).unwrap();
+        assert_eq!(out2.len(), 2, "Expected {} channels, got {}", 2, out2.len());
+        assert!(
+            out2[0].len() > 167 && out2[0].len() < 173,
+            "expected {} - {} samples, got {}",
+            167,
+            173,
+            out2[0].len()
+        );
+    }
+
+    #[test]
+    fn make_resampler_fi_upsample() {
+        // Replicate settings from reported issue
+        let params = SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 160,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler =
+            SincFixedIn::<f64>::new(192000 as f64 / 44100 as f64, 1.0, params, 1024, 2).unwrap();
+        let waves = vec![vec![0.0f64; 1024]; 2];
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
+        assert!(
+            out[0].len() > 3800 && out[0].len() < 4458,
+            "expected {} - {} samples, got {}",
+            3800,
+            4458,
+            out[0].len()
+        );
+        let out2 = resampler.process(&waves, None).unwrap();
+        assert_eq!(out2.len(), 2, "Expected {} channels, got {}", 2, out2.len());
+        assert!(
+            out2[0].len() > 4455 && out2[0].len() < 4461,
+            "expected {} - {} samples, got {}",
+            4455,
+            4461,
+            out2[0].len()
+        );
+    }
+
+    #[test]
+    fn make_resampler_fo() {
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let frames = resampler.input_frames_next();
+        println!("{}", frames);
+        assert!(frames > 800 && frames < 900);
+        let waves = vec![vec![0.0f64; frames]; 2];
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].len(), 1024);
+    }
+
+    #[test]
+    fn input_frames_needed_fo() {
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 2.0, basic_params(), 1024, 2).unwrap();
+        for ratio in [1.2, 1.7, 0.9, 0.9, 1.2] {
+            resampler.set_resample_ratio(ratio, true).unwrap();
+            let frames = resampler.input_frames_needed();
+            assert_eq!(frames, resampler.input_frames_next());
+            let waves = vec![vec![0.0f64; frames]; 2];
+            assert!(resampler.process(&waves[..], None).is_ok());
+            let next = resampler.input_frames_needed();
+            let short = vec![vec![0.0f64; next - 1]; 2];
+            assert!(matches!(
+                resampler.process(&short, None),
+                Err(ResampleError::InsufficientInputBufferSize { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn reset_resampler_fo() {
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let frames = resampler.input_frames_next();
+
+        let mut rng = rand::thread_rng();
+        let mut waves = vec![vec![0.0f64; frames]; 2];
+        waves
+            .iter_mut()
+            .for_each(|ch| ch.iter_mut().for_each(|s| *s = rng.gen()));
+        let out1 = resampler.process(&waves, None).unwrap();
+        resampler.reset();
+        assert_eq!(
+            frames,
+            resampler.input_frames_next(),
+            "Resampler requires different number of frames when new and after a reset."
+        );
+        let out2 = resampler.process(&waves, None).unwrap();
+        assert_eq!(
+            out1, out2,
+            "Resampler gives different output when new and after a reset."
+        );
+    }
+
+    #[test]
+    fn make_resampler_fo_32() {
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f32>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let frames = resampler.input_frames_next();
+        println!("{}", frames);
+        assert!(frames > 800 && frames < 900);
+        let waves = vec![vec![0.0f32; frames]; 2];
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].len(), 1024);
+    }
+
+    #[test]
+    fn make_resampler_fo_skipped() {
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let frames = resampler.input_frames_next();
+        println!("{}", frames);
+        assert!(frames > 800 && frames < 900);
+        let mut waves = vec![vec![0.0f64; frames], Vec::new()];
+        let mask = vec![true, false];
+        waves[0][100] = 3.0;
+        let out = resampler.process(&waves, Some(&mask)).unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].len(), 1024);
+        assert!(out[1].is_empty());
+        println!("{:?}", out[0]);
+        let summed = out[0].iter().sum::<f64>();
+        println!("sum: {}", summed);
+        assert!(summed < 4.0);
+        assert!(summed > 2.0);
+
+        let frames = resampler.input_frames_next();
+        let mut waves = vec![Vec::new(), vec![0.0f64; frames]];
+        let mask = vec![false, true];
+        waves[1][10] = 3.0;
+        let out = resampler.process(&waves, Some(&mask)).unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[1].len(), 1024);
+        assert!(out[0].is_empty());
+        let summed = out[1].iter().sum::<f64>();
+        assert!(summed < 4.0);
+        assert!(summed > 2.0);
+    }
+
+    #[test]
+    fn make_resampler_fo_downsample() {
+        let params = SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 160,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler = SincFixedOut::<f64>::new(0.125, 1.0, params, 1024, 2).unwrap();
+        let frames = resampler.input_frames_next();
+        println!("{}", frames);
+        assert!(
+            frames > 8192 && frames < 9000,
+            "expected {}..{} samples, got {}",
+            8192,
+            9000,
+            frames
+        );
+        let waves = vec![vec![0.0f64; frames]; 2];
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
+        assert_eq!(
+            out[0].len(),
+            1024,
+            "Expected {} frames, got {}",
+            1024,
+            out[0].len()
+        );
+        let frames2 = resampler.input_frames_next();
+        assert!(
+            frames2 > 8189 && frames2 < 8195,
+            "expected {}..{} samples, got {}",
+            8189,
+            8195,
+            frames2
+        );
+        let waves2 = vec![vec![0.0f64; frames2]; 2];
+        let out2 = resampler.process(&waves2, None).unwrap();
+        assert_eq!(
+            out2[0].len(),
+            1024,
+            "Expected {} frames, got {}",
+            1024,
+            out2[0].len()
+        );
+    }
+
+    #[test]
+    fn make_resampler_fo_upsample() {
+        let params = SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 160,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler = SincFixedOut::<f64>::new(8.0, 1.0, params, 1024, 2).unwrap();
+        let frames = resampler.input_frames_next();
+        println!("{}", frames);
+        assert!(
+            frames > 128 && frames < 300,
+            "expected {}..{} samples, got {}",
+            140,
+            200,
+            frames
+        );
+        let waves = vec![vec![0.0f64; frames]; 2];
+        let out = resampler.process(&waves, None).unwrap();
+        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
+        assert_eq!(
+            out[0].len(),
+            1024,
+            "Expected {} frames, got {}",
+            1024,
+            out[0].len()
+        );
+        let frames2 = resampler.input_frames_next();
+        assert!(
+            frames2 > 125 && frames2 < 131,
+            "expected {}..{} samples, got {}",
+            125,
+            131,
+            frames2
+        );
+        let waves2 = vec![vec![0.0f64; frames2]; 2];
+        let out2 = resampler.process(&waves2, None).unwrap();
+        assert_eq!(
+            out2[0].len(),
+            1024,
+            "Expected {} frames, got {}",
+            1024,
+            out2[0].len()
+        );
+    }
+
+    #[test]
+    fn check_fo_output_up() {
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn check_fo_output_down() {
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f64>::new(0.8, 1.0, params, 1024, 2).unwrap();
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn check_fi_output_up() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn check_fi_output_down() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(0.8, 1.0, params, 1024, 2).unwrap();
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn resample_small_fo_up() {
+        let ratio = 96000.0 / 44100.0;
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f32>::new(ratio, 1.0, params, 1, 2).unwrap();
+        check_ratio!(resampler, ratio, 100000);
+    }
+
+    #[test]
+    fn resample_big_fo_up() {
+        let ratio = 96000.0 / 44100.0;
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f32>::new(ratio, 1.0, params, 1024, 2).unwrap();
+        check_ratio!(resampler, ratio, 100);
+    }
+
+    #[test]
+    fn resample_small_fo_down() {
+        let ratio = 44100.0 / 96000.0;
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f32>::new(ratio, 1.0, params, 1, 2).unwrap();
+        check_ratio!(resampler, ratio, 100000);
+    }
+
+    #[test]
+    fn resample_big_fo_down() {
+        let ratio = 44100.0 / 96000.0;
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f32>::new(ratio, 1.0, params, 1024, 2).unwrap();
+        check_ratio!(resampler, ratio, 100);
+    }
+
+    #[test]
+    fn resample_small_fi_up() {
+        let ratio = 96000.0 / 44100.0;
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, 1, 2).unwrap();
+        check_ratio!(resampler, ratio, 100000);
+    }
+
+    #[test]
+    fn resample_big_fi_up() {
+        let ratio = 96000.0 / 44100.0;
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, 1024, 2).unwrap();
+        check_ratio!(resampler, ratio, 100);
+    }
+
+    #[test]
+    fn resample_small_fi_down() {
+        let ratio = 44100.0 / 96000.0;
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, 1, 2).unwrap();
+        check_ratio!(resampler, ratio, 100000);
+    }
+
+    #[test]
+    fn resample_big_fi_down() {
+        let ratio = 44100.0 / 96000.0;
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, 1024, 2).unwrap();
+        check_ratio!(resampler, ratio, 100);
+    }
+
+    #[test]
+    fn check_fo_output_resize() {
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        assert_eq!(resampler.output_frames_next(), 1024);
+        resampler.set_chunk_size(256).unwrap();
+        assert_eq!(resampler.output_frames_next(), 256);
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn check_fi_output_resize() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        assert_eq!(resampler.input_frames_next(), 1024);
+        resampler.set_chunk_size(256).unwrap();
+        assert_eq!(resampler.input_frames_next(), 256);
+        check_output!(resampler);
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn profiling_fi() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        resampler.set_profiling_buckets(&[1.0, 10.0, 100.0, 1000.0, 10000.0]);
+        let waves = vec![vec![0.0f64; 1024]; 2];
+        for _ in 0..10 {
+            resampler.process(&waves, None).unwrap();
+        }
+        let report = resampler.profiling_report();
+        assert_eq!(report.call_count, 10);
+        assert!(report.max_us > 0.0);
+        assert!(report.p50_us <= report.p99_us);
+        assert!(report.p99_us <= report.p999_us);
+    }
+
+    #[test]
+    fn sidechain_alignment_fi() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 256, 1).unwrap();
+        let impulse_pos = 300;
+        let mut audio_out = Vec::new();
+        let mut control_out = Vec::new();
+        for chunk in 0..4 {
+            let mut audio = vec![vec![0.0f64; 256]];
+            if impulse_pos / 256 == chunk {
+                audio[0][impulse_pos % 256] = 1.0;
+            }
+            // The control signal is a ramp holding the input frame number.
+            let control: Vec<f64> = (256 * chunk..256 * (chunk + 1)).map(|v| v as f64).collect();
+            let (audio_chunk, control_chunk) = resampler
+                .process_with_sidechain(&audio, &control, None)
+                .unwrap();
+            assert_eq!(audio_chunk[0].len(), control_chunk.len());
+            audio_out.extend_from_slice(&audio_chunk[0]);
+            control_out.extend_from_slice(&control_chunk);
+        }
+        let peak = audio_out
+            .iter()
+            .enumerate()
+            .fold(
+                (0, 0.0),
+                |acc, (n, v)| if *v > acc.1 { (n, *v) } else { acc },
+            )
+            .0;
+        assert!(
+            (control_out[peak] - impulse_pos as f64).abs() < 0.5,
+            "control value {} at audio peak, expected {}",
+            control_out[peak],
+            impulse_pos
+        );
+        let res = resampler.process_with_sidechain(&[vec![0.0; 256]], &[0.0; 255], None);
+        assert!(matches!(
+            res,
+            Err(ResampleError::InsufficientSidechainLength {
+                expected: 256,
+                actual: 255
+            })
+        ));
+    }
+
+    #[test]
+    fn process_chunked_fi() {
+        let params = basic_params();
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        let mut waves = vec![vec![0.0f64; 5000]; 2];
+        waves
+            .iter_mut()
+            .for_each(|ch| ch.iter_mut().for_each(|s| *s = rng.gen()));
+        let out = resampler.process_chunked(&waves, None).unwrap();
+
+        resampler.reset();
+        let mut expected = vec![Vec::new(); 2];
+        for chunk in 0..4 {
+            let chunk_in = waves
+                .iter()
+                .map(|ch| &ch[1024 * chunk..1024 * (chunk + 1)])
+                .collect::<Vec<_>>();
+            let chunk_out = resampler.process(&chunk_in, None).unwrap();
+            for (ch_exp, ch_out) in expected.iter_mut().zip(chunk_out.iter()) {
+                ch_exp.extend_from_slice(ch_out);
+            }
+        }
+        let chunk_in = waves.iter().map(|ch| &ch[4096..]).collect::<Vec<_>>();
+        let chunk_out = resampler.process_partial(Some(&chunk_in), None).unwrap();
+        for (ch_exp, ch_out) in expected.iter_mut().zip(chunk_out.iter()) {
+            ch_exp.extend_from_slice(ch_out);
+        }
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn process_chunked_fo() {
+        let params = basic_params();
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let waves = vec![vec![0.5f64; 5000]; 2];
+        let out = resampler.process_chunked(&waves, None).unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].len() % 1024, 0);
+        assert!(out[0].len() >= 6000);
+    }
+
+    #[test]
+    fn process_max_frames_fo() {
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, basic_params(), 128, 2).unwrap();
+        let mut reference = SincFixedOut::<f64>::new(1.2, 1.0, basic_params(), 128, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        let waves = (0..2)
+            .map(|_| (0..2000).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let (out, frames_in) = resampler.process_max_frames(&waves, 300).unwrap();
+        assert_eq!(out[0].len(), 256);
+        let mut expected_in = 0;
+        for _ in 0..2 {
+            let chunk = waves
+                .iter()
+                .map(|wave| &wave[expected_in..])
+                .collect::<Vec<_>>();
+            let (frames, _) = reference
+                .process_into_buffer(&chunk, &mut vec![vec![0.0; 128]; 2], None)
+                .unwrap();
+            expected_in += frames;
+        }
+        assert_eq!(frames_in, expected_in);
+
+        // Continue with the remaining input, without any limit on the output.
+        let rest = waves
+            .iter()
+            .map(|wave| &wave[frames_in..])
+            .collect::<Vec<_>>();
+        let (out, _) = resampler.process_max_frames(&rest, usize::MAX).unwrap();
+        assert!(out[0].len() > 256);
+        assert_eq!(out[0].len() % 128, 0);
+
+        let (out, frames_in) = resampler.process_max_frames(&waves, 100).unwrap();
+        assert_eq!(out[0].len(), 0);
+        assert_eq!(frames_in, 0);
+    }
+
+    #[test]
+    fn process_pair_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.1, basic_params(), 1024, 2).unwrap();
+        let mut reference = SincFixedIn::<f64>::new(1.2, 1.1, basic_params(), 1024, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        for variant in 0..4 {
+            let interpolation = || match variant {
+                0 => SincInterpolationType::Cubic,
+                1 => SincInterpolationType::Quadratic,
+                2 => SincInterpolationType::Linear,
+                _ => SincInterpolationType::Nearest,
+            };
+            resampler.interpolation = interpolation();
+            reference.interpolation = interpolation();
+            for _ in 0..3 {
+                let waves = (0..2)
+                    .map(|_| (0..1024).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+                let (out_a, out_b) = resampler.process_pair(&waves[0], &waves[1]).unwrap();
+                let expected = reference.process(&waves, None).unwrap();
+                assert_eq!(out_a.len(), expected[0].len());
+                for (out, exp) in [out_a, out_b].iter().zip(expected.iter()) {
+                    for (val, exp_val) in out.iter().zip(exp.iter()) {
+                        assert!((val - exp_val).abs() < 1.0e-12);
+                    }
+                }
+            }
+            resampler.set_resample_ratio(1.25, true).unwrap();
+            reference.set_resample_ratio(1.25, true).unwrap();
+        }
+    }
+
+    #[test]
+    fn process_pair_state_fi() {
+        // Mixing process_pair with other methods must give the same output and metrics
+        // as using process only, also in transient mode.
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 256, 2).unwrap();
+        resampler.set_transient_mode(true);
+        let mut reference = resampler.try_clone();
+        let waves = |chunk: usize| {
+            (0..2)
+                .map(|chan| {
+                    (256 * chunk..256 * (chunk + 1))
+                        .map(|n| if n % 300 == 100 * chan { 1.0 } else { 0.0 })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        resampler.set_flush_remaining(Some(0));
+        let (out_a, out_b) = resampler.process_pair(&waves(0)[0], &waves(0)[1]).unwrap();
+        assert_eq!(resampler.flush_remaining(), None);
+        assert_eq!(
+            vec![out_a, out_b],
+            reference.process(&waves(0), None).unwrap()
+        );
+        // The first frames were already returned, so no frames are left out.
+        assert_eq!(
+            resampler.process_valid_only(&waves(1), None).unwrap(),
+            reference.process(&waves(1), None).unwrap()
+        );
+        let (out_a, out_b) = resampler.process_pair(&waves(2)[0], &waves(2)[1]).unwrap();
+        assert_eq!(
+            vec![out_a, out_b],
+            reference.process(&waves(2), None).unwrap()
+        );
+        assert!(resampler.process_pair(&[0.0; 100], &[0.0; 256]).is_err());
+        assert!(reference
+            .process(&[vec![0.0; 100], vec![0.0; 256]], None)
+            .is_err());
+        let metrics = resampler.metrics();
+        assert_eq!(metrics.process_calls, 3);
+        assert_eq!(metrics.xruns, 1);
+        assert_eq!(
+            metrics.frames_processed,
+            reference.metrics().frames_processed
+        );
+    }
+
+    #[test]
+    fn convert_fi_fo_continuous() {
+        let input = (0..20000)
+            .map(|n| (n as f64 * 0.05).sin())
+            .collect::<Vec<_>>();
+        let mut reference = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 1).unwrap();
+        let expected = reference.process_chunked(&[&input], None).unwrap();
+
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 1).unwrap();
+        let mut output = Vec::new();
+        let mut pos = 0;
+        for _ in 0..3 {
+            let out = resampler.process(&[&input[pos..pos + 1024]], None).unwrap();
+            output.extend_from_slice(&out[0]);
+            pos += 1024;
+        }
+        let mut resampler = SincFixedOut::from(resampler);
+        for _ in 0..3 {
+            let needed = resampler.input_frames_next();
+            let out = resampler
+                .process(&[&input[pos..pos + needed]], None)
+                .unwrap();
+            assert_eq!(out[0].len(), 1024);
+            output.extend_from_slice(&out[0]);
+            pos += needed;
+        }
+        let mut resampler = SincFixedIn::from(resampler);
+        for _ in 0..3 {
+            let out = resampler.process(&[&input[pos..pos + 1024]], None).unwrap();
+            output.extend_from_slice(&out[0]);
+            pos += 1024;
+        }
+        assert!(output.len() > 9 * 1024);
+        for (val, exp) in output.iter().zip(expected[0].iter()) {
+            assert!((val - exp).abs() < 1.0e-9);
+        }
+    }
+
+    #[test]
+    fn convert_drops_fixed_in_state() {
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 256, 1).unwrap();
+        resampler.set_discard_tail(true);
+        resampler.process(&[vec![0.5; 256]], None).unwrap();
+        let mut resampler = SincFixedIn::from(SincFixedOut::from(resampler));
+        assert_eq!(resampler.metrics().frames_processed, vec![0]);
+        // The tail is flushed instead of discarded.
+        let mut wave_out = vec![vec![0.0; resampler.output_frames_max()]; 1];
+        assert!(resampler.flush_into_buffer(&mut wave_out, None).unwrap() > 0);
+        assert_eq!(resampler.discarded_frame_count(), 0);
+    }
+
+    #[test]
+    fn try_clone_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 2.0, basic_params(), 1024, 2).unwrap();
+        let mut reference = SincFixedIn::<f64>::new(1.2, 2.0, basic_params(), 1024, 2).unwrap();
+        let mut fresh = SincFixedIn::<f64>::new(1.2, 2.0, basic_params(), 1024, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        let waves = (0..2)
+            .map(|_| (0..1024).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        resampler.process(&waves, None).unwrap();
+        reference.process(&waves, None).unwrap();
+
+        let mut clone = resampler.try_clone();
+        assert_eq!(Arc::strong_count(&resampler.interpolator), 2);
+        assert_eq!(
+            clone.process(&waves, None).unwrap(),
+            fresh.process(&waves, None).unwrap()
+        );
+        clone.set_resample_ratio(2.0, false).unwrap();
+        clone.process(&waves, None).unwrap();
+        assert_eq!(
+            resampler.process(&waves, None).unwrap(),
+            reference.process(&waves, None).unwrap()
+        );
+
+        let mut clone_fo = SincFixedOut::<f64>::new(1.2, 2.0, basic_params(), 1024, 2)
+            .unwrap()
+            .try_clone();
+        assert_eq!(clone_fo.output_frames_next(), 1024);
+        let frames = clone_fo.input_frames_next();
+        let out = clone_fo
+            .process(&[&waves[0][..frames], &waves[1][..frames]], None)
+            .unwrap();
+        assert_eq!(out[0].len(), 1024);
+    }
+
+    #[test]
+    fn phase_dc_gains_fi() {
+        let resampler = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
+        let gains = resampler.phase_dc_gains();
+        assert_eq!(gains.len(), 16);
+        for (phase, gain) in gains.iter().enumerate() {
+            assert!(
+                (gain - 1.0).abs() < 1.0e-6,
+                "Phase {} has DC gain {}",
+                phase,
+                gain
+            );
+        }
+    }
 
-    fn basic_params() -> SincInterpolationParameters {
-        SincInterpolationParameters {
-            sinc_len: 64,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Cubic,
-            oversampling_factor: 16,
-            window: WindowFunction::BlackmanHarris2,
+    #[test]
+    fn process_with_auto_gain_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(0.5, 1.0, basic_params(), 1024, 1).unwrap();
+        let gain = resampler.auto_gain_compensation();
+        assert!(gain > 0.9 && gain < 1.1);
+        let ones = vec![1.0; 1024];
+        let mut output = Vec::new();
+        for _ in 0..4 {
+            let out = resampler.process_with_auto_gain(&[&ones], None).unwrap();
+            output.extend_from_slice(&out[0]);
+        }
+        let settled = &output[output.len() - 100..];
+        for value in settled.iter() {
+            assert!((value - 1.0).abs() < 1.0e-6, "{} is not 1.0", value);
         }
     }
 
     #[test]
-    fn int_cubic() {
-        let params = basic_params();
-        let _resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let yvals = [0.0f64, 2.0f64, 4.0f64, 6.0f64];
-        let interp = interp_cubic(0.5f64, &yvals);
-        assert_eq!(interp, 3.0f64);
+    fn export_c_header_fi() {
+        let resampler = SincFixedIn::<f32>::new(1.2, 1.0, basic_params(), 1024, 1).unwrap();
+        let header = resampler.export_c_header("SINC_TABLE");
+        assert!(header.starts_with("const float SINC_TABLE[] = {\n"));
+        assert!(header.ends_with("};\n"));
+        let body = &header[header.find('{').unwrap() + 1..header.rfind('}').unwrap()];
+        let values = body
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("/*"))
+            .flat_map(|line| line.split(','))
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .map(|item| item.trim_end_matches('f').parse::<f32>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values.len(), 64 * 16);
+        // The sincs are normalized, each one should sum to approximately one.
+        for sinc in values.chunks(64) {
+            let sum: f32 = sinc.iter().sum();
+            assert!((sum - 1.0).abs() < 1.0e-3, "{}", sum);
+        }
     }
 
     #[test]
-    fn int_lin_32() {
-        let params = basic_params();
-        let _resampler = SincFixedIn::<f32>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let yvals = [1.0f32, 5.0f32];
-        let interp = interp_lin(0.25f32, &yvals);
-        assert_eq!(interp, 2.0f32);
+    fn soft_clip_curve() {
+        // Unity slope below the knee, continuous and monotonic up to the threshold.
+        assert_eq!(soft_clip(0.3, 1.0), (0.3, false));
+        assert_eq!(soft_clip(-0.5, 1.0), (-0.5, false));
+        assert_eq!(soft_clip(2.0, 1.0), (1.0, true));
+        assert_eq!(soft_clip(-1.25, 1.0), (-1.0, true));
+        let mut previous = 0.5;
+        for n in 1..=75 {
+            let value = 0.5 + n as f64 * 0.01;
+            let (clipped, modified) = soft_clip(value, 1.0);
+            assert!(modified);
+            assert!(clipped > previous && clipped - previous <= 0.01 + 1.0e-12);
+            previous = clipped;
+        }
+        assert!((previous - 1.0).abs() < 1.0e-12);
     }
 
     #[test]
-    fn int_cubic_32() {
-        let params = basic_params();
-        let _resampler = SincFixedIn::<f32>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let yvals = [0.0f32, 2.0f32, 4.0f32, 6.0f32];
-        let interp = interp_cubic(0.5f32, &yvals);
-        assert_eq!(interp, 3.0f32);
+    fn process_with_soft_clip_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.5, 1.0, basic_params(), 1024, 2).unwrap();
+        let loud = (0..1024)
+            .map(|n| 1.2 * (n as f64 * 0.05).sin())
+            .collect::<Vec<_>>();
+        let quiet = loud.iter().map(|v| 0.3 * v).collect::<Vec<_>>();
+        let (output, stats) = resampler
+            .process_with_soft_clip(&[&loud, &quiet], 1.0)
+            .unwrap();
+        assert!(output[0].iter().all(|v| v.abs() <= 1.0));
+        assert!(stats.peaks[0] > 1.1 && stats.peaks[0] < 1.3);
+        assert!(stats.clip_events[0] > 0);
+        assert!(stats.peaks[1] < 0.5);
+        assert_eq!(stats.clip_events[1], 0);
     }
 
+    #[cfg(feature = "tracing")]
     #[test]
-    fn int_lin() {
-        let params = basic_params();
-        let _resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let yvals = [1.0f64, 5.0f64];
-        let interp = interp_lin(0.25f64, &yvals);
-        assert_eq!(interp, 2.0f64);
+    fn construction_span_fields() {
+        use std::sync::Mutex;
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id};
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+        #[derive(Default)]
+        struct Recorder(Arc<Mutex<Vec<(String, String)>>>);
+
+        impl Visit for Recorder {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((field.name().to_string(), format!("{:?}", value)));
+            }
+        }
+
+        struct SpanLayer(Arc<Mutex<Vec<(String, String)>>>);
+
+        impl<S: tracing::Subscriber> Layer<S> for SpanLayer {
+            fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+                if attrs.metadata().name() == "SincFixedIn::new" {
+                    attrs.record(&mut Recorder(self.0.clone()));
+                }
+            }
+        }
+
+        let fields = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(SpanLayer(fields.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
+        });
+        let fields = fields.lock().unwrap();
+        let value = |name: &str| {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(value("channels").as_deref(), Some("2"));
+        assert_eq!(value("ratio").as_deref(), Some("1.2"));
+        assert_eq!(value("window").as_deref(), Some("BlackmanHarris2"));
+        assert_eq!(value("sinc_len").as_deref(), Some("64"));
+        let f_cutoff: f64 = value("f_cutoff").unwrap().parse().unwrap();
+        assert!((f_cutoff - 0.95).abs() < 1.0e-6);
     }
 
     #[test]
-    fn make_resampler_fi() {
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let waves = vec![vec![0.0f64; 1024]; 2];
-        let out = resampler.process(&waves, None).unwrap();
-        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
-        assert!(
-            out[0].len() > 1150 && out[0].len() < 1229,
-            "expected {} - {} samples, got {}",
-            1150,
-            1229,
-            out[0].len()
+    fn process_benchmark_mode_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
+        let mut reference = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        let waves = (0..2)
+            .map(|_| (0..1024).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for _ in 0..3 {
+            unsafe { resampler.process_benchmark_mode_unchecked(&[&waves[0], &waves[1]]) };
+            reference.process(&waves, None).unwrap();
+        }
+        assert_eq!(
+            resampler.metrics().frames_processed,
+            reference.metrics().frames_processed
         );
-        let out2 = resampler.process(&waves, None).unwrap();
-        assert_eq!(out2.len(), 2, "Expected {} channels, got {}", 2, out2.len());
-        assert!(
-            out2[0].len() > 1226 && out2[0].len() < 1232,
-            "expected {} - {} samples, got {}",
-            1226,
-            1232,
-            out2[0].len()
+        // The stream was already started, so no frames are left out.
+        assert_eq!(
+            resampler.process_valid_only(&waves, None).unwrap(),
+            reference.process(&waves, None).unwrap()
         );
     }
 
     #[test]
-    fn reset_resampler_fi() {
+    fn memory_usage_fi() {
         let params = basic_params();
-        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let estimate = SincFixedIn::<f64>::estimate_memory_usage(&params, 1024, 2);
+        let resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        assert_eq!(resampler.peak_memory_usage(), estimate);
+        assert_eq!(resampler.current_memory_usage(), estimate);
+        assert_eq!(estimate, 8 * (64 * 16 + 3 * (1024 + 2 * 64)));
+    }
 
+    #[test]
+    fn process_averaged_fi() {
+        let mut resamplers = vec![
+            SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap(),
+            SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap(),
+        ];
+        let mut reference = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
         let mut rng = rand::thread_rng();
-        let mut waves = vec![vec![0.0f64; 1024]; 2];
-        waves
-            .iter_mut()
-            .for_each(|ch| ch.iter_mut().for_each(|s| *s = rng.gen()));
-        let out1 = resampler.process(&waves, None).unwrap();
-        resampler.reset();
-        let out2 = resampler.process(&waves, None).unwrap();
-        assert_eq!(
-            out1, out2,
-            "Resampler gives different output when new and after a reset."
-        );
+        let waves = (0..2)
+            .map(|_| (0..1024).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let averaged = SincFixedIn::process_averaged(&mut resamplers, &waves, &[1.0, 3.0]).unwrap();
+        let expected = reference.process(&waves, None).unwrap();
+        assert_eq!(averaged.len(), 2);
+        for (avg_chan, exp_chan) in averaged.iter().zip(expected.iter()) {
+            assert_eq!(avg_chan.len(), exp_chan.len());
+            for (avg, exp) in avg_chan.iter().zip(exp_chan.iter()) {
+                assert!((avg - exp).abs() < 1.0e-12);
+            }
+        }
+
+        let mut params = basic_params();
+        params.sinc_len = 128;
+        let mut resamplers = vec![
+            SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap(),
+            SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap(),
+        ];
+        let averaged = SincFixedIn::process_averaged(&mut resamplers, &waves, &[0.5, 0.5]).unwrap();
+        let mut params = basic_params();
+        params.sinc_len = 128;
+        let mut long_sinc = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
+        let long_sinc_len = long_sinc.process(&waves, None).unwrap()[0].len();
+        assert_eq!(averaged[0].len(), expected[0].len().min(long_sinc_len));
     }
 
     #[test]
-    fn make_resampler_fi_32() {
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f32>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let waves = vec![vec![0.0f32; 1024]; 2];
-        let out = resampler.process(&waves, None).unwrap();
-        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
-        assert!(
-            out[0].len() > 1150 && out[0].len() < 1229,
-            "expected {} - {} samples, got {}",
-            1150,
-            1229,
-            out[0].len()
-        );
-        let out2 = resampler.process(&waves, None).unwrap();
-        assert_eq!(out2.len(), 2, "Expected {} channels, got {}", 2, out2.len());
-        assert!(
-            out2[0].len() > 1226 && out2[0].len() < 1232,
-            "expected {} - {} samples, got {}",
-            1226,
-            1232,
-            out2[0].len()
-        );
+    fn is_drained_fo() {
+        let mut resampler = SincFixedOut::<f64>::new(1.0, 1.0, basic_params(), 16, 1).unwrap();
+        let delay = resampler.output_delay();
+        assert_eq!(delay, 32);
+        assert!(!resampler.is_drained());
+        let waves = resampler.input_buffer_allocate(true);
+        let mut wave_out = resampler.output_buffer_allocate(true);
+        resampler
+            .process_into_buffer(&waves, &mut wave_out, None)
+            .unwrap();
+        assert!(!resampler.is_drained());
+        let mut flushed = 0;
+        let frames = resampler.flush_into_buffer(&mut wave_out, None).unwrap();
+        assert_eq!(frames, 16);
+        flushed += frames;
+        assert!(!resampler.is_drained());
+        loop {
+            let frames = resampler.flush_into_buffer(&mut wave_out, None).unwrap();
+            flushed += frames;
+            if frames == 0 {
+                break;
+            }
+        }
+        assert_eq!(flushed, delay);
+        assert!(resampler.is_drained());
+        resampler
+            .process_into_buffer(&waves, &mut wave_out, None)
+            .unwrap();
+        assert!(!resampler.is_drained());
     }
 
     #[test]
-    fn make_resampler_fi_skipped() {
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let waves = vec![vec![0.0f64; 1024], Vec::new()];
-        let mask = vec![true, false];
-        let out = resampler.process(&waves, Some(&mask)).unwrap();
-        assert_eq!(out.len(), 2);
-        assert!(out[0].len() > 1150 && out[0].len() < 1250);
-        assert!(out[1].is_empty());
-        let waves = vec![Vec::new(), vec![0.0f64; 1024]];
-        let mask = vec![false, true];
-        let out = resampler.process(&waves, Some(&mask)).unwrap();
-        assert_eq!(out.len(), 2);
-        assert!(out[1].len() > 1150 && out[0].len() < 1250);
-        assert!(out[0].is_empty());
+    fn metrics_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
+        let waves = vec![vec![0.0f64; 1024]; 2];
+        resampler.process(&waves, None).unwrap();
+        resampler.process(&waves, Some(&[true, false])).unwrap();
+        let short = vec![vec![0.0f64; 100]; 2];
+        assert!(resampler.process(&short, None).is_err());
+        let metrics = resampler.metrics();
+        assert_eq!(metrics.frames_processed, vec![2048, 1024]);
+        assert_eq!(metrics.process_calls, 2);
+        assert_eq!(metrics.xruns, 1);
+        #[cfg(any(feature = "profiling", feature = "prometheus"))]
+        assert!(metrics.process_duration.as_nanos() > 0);
+        resampler.reset();
+        assert_eq!(resampler.metrics().process_calls, 0);
+        assert_eq!(resampler.metrics().frames_processed, vec![0, 0]);
     }
 
     #[test]
-    fn make_resampler_fi_downsample() {
-        // Replicate settings from reported issue
-        let params = SincInterpolationParameters {
-            sinc_len: 256,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Cubic,
-            oversampling_factor: 160,
-            window: WindowFunction::BlackmanHarris2,
+    fn set_interpolation_type_fi() {
+        let linear_params = || {
+            let mut params = basic_params();
+            params.interpolation = SincInterpolationType::Linear;
+            params
         };
-        let mut resampler =
-            SincFixedIn::<f64>::new(16000 as f64 / 96000 as f64, 1.0, params, 1024, 2).unwrap();
-        let waves = vec![vec![0.0f64; 1024]; 2];
-        let out = resampler.process(&waves, None).unwrap();
-        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
-        assert!(
-            out[0].len() > 140 && out[0].len() < 200,
-            "expected {} - {} samples, got {}",
-            140,
-            200,
-            out[0].len()
-        );
-        let out2 = resampler.process(&waves, None).unwrap();
-        assert_eq!(out2.len(), 2, "Expected {} channels, got {}", 2, out2.len());
-        assert!(
-            out2[0].len() > 167 && out2[0].len() < 173,
-            "expected {} - {} samples, got {}",
-            167,
-            173,
-            out2[0].len()
-        );
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, linear_params(), 256, 1).unwrap();
+        let mut reference = SincFixedIn::<f64>::new(1.2, 1.0, linear_params(), 256, 1).unwrap();
+        let input = (0..256 * 8)
+            .map(|n| (n as f64 * 0.05).sin())
+            .collect::<Vec<_>>();
+        let mut output = Vec::new();
+        let mut expected = Vec::new();
+        let mut switch_at = 0;
+        for (block, chunk) in input.chunks(256).enumerate() {
+            if block == 4 {
+                resampler.set_interpolation_type(SincInterpolationType::Cubic);
+                switch_at = output.len();
+            }
+            output.extend(resampler.process(&[chunk], None).unwrap().remove(0));
+            expected.extend(reference.process(&[chunk], None).unwrap().remove(0));
+        }
+        assert_eq!(output.len(), expected.len());
+        // Identical before the switch, different after.
+        for n in 0..switch_at {
+            assert_eq!(output[n], expected[n]);
+        }
+        let max_diff = output[switch_at..]
+            .iter()
+            .zip(expected[switch_at..].iter())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        assert!(max_diff > 0.0);
+        assert!(max_diff < 1.0e-3, "{}", max_diff);
+        // The output stays smooth across the switch.
+        let max_step = input
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0, f64::max);
+        for n in switch_at - 4..switch_at + 4 {
+            assert!((output[n + 1] - output[n]).abs() < 1.1 * max_step);
+        }
     }
 
     #[test]
-    fn make_resampler_fi_upsample() {
-        // Replicate settings from reported issue
-        let params = SincInterpolationParameters {
-            sinc_len: 256,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Cubic,
-            oversampling_factor: 160,
-            window: WindowFunction::BlackmanHarris2,
+    fn reconstruct_from_phase_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 2.0, basic_params(), 256, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        let blocks = (0..6)
+            .map(|_| {
+                (0..2)
+                    .map(|_| (0..256).map(|_| rng.gen::<f64>()).collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for block in blocks[..3].iter() {
+            resampler.process(block, None).unwrap();
+        }
+        // Take the checkpoint with a ramp pending, which must continue after resuming.
+        resampler.set_resample_ratio(1.5, true).unwrap();
+        let checkpoint = resampler.checkpoint();
+        assert_eq!(checkpoint.delay_line[0].len(), 128);
+        let mut resumed = SincFixedIn::<f64>::reconstruct_from_phase(
+            1.2,
+            2.0,
+            basic_params(),
+            256,
+            checkpoint.clone(),
+        )
+        .unwrap();
+        for block in blocks[3..].iter() {
+            let expected = resampler.process(block, None).unwrap();
+            let output = resumed.process(block, None).unwrap();
+            assert_eq!(output, expected);
+        }
+
+        let bad_checkpoint = SincCheckpoint {
+            delay_line: vec![vec![0.0; 128], vec![0.0; 100]],
+            ..checkpoint
         };
+        assert!(matches!(
+            SincFixedIn::<f64>::reconstruct_from_phase(
+                1.2,
+                2.0,
+                basic_params(),
+                256,
+                bad_checkpoint
+            ),
+            Err(ResamplerConstructionError::InvalidDelayLineLength {
+                channel: 1,
+                expected: 128,
+                actual: 100
+            })
+        ));
+    }
+
+    #[test]
+    fn process_partial_last_fi() {
+        for (ratio, frames) in [(2.0, 1000), (0.5, 1000), (1.25, 777)] {
+            let mut resampler =
+                SincFixedIn::<f64>::new(ratio, 1.0, basic_params(), 256, 2).unwrap();
+            let waves = vec![vec![0.1f64; frames]; 2];
+            let mut buffer_out = resampler.output_buffer_allocate(true);
+            let mut frames_in = 0;
+            let mut frames_out = 0;
+            while frames - frames_in >= resampler.input_frames_next() {
+                let chunk = waves
+                    .iter()
+                    .map(|wave| &wave[frames_in..])
+                    .collect::<Vec<_>>();
+                let (chunk_in, chunk_out) = resampler
+                    .process_into_buffer(&chunk, &mut buffer_out, None)
+                    .unwrap();
+                frames_in += chunk_in;
+                frames_out += chunk_out;
+            }
+            let last = waves
+                .iter()
+                .map(|wave| &wave[frames_in..])
+                .collect::<Vec<_>>();
+            let wave_out = resampler.process_partial_last(&last).unwrap();
+            assert_eq!(wave_out.len(), 2);
+            assert_eq!(wave_out[0].len(), wave_out[1].len());
+            let total = frames_out + wave_out[0].len();
+            assert!((total as f64 - frames as f64 * ratio).abs() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn output_schedule_fi() {
         let mut resampler =
-            SincFixedIn::<f64>::new(192000 as f64 / 44100 as f64, 1.0, params, 1024, 2).unwrap();
+            SincFixedIn::<f64>::new(48000.0 / 44100.0, 1.0, basic_params(), 1024, 2).unwrap();
+        let schedule = resampler.output_schedule(20);
         let waves = vec![vec![0.0f64; 1024]; 2];
-        let out = resampler.process(&waves, None).unwrap();
-        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
-        assert!(
-            out[0].len() > 3800 && out[0].len() < 4458,
-            "expected {} - {} samples, got {}",
-            3800,
-            4458,
-            out[0].len()
-        );
-        let out2 = resampler.process(&waves, None).unwrap();
-        assert_eq!(out2.len(), 2, "Expected {} channels, got {}", 2, out2.len());
-        assert!(
-            out2[0].len() > 4455 && out2[0].len() < 4461,
-            "expected {} - {} samples, got {}",
-            4455,
-            4461,
-            out2[0].len()
-        );
+        let mut wave_out = resampler.output_buffer_allocate(true);
+        let mut total_output_frames = 0;
+        for expected in schedule.iter() {
+            let (_, frames_out) = resampler
+                .process_into_buffer(&waves, &mut wave_out, None)
+                .unwrap();
+            assert_eq!(frames_out, *expected);
+            total_output_frames += frames_out;
+        }
+        assert_eq!(schedule.iter().sum::<usize>(), total_output_frames);
     }
 
     #[test]
-    fn make_resampler_fo() {
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let frames = resampler.input_frames_next();
-        println!("{}", frames);
-        assert!(frames > 800 && frames < 900);
-        let waves = vec![vec![0.0f64; frames]; 2];
-        let out = resampler.process(&waves, None).unwrap();
-        assert_eq!(out.len(), 2);
-        assert_eq!(out[0].len(), 1024);
+    fn warm_start_fi() {
+        let ratio = 1.5;
+        let track_a = (0..2048)
+            .map(|n| (0.01 * n as f64).sin())
+            .collect::<Vec<_>>();
+        let mut resampler = SincFixedIn::<f64>::new(ratio, 1.0, basic_params(), 1024, 1).unwrap();
+        let mut output_a = resampler.process(&[&track_a[..1024]], None).unwrap();
+        let output_a2 = resampler.process(&[&track_a[1024..]], None).unwrap();
+        output_a[0].extend_from_slice(&output_a2[0]);
+
+        // The last output frame corresponds to this input position, in units of input frames.
+        let end = output_a[0].len() as f64 / ratio - 1.0;
+        let track_b = vec![(0..1024)
+            .map(|n| (0.01 * (end + 1.0 + n as f64)).sin())
+            .collect::<Vec<_>>()];
+        let mut cold = SincFixedIn::<f64>::new(ratio, 1.0, basic_params(), 1024, 1).unwrap();
+        let mut warm = cold.try_clone();
+        warm.warm_start(&output_a).unwrap();
+        let out_cold = cold.process(&track_b, None).unwrap();
+        let out_warm = warm.process(&track_b, None).unwrap();
+        let max_error = |out: &[f64]| {
+            out.iter()
+                .take(64)
+                .enumerate()
+                .map(|(n, value)| {
+                    let position = (n + 1) as f64 / ratio - 1.0;
+                    (value - (0.01 * (end + 1.0 + position)).sin()).abs()
+                })
+                .fold(0.0, f64::max)
+        };
+        assert!(max_error(&out_warm[0]) < 0.01);
+        assert!(max_error(&out_cold[0]) > 0.1);
     }
 
     #[test]
-    fn reset_resampler_fo() {
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let frames = resampler.input_frames_next();
-
+    fn process_with_gain_matrix_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 256, 2).unwrap();
+        // The transients must be blended in before mixing, like for process.
+        resampler.set_transient_mode(true);
+        let mut reference = resampler.try_clone();
         let mut rng = rand::thread_rng();
-        let mut waves = vec![vec![0.0f64; frames]; 2];
-        waves
-            .iter_mut()
-            .for_each(|ch| ch.iter_mut().for_each(|s| *s = rng.gen()));
-        let out1 = resampler.process(&waves, None).unwrap();
-        resampler.reset();
-        assert_eq!(
-            frames,
-            resampler.input_frames_next(),
-            "Resampler requires different number of frames when new and after a reset."
-        );
-        let out2 = resampler.process(&waves, None).unwrap();
+        let matrix = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.5, -0.25]];
+        for chunk in 0..3 {
+            let mut waves = (0..2)
+                .map(|_| {
+                    (0..256)
+                        .map(|_| 0.01 * rng.gen::<f64>())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            if chunk == 1 {
+                waves[0][100] = 1.0;
+            }
+            let mixed = resampler.process_with_gain_matrix(&waves, &matrix).unwrap();
+            let expected = reference.process(&waves, None).unwrap();
+            assert_eq!(mixed.len(), 3);
+            assert_eq!(mixed[0], expected[0]);
+            assert_eq!(mixed[1], expected[1]);
+            for (value, (left, right)) in mixed[2]
+                .iter()
+                .zip(expected[0].iter().zip(expected[1].iter()))
+            {
+                assert!((value - (0.5 * left - 0.25 * right)).abs() < 1.0e-12);
+            }
+        }
+        assert_eq!(resampler.metrics().process_calls, 3);
         assert_eq!(
-            out1, out2,
-            "Resampler gives different output when new and after a reset."
+            resampler.metrics().frames_processed,
+            reference.metrics().frames_processed
         );
+        let waves = vec![vec![0.0; 256]; 2];
+        #[cfg(feature = "fft_resampler")]
+        {
+            resampler.set_spectrum_capture(true);
+            resampler.process_with_gain_matrix(&waves, &matrix).unwrap();
+            assert_eq!(resampler.last_block_spectrum().len(), 3);
+            resampler.process(&waves, None).unwrap();
+            assert_eq!(resampler.last_block_spectrum().len(), 2);
+        }
+        assert!(matches!(
+            resampler.process_with_gain_matrix(&waves, &[vec![1.0, 0.0], vec![1.0]]),
+            Err(ResampleError::WrongNumberOfMatrixColumns {
+                row: 1,
+                expected: 2,
+                actual: 1
+            })
+        ));
     }
 
     #[test]
-    fn make_resampler_fo_32() {
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f32>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let frames = resampler.input_frames_next();
-        println!("{}", frames);
-        assert!(frames > 800 && frames < 900);
-        let waves = vec![vec![0.0f32; frames]; 2];
-        let out = resampler.process(&waves, None).unwrap();
-        assert_eq!(out.len(), 2);
-        assert_eq!(out[0].len(), 1024);
+    fn estimated_mac_per_block_fi() {
+        let mono = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 1).unwrap();
+        let stereo = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
+        let mut params = basic_params();
+        params.sinc_len *= 2;
+        let long = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 1).unwrap();
+        let estimate = mono.estimated_mac_per_block();
+        assert!(estimate > 0);
+        assert_eq!(stereo.estimated_mac_per_block(), 2 * estimate);
+        assert_eq!(long.estimated_mac_per_block(), 2 * estimate);
     }
 
     #[test]
-    fn make_resampler_fo_skipped() {
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        let frames = resampler.input_frames_next();
-        println!("{}", frames);
-        assert!(frames > 800 && frames < 900);
-        let mut waves = vec![vec![0.0f64; frames], Vec::new()];
-        let mask = vec![true, false];
-        waves[0][100] = 3.0;
-        let out = resampler.process(&waves, Some(&mask)).unwrap();
-        assert_eq!(out.len(), 2);
-        assert_eq!(out[0].len(), 1024);
-        assert!(out[1].is_empty());
-        println!("{:?}", out[0]);
-        let summed = out[0].iter().sum::<f64>();
-        println!("sum: {}", summed);
-        assert!(summed < 4.0);
-        assert!(summed > 2.0);
+    fn process_with_end_padding_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.5, 1.0, basic_params(), 1024, 2).unwrap();
+        let mut reference = resampler.try_clone();
+        let waves = vec![
+            (0..2500)
+                .map(|n| (n as f64 * 0.01).sin())
+                .collect::<Vec<_>>();
+            2
+        ];
+        let expected_len = 3750;
+        let padded = resampler
+            .process_with_end_padding(&waves, expected_len)
+            .unwrap();
+        assert_eq!(padded.len(), 2);
+        assert_eq!(padded[0].len(), expected_len);
+        assert_eq!(padded[1].len(), expected_len);
 
-        let frames = resampler.input_frames_next();
-        let mut waves = vec![Vec::new(), vec![0.0f64; frames]];
-        let mask = vec![false, true];
-        waves[1][10] = 3.0;
-        let out = resampler.process(&waves, Some(&mask)).unwrap();
-        assert_eq!(out.len(), 2);
-        assert_eq!(out[1].len(), 1024);
-        assert!(out[0].is_empty());
-        let summed = out[1].iter().sum::<f64>();
-        assert!(summed < 4.0);
-        assert!(summed > 2.0);
+        // Compare with manually padding the input to whole chunks.
+        let mut manual = vec![Vec::new(); 2];
+        let mut padded_waves = waves.clone();
+        padded_waves
+            .iter_mut()
+            .for_each(|wave| wave.resize(5 * 1024, 0.0));
+        for chunk in 0..5 {
+            let chunk_in = padded_waves
+                .iter()
+                .map(|wave| &wave[chunk * 1024..(chunk + 1) * 1024])
+                .collect::<Vec<_>>();
+            let out = reference.process(&chunk_in, None).unwrap();
+            for (wave, chunk_out) in manual.iter_mut().zip(out.iter()) {
+                wave.extend_from_slice(chunk_out);
+            }
+        }
+        assert_eq!(padded[0][..], manual[0][..expected_len]);
+        assert_eq!(padded[1][..], manual[1][..expected_len]);
     }
 
     #[test]
-    fn make_resampler_fo_downsample() {
-        let params = SincInterpolationParameters {
-            sinc_len: 256,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Cubic,
-            oversampling_factor: 160,
-            window: WindowFunction::BlackmanHarris2,
+    fn band_edges() {
+        let (passband_edge, stopband_edge) = (0.8, 0.9);
+        let params = SincInterpolationParameters::from_band_edges(
+            passband_edge,
+            stopband_edge,
+            SincInterpolationType::Cubic,
+            16,
+        )
+        .unwrap();
+        assert_eq!(params.sinc_len % 8, 0);
+        // Evaluate the response of the oversampled sinc, with the frequency relative to Nyquist.
+        let sinc = make_windowed_sinc::<f64>(params.sinc_len, 16, params.f_cutoff, params.window);
+        let center = (sinc.len() / 2) as f64;
+        let response = |freq: f64| {
+            sinc.iter()
+                .enumerate()
+                .map(|(n, value)| {
+                    value * (std::f64::consts::PI * freq * (n as f64 - center) / 16.0).cos()
+                })
+                .sum::<f64>()
         };
-        let mut resampler = SincFixedOut::<f64>::new(0.125, 1.0, params, 1024, 2).unwrap();
-        let frames = resampler.input_frames_next();
-        println!("{}", frames);
+        let dc = response(0.0);
+        let freqs = (0..=1200).map(|n| n as f64 / 1000.0).collect::<Vec<_>>();
+        let gains = freqs
+            .iter()
+            .map(|freq| response(*freq) / dc)
+            .collect::<Vec<_>>();
+        let tolerance = 1.0e-6;
+        let measured_pass = freqs[gains
+            .iter()
+            .position(|gain| (gain - 1.0).abs() > tolerance)
+            .unwrap()];
+        let measured_stop = freqs[gains
+            .iter()
+            .rposition(|gain| gain.abs() > tolerance)
+            .unwrap()];
         assert!(
-            frames > 8192 && frames < 9000,
-            "expected {}..{} samples, got {}",
-            8192,
-            9000,
-            frames
-        );
-        let waves = vec![vec![0.0f64; frames]; 2];
-        let out = resampler.process(&waves, None).unwrap();
-        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
-        assert_eq!(
-            out[0].len(),
-            1024,
-            "Expected {} frames, got {}",
-            1024,
-            out[0].len()
+            (measured_pass - passband_edge as f64).abs() < 0.02,
+            "{}",
+            measured_pass
         );
-        let frames2 = resampler.input_frames_next();
         assert!(
-            frames2 > 8189 && frames2 < 8195,
-            "expected {}..{} samples, got {}",
-            8189,
-            8195,
-            frames2
-        );
-        let waves2 = vec![vec![0.0f64; frames2]; 2];
-        let out2 = resampler.process(&waves2, None).unwrap();
-        assert_eq!(
-            out2[0].len(),
-            1024,
-            "Expected {} frames, got {}",
-            1024,
-            out2[0].len()
+            (measured_stop - stopband_edge as f64).abs() < 0.02,
+            "{}",
+            measured_stop
         );
-    }
 
-    #[test]
-    fn make_resampler_fo_upsample() {
-        let params = SincInterpolationParameters {
-            sinc_len: 256,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Cubic,
-            oversampling_factor: 160,
-            window: WindowFunction::BlackmanHarris2,
-        };
-        let mut resampler = SincFixedOut::<f64>::new(8.0, 1.0, params, 1024, 2).unwrap();
-        let frames = resampler.input_frames_next();
-        println!("{}", frames);
-        assert!(
-            frames > 128 && frames < 300,
-            "expected {}..{} samples, got {}",
-            140,
-            200,
-            frames
-        );
-        let waves = vec![vec![0.0f64; frames]; 2];
-        let out = resampler.process(&waves, None).unwrap();
-        assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
-        assert_eq!(
-            out[0].len(),
-            1024,
-            "Expected {} frames, got {}",
-            1024,
-            out[0].len()
-        );
-        let frames2 = resampler.input_frames_next();
-        assert!(
-            frames2 > 125 && frames2 < 131,
-            "expected {}..{} samples, got {}",
-            125,
-            131,
-            frames2
-        );
-        let waves2 = vec![vec![0.0f64; frames2]; 2];
-        let out2 = resampler.process(&waves2, None).unwrap();
-        assert_eq!(
-            out2[0].len(),
-            1024,
-            "Expected {} frames, got {}",
-            1024,
-            out2[0].len()
-        );
+        assert!(SincInterpolationParameters::from_band_edges(
+            0.9,
+            0.8,
+            SincInterpolationType::Cubic,
+            16
+        )
+        .is_err());
+        assert!(SincInterpolationParameters::from_band_edges(
+            0.9,
+            0.9001,
+            SincInterpolationType::Cubic,
+            16
+        )
+        .is_err());
     }
 
     #[test]
-    fn check_fo_output_up() {
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        check_output!(resampler);
+    fn process_valid_only_fi() {
+        for ratio in [2.0, 1.5, 0.5] {
+            let mut resampler =
+                SincFixedIn::<f64>::new(ratio, 1.0, basic_params(), 256, 1).unwrap();
+            let mut reference = resampler.try_clone();
+            let mut waves = vec![vec![0.0; 256]; 1];
+            waves[0][0] = 1.0;
+            let valid = resampler.process_valid_only(&waves, None).unwrap();
+            let peak = valid[0]
+                .iter()
+                .enumerate()
+                .fold((0, 0.0), |(idx, max), (n, value)| {
+                    if value.abs() > max {
+                        (n, value.abs())
+                    } else {
+                        (idx, max)
+                    }
+                });
+            assert_eq!(peak.0, 0, "ratio {}", ratio);
+            let all = reference.process(&waves, None).unwrap();
+            let warm_up = all[0].len() - valid[0].len();
+            assert_eq!(valid[0][..], all[0][warm_up..]);
+
+            // Later calls return all frames.
+            let waves = vec![vec![0.5; 256]; 1];
+            let valid = resampler.process_valid_only(&waves, None).unwrap();
+            let all = reference.process(&waves, None).unwrap();
+            assert_eq!(valid, all);
+        }
     }
 
     #[test]
-    fn check_fo_output_down() {
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f64>::new(0.8, 1.0, params, 1024, 2).unwrap();
-        check_output!(resampler);
+    fn process_noalloc_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.5, 1.0, basic_params(), 256, 2).unwrap();
+        let mut reference = resampler.try_clone();
+        let mut scratch = vec![0.0; resampler.scratch_len()];
+        assert_eq!(scratch.len(), 512);
+        let mut out_a = vec![0.0; resampler.output_frames_max()];
+        let mut out_b = vec![0.0; resampler.output_frames_max()];
+        let input = (0..300)
+            .map(|n| (n as f64 * 0.05).sin())
+            .collect::<Vec<_>>();
+
+        // A full chunk, followed by a short one and an empty one for flushing.
+        for frames in [256, 44, 0] {
+            let waves = vec![&input[..frames], &input[..frames]];
+            let (frames_in, frames_out) = resampler
+                .process_noalloc(&waves, &mut [&mut out_a, &mut out_b], &mut scratch)
+                .unwrap();
+            let reference_in = if frames > 0 { Some(&waves[..]) } else { None };
+            let expected = reference.process_partial(reference_in, None).unwrap();
+            assert_eq!(frames_in, 256);
+            assert_eq!(frames_out, expected[0].len());
+            assert_eq!(out_a[..frames_out], expected[0][..]);
+            assert_eq!(out_b[..frames_out], expected[1][..]);
+        }
+
+        let waves = vec![&input[..256], &input[..256]];
+        let res =
+            resampler.process_noalloc(&waves, &mut [&mut out_a, &mut out_b], &mut scratch[..511]);
+        assert!(matches!(
+            res,
+            Err(ResampleError::InsufficientScratchSize {
+                expected: 512,
+                actual: 511
+            })
+        ));
     }
 
     #[test]
-    fn check_fi_output_up() {
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        check_output!(resampler);
+    fn history_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.5, 1.0, basic_params(), 256, 2).unwrap();
+        assert_eq!(resampler.history(1), vec![0.0; 128]);
+        let ramp = (0..256).map(|n| n as f64).collect::<Vec<_>>();
+        let waves = vec![ramp.clone(), vec![0.0; 256]];
+        resampler.process(&waves, None).unwrap();
+        assert_eq!(resampler.history(0), &ramp[128..]);
+        assert_eq!(resampler.history(1), vec![0.0; 128]);
     }
 
     #[test]
-    fn check_fi_output_down() {
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f64>::new(0.8, 1.0, params, 1024, 2).unwrap();
-        check_output!(resampler);
+    fn discard_tail_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.5, 1.0, basic_params(), 256, 2).unwrap();
+        resampler.set_discard_tail(true);
+        let waves = vec![vec![0.5; 256]; 2];
+        resampler.process(&waves, None).unwrap();
+        let latency = resampler.output_delay();
+        let mut wave_out = vec![vec![0.0; resampler.output_frames_max()]; 2];
+        assert_eq!(resampler.flush_into_buffer(&mut wave_out, None).unwrap(), 0);
+        assert!(resampler.is_drained());
+        assert_eq!(resampler.discarded_frame_count(), latency);
+        assert_eq!(resampler.flush_into_buffer(&mut wave_out, None).unwrap(), 0);
+        assert_eq!(resampler.discarded_frame_count(), latency);
+        resampler.reset();
+        assert_eq!(resampler.discarded_frame_count(), 0);
     }
 
     #[test]
-    fn resample_small_fo_up() {
-        let ratio = 96000.0 / 44100.0;
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f32>::new(ratio, 1.0, params, 1, 2).unwrap();
-        check_ratio!(resampler, ratio, 100000);
+    fn process_with_mute_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.0, 1.0, basic_params(), 256, 2).unwrap();
+        let waves = vec![vec![0.5; 256]; 2];
+        // Fill the delay line, so that the output is constant.
+        resampler.process(&waves, None).unwrap();
+        let unmuted = resampler.process(&waves, None).unwrap();
+        let level = unmuted[0][100];
+
+        let out = resampler
+            .process_with_mute(&waves, &[true, false], 100)
+            .unwrap();
+        assert!((out[0][0] - 0.99 * level).abs() < 1.0e-6);
+        assert!((out[0][49] - 0.5 * level).abs() < 1.0e-6);
+        assert!(out[0][99..].iter().all(|value| *value == 0.0));
+        assert_eq!(out[1], unmuted[1]);
+
+        // A new ramp continues from the current gain.
+        let out = resampler
+            .process_with_mute(&waves, &[false, true], 200)
+            .unwrap();
+        assert!((out[0][0] - 0.005 * level).abs() < 1.0e-6);
+        assert!((out[1][99] - 0.5 * level).abs() < 1.0e-6);
+        let out = resampler
+            .process_with_mute(&waves, &[false, false], 200)
+            .unwrap();
+        assert!((out[1][0] - 0.005 * level).abs() < 1.0e-6);
+        assert!((out[0][0] - level).abs() < 1.0e-6);
+
+        assert!(matches!(
+            resampler.process_with_mute(&waves, &[false], 10),
+            Err(ResampleError::WrongNumberOfMaskChannels { .. })
+        ));
     }
 
+    #[cfg(feature = "fft_resampler")]
     #[test]
-    fn resample_big_fo_up() {
-        let ratio = 96000.0 / 44100.0;
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f32>::new(ratio, 1.0, params, 1024, 2).unwrap();
-        check_ratio!(resampler, ratio, 100);
+    fn last_block_spectrum_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(2.0, 1.0, basic_params(), 1024, 1).unwrap();
+        resampler.set_spectrum_capture(true);
+        // A tone at 3 kHz, resampled from 24 kHz to 48 kHz.
+        let waves = crate::testing::signals::sine::<f64>(3000.0, 24000.0, 2048, 1);
+        resampler.process(&[&waves[0][..1024]], None).unwrap();
+        resampler.process(&[&waves[0][1024..]], None).unwrap();
+        let spectra = resampler.last_block_spectrum();
+        // The FFT length is 4096, and the bins are 48000 / 4096 Hz apart.
+        assert_eq!(spectra[0].len(), 2049);
+        let peak = spectra[0]
+            .iter()
+            .enumerate()
+            .fold((0, 0.0), |(idx, max), (n, value)| {
+                if *value > max {
+                    (n, *value)
+                } else {
+                    (idx, max)
+                }
+            });
+        assert_eq!(peak.0, 256);
+        resampler.set_spectrum_capture(false);
+        assert!(resampler.last_block_spectrum()[0].is_empty());
     }
 
+    #[cfg(feature = "fft_resampler")]
     #[test]
-    fn resample_small_fo_down() {
-        let ratio = 44100.0 / 96000.0;
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f32>::new(ratio, 1.0, params, 1, 2).unwrap();
-        check_ratio!(resampler, ratio, 100000);
+    fn process_with_spectrum_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(2.0, 1.0, basic_params(), 1024, 1).unwrap();
+        let waves = crate::testing::signals::sine::<f64>(3000.0, 24000.0, 2048, 1);
+        resampler.process(&[&waves[0][..1024]], None).unwrap();
+        let (output, spectra) = resampler
+            .process_with_spectrum(&[&waves[0][1024..]], 2048)
+            .unwrap();
+        assert_eq!(spectra[0].len(), 1025);
+        // The tone at 3 kHz is at bin 128 at 48 kHz, and is at full scale.
+        let peak =
+            spectra[0]
+                .iter()
+                .enumerate()
+                .fold((0, f64::NEG_INFINITY), |(idx, max), (n, value)| {
+                    if *value > max {
+                        (n, *value)
+                    } else {
+                        (idx, max)
+                    }
+                });
+        assert_eq!(peak.0, 128);
+        assert!(peak.1.abs() < 0.5, "Peak level {} dBFS", peak.1);
+        assert!(output[0].len() > 2000);
     }
 
+    #[cfg(feature = "debug-interp")]
     #[test]
-    fn resample_big_fo_down() {
-        let ratio = 44100.0 / 96000.0;
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f32>::new(ratio, 1.0, params, 1024, 2).unwrap();
-        check_ratio!(resampler, ratio, 100);
+    fn process_with_interp_debug_fi() {
+        let input = (0..256)
+            .map(|n| (n * 37 % 101) as f64 / 50.0 - 1.0)
+            .collect::<Vec<_>>();
+        for interpolation in [
+            SincInterpolationType::Cubic,
+            SincInterpolationType::Quadratic,
+            SincInterpolationType::Linear,
+            SincInterpolationType::Nearest,
+        ] {
+            let label = format!("{:?}", interpolation);
+            let mut params = basic_params();
+            params.interpolation = interpolation;
+            let sinc_len = params.sinc_len;
+            let mut resampler = SincFixedIn::<f64>::new(1.1, 1.0, params, 128, 1).unwrap();
+            for chunk in 0..2 {
+                let (output, frames) = resampler
+                    .process_with_interp_debug(&[&input[chunk * 128..(chunk + 1) * 128]])
+                    .unwrap();
+                assert_eq!(output[0].len(), frames.len());
+                for (frame, value) in frames.iter().zip(output[0].iter()) {
+                    assert_eq!(frame.output_sample, *value);
+                    assert_eq!(frame.phase_coefficients.len(), sinc_len + 2);
+                    assert!(frame.phase_frac >= 0.0 && frame.phase_frac < 1.0);
+                    // Apply the coefficients to the input stream, which starts with zeros.
+                    let first = (chunk * 128) as isize + frame.phase_int - 1;
+                    let expected: f64 = frame
+                        .phase_coefficients
+                        .iter()
+                        .enumerate()
+                        .map(|(k, coeff)| {
+                            let pos = first + k as isize;
+                            if pos < 0 {
+                                0.0
+                            } else {
+                                coeff * input[pos as usize]
+                            }
+                        })
+                        .sum();
+                    assert!(
+                        (expected - value).abs() < 1.0e-9,
+                        "{}: {} != {}",
+                        label,
+                        expected,
+                        value
+                    );
+                }
+            }
+        }
     }
 
     #[test]
-    fn resample_small_fi_up() {
-        let ratio = 96000.0 / 44100.0;
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, 1, 2).unwrap();
-        check_ratio!(resampler, ratio, 100000);
+    fn transient_mode_pre_ring() {
+        let params = SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 256,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut input = vec![0.0; 2048];
+        input[1500] = 1.0;
+        let ratio = 48000.0 / 44100.0;
+        let mut linear = SincFixedIn::<f64>::new(ratio, 1.0, params, 1024, 1).unwrap();
+        let mut transient = linear.try_clone();
+        transient.set_transient_mode(true);
+        let mut outputs = Vec::new();
+        for resampler in [&mut linear, &mut transient] {
+            let mut output = Vec::new();
+            for chunk in input.chunks(1024) {
+                output.extend_from_slice(&resampler.process(&[chunk], None).unwrap()[0]);
+            }
+            outputs.push(output);
+        }
+        let peak = outputs[0].iter().enumerate().fold(0, |best, (n, value)| {
+            if value.abs() > outputs[0][best].abs() {
+                n
+            } else {
+                best
+            }
+        });
+        // Measure the energy before the click, up to half the long filter length.
+        let first = peak - (128.0 * ratio) as usize;
+        let pre_ring = |output: &[f64]| {
+            output[first..peak - 2]
+                .iter()
+                .map(|value| value * value)
+                .sum::<f64>()
+        };
+        let linear_energy = pre_ring(&outputs[0]);
+        let transient_energy = pre_ring(&outputs[1]);
+        assert!(
+            transient_energy < 0.5 * linear_energy,
+            "Pre-ring energy {} vs {}",
+            transient_energy,
+            linear_energy
+        );
+        // Far from the click, the outputs are identical.
+        assert_eq!(outputs[0][..first - 200], outputs[1][..first - 200]);
     }
 
     #[test]
-    fn resample_big_fi_up() {
-        let ratio = 96000.0 / 44100.0;
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, 1024, 2).unwrap();
-        check_ratio!(resampler, ratio, 100);
+    fn estimated_snr() {
+        let high = SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 256,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let fast = SincInterpolationParameters {
+            sinc_len: 32,
+            f_cutoff: 0.9,
+            interpolation: SincInterpolationType::Nearest,
+            oversampling_factor: 16,
+            window: WindowFunction::Hann,
+        };
+        for ratio in [48000.0 / 44100.0, 44100.0 / 48000.0] {
+            let snr_high = high.estimated_snr_db(ratio);
+            let snr_fast = fast.estimated_snr_db(ratio);
+            assert!(snr_high > 80.0, "High quality SNR {}", snr_high);
+            assert!(
+                snr_high > snr_fast + 20.0,
+                "High quality SNR {}, fast SNR {}",
+                snr_high,
+                snr_fast
+            );
+        }
     }
 
     #[test]
-    fn resample_small_fi_down() {
-        let ratio = 44100.0 / 96000.0;
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, 1, 2).unwrap();
-        check_ratio!(resampler, ratio, 100000);
+    fn output_delay_ms_fi() {
+        let resampler = SincFixedIn::<f64>::new(1.0, 1.0, basic_params(), 1024, 2).unwrap();
+        assert_eq!(resampler.output_delay(), 32);
+        let delay_ms = resampler.output_delay_ms(48000.0);
+        assert!((delay_ms - 32.0 / 48.0).abs() < 1.0e-12);
     }
 
     #[test]
-    fn resample_big_fi_down() {
-        let ratio = 44100.0 / 96000.0;
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, 1024, 2).unwrap();
-        check_ratio!(resampler, ratio, 100);
+    fn constant_input_frames_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.0, 2.0, basic_params(), 512, 2).unwrap();
+        let waves = vec![vec![0.1f64; 512]; 2];
+        let mut wave_out = resampler.output_buffer_allocate(true);
+        for step in 0..=20 {
+            let ratio = 0.5 * 4.0f64.powf(step as f64 / 20.0);
+            for ramp in [false, true] {
+                resampler.set_resample_ratio(ratio, ramp).unwrap();
+                assert_eq!(resampler.input_frames_next(), 512);
+                let frames_out_next = resampler.output_frames_next();
+                let (frames_in, frames_out) = resampler
+                    .process_into_buffer(&waves, &mut wave_out, None)
+                    .unwrap();
+                assert_eq!(frames_in, 512);
+                assert!(frames_out <= frames_out_next);
+            }
+        }
     }
 
     #[test]
-    fn check_fo_output_resize() {
-        let params = basic_params();
-        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        assert_eq!(resampler.output_frames_next(), 1024);
-        resampler.set_chunk_size(256).unwrap();
-        assert_eq!(resampler.output_frames_next(), 256);
-        check_output!(resampler);
+    fn process_with_reference_fi() {
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
+        let mut plain = SincFixedIn::<f64>::new(1.2, 1.0, basic_params(), 1024, 2).unwrap();
+        let mut rng = rand::thread_rng();
+        for _ in 0..3 {
+            let reference = (0..2)
+                .map(|_| {
+                    (0..1024)
+                        .map(|_| rng.gen::<f64>() - 0.5)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let (output, metrics) = resampler
+                .process_with_reference(&reference, &reference)
+                .unwrap();
+            assert_eq!(output, plain.process(&reference, None).unwrap());
+            assert!(metrics.snr_db.is_infinite());
+        }
+        // Silent blocks are identical, and an invalid input must not advance the reference.
+        let silence = vec![vec![0.0; 1024]; 2];
+        let (_, metrics) = resampler
+            .process_with_reference(&silence, &silence)
+            .unwrap();
+        assert_eq!(metrics.snr_db, f64::INFINITY);
+        assert_eq!(metrics.channel_snr_db, vec![f64::INFINITY; 2]);
+        let short = vec![vec![0.0; 100]; 2];
+        assert!(resampler.process_with_reference(&short, &silence).is_err());
+        assert!(resampler.process_with_reference(&silence, &short).is_err());
+        let reference = vec![vec![0.5; 1024]; 2];
+        let (_, metrics) = resampler
+            .process_with_reference(&reference, &reference)
+            .unwrap();
+        assert!(metrics.snr_db.is_infinite());
+        // Add noise 40 dB below the signal to one channel.
+        resampler.reset();
+        for _ in 0..3 {
+            let reference = (0..2)
+                .map(|_| {
+                    (0..1024)
+                        .map(|n| (n as f64 * 0.05).sin())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let mut noisy = reference.clone();
+            for value in noisy[1].iter_mut() {
+                *value += 0.01 * (rng.gen::<f64>() - 0.5) * 12.0f64.sqrt();
+            }
+            let (_, metrics) = resampler
+                .process_with_reference(&noisy, &reference)
+                .unwrap();
+            assert!(metrics.channel_snr_db[0].is_infinite());
+            assert!(
+                (metrics.channel_snr_db[1] - 37.0).abs() < 3.0,
+                "{:?}",
+                metrics
+            );
+            assert!((metrics.snr_db - metrics.channel_snr_db[1] - 3.0).abs() < 0.5);
+        }
     }
 
     #[test]
-    fn check_fi_output_resize() {
-        let params = basic_params();
-        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap();
-        assert_eq!(resampler.input_frames_next(), 1024);
-        resampler.set_chunk_size(256).unwrap();
-        assert_eq!(resampler.input_frames_next(), 256);
-        check_output!(resampler);
+    fn process_with_reference_after_process_fi() {
+        // The reference must follow the input stream when it was processed without a reference,
+        // also with a ratio that gives a varying number of output frames.
+        let mut resampler = SincFixedIn::<f64>::new(1.23, 2.0, basic_params(), 1000, 1).unwrap();
+        let wave = |chunk: usize| {
+            vec![(1000 * chunk..1000 * (chunk + 1))
+                .map(|n| (n as f64 * 0.03).sin())
+                .collect::<Vec<_>>()]
+        };
+        for chunk in 0..7 {
+            if chunk % 3 == 0 {
+                resampler.process(&wave(chunk), None).unwrap();
+                resampler
+                    .set_resample_ratio(1.23 + 0.05 * chunk as f64, true)
+                    .unwrap();
+            } else {
+                let (_, metrics) = resampler
+                    .process_with_reference(&wave(chunk), &wave(chunk))
+                    .unwrap();
+                assert!(metrics.snr_db.is_infinite(), "{:?}", metrics);
+            }
+        }
     }
 }
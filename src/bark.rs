@@ -0,0 +1,205 @@
+use crate::error::ResamplerConstructionError;
+use crate::sinc::make_sincs;
+use crate::sinc_interpolator::{ScalarInterpolator, SincInterpolator};
+use crate::windows::WindowFunction;
+use crate::Sample;
+
+/// Parameters for a [BarkSincTable].
+#[derive(Debug)]
+pub struct BarkSincConfig {
+    /// Length of the windowed sinc interpolation filter, see
+    /// [SincInterpolationParameters](crate::SincInterpolationParameters).
+    /// Must be a multiple of 8.
+    pub sinc_len: usize,
+    /// Relative cutoff frequency of the sinc interpolation filter,
+    /// given in the unwarped frequency domain.
+    pub f_cutoff: f32,
+    /// The number of intermediate points to use for interpolation.
+    pub oversampling_factor: usize,
+    /// Window function to use.
+    pub window: WindowFunction,
+    /// Coefficient of the first-order allpass sections used to warp the frequency axis.
+    /// Zero gives no warping, while positive values increase the frequency resolution
+    /// at low frequencies at the expense of the high frequencies.
+    /// Must be larger than -1.0 and smaller than 1.0.
+    /// A coefficient approximating the Bark scale is given by
+    /// [BarkSincConfig::bark_coefficient].
+    pub warping_coefficient: f64,
+}
+
+impl BarkSincConfig {
+    /// Calculate the warping coefficient that best approximates the Bark frequency scale
+    /// for the given sample rate in Hz, using the formula by Smith and Abel.
+    pub fn bark_coefficient(sample_rate: f64) -> f64 {
+        1.0674 * (2.0 / std::f64::consts::PI * (0.06583 * sample_rate / 1000.0).atan()).sqrt()
+            - 0.1916
+    }
+}
+
+/// A table of windowed sincs calculated on a frequency-warped grid.
+///
+/// Each of the oversampled sincs is treated as an FIR filter, where every unit delay
+/// is replaced by a first-order allpass section `(z^-1 - a) / (1 - a*z^-1)`,
+/// with `a` being the warping coefficient. The impulse responses of the resulting
+/// filters are truncated to the sinc length, and each one is normalized to unity gain at DC.
+///
+/// The table implements [SincInterpolator] and can be used with
+/// [SincFixedIn::new_with_interpolator](crate::SincFixedIn::new_with_interpolator),
+/// or via the [SincFixedIn::new_bark](crate::SincFixedIn::new_bark) convenience constructor.
+/// Note that the warped filters do not have linear phase,
+/// meaning that the delay through the resampler depends on frequency.
+pub struct BarkSincTable<T> {
+    interpolator: ScalarInterpolator<T>,
+    warping_coefficient: f64,
+}
+
+/// Helper function. Replace the unit delays of an FIR filter by first-order allpass sections,
+/// and return the first `coefficients.len()` points of the impulse response.
+fn warp_fir(coefficients: &[f64], warping_coefficient: f64) -> Vec<f64> {
+    // Evaluate h0 + D(h1 + D(h2 + ...)) from the inside out, where D is the allpass.
+    let mut response = vec![0.0; coefficients.len()];
+    for coeff in coefficients.iter().rev() {
+        let mut x_prev = 0.0;
+        let mut y_prev = 0.0;
+        for value in response.iter_mut() {
+            let x = *value;
+            let y = x_prev + warping_coefficient * (y_prev - x);
+            x_prev = x;
+            y_prev = y;
+            *value = y;
+        }
+        response[0] += coeff;
+    }
+    response
+}
+
+impl<T> BarkSincTable<T>
+where
+    T: Sample,
+{
+    /// Create a new BarkSincTable.
+    /// The sincs are calculated in f64 precision and then converted to `T`.
+    /// Returns [ResamplerConstructionError::InvalidWarpingCoefficient]
+    /// if the warping coefficient is outside the allowed range.
+    pub fn new(config: BarkSincConfig) -> Result<Self, ResamplerConstructionError> {
+        if !(config.warping_coefficient > -1.0 && config.warping_coefficient < 1.0) {
+            return Err(ResamplerConstructionError::InvalidWarpingCoefficient(
+                config.warping_coefficient,
+            ));
+        }
+        debug!("Create new BarkSincTable, config: {:?}", config);
+        let sincs = make_sincs::<f64>(
+            config.sinc_len,
+            config.oversampling_factor,
+            config.f_cutoff,
+            config.window,
+        );
+        let sincs = sincs
+            .iter()
+            .map(|sinc| {
+                let warped = warp_fir(sinc, config.warping_coefficient);
+                let sum: f64 = warped.iter().sum();
+                warped.iter().map(|val| T::coerce(val / sum)).collect()
+            })
+            .collect();
+        Ok(Self {
+            interpolator: ScalarInterpolator::from_sincs(sincs),
+            warping_coefficient: config.warping_coefficient,
+        })
+    }
+
+    /// Get the warping coefficient used for the table.
+    pub fn warping_coefficient(&self) -> f64 {
+        self.warping_coefficient
+    }
+}
+
+impl<T> SincInterpolator<T> for BarkSincTable<T>
+where
+    T: Sample,
+{
+    fn get_sinc_interpolated(&self, wave: &[T], index: usize, subindex: usize) -> T {
+        self.interpolator
+            .get_sinc_interpolated(wave, index, subindex)
+    }
+
+    fn len(&self) -> usize {
+        self.interpolator.len()
+    }
+
+    fn nbr_sincs(&self) -> usize {
+        self.interpolator.nbr_sincs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{warp_fir, BarkSincConfig, BarkSincTable};
+    use crate::sinc_interpolator::{ScalarInterpolator, SincInterpolator};
+    use crate::{
+        Resampler, ResamplerConstructionError, SincFixedIn, SincInterpolationType, WindowFunction,
+    };
+    use test_log::test;
+
+    fn config(warping_coefficient: f64) -> BarkSincConfig {
+        BarkSincConfig {
+            sinc_len: 32,
+            f_cutoff: 0.9,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+            warping_coefficient,
+        }
+    }
+
+    #[test]
+    fn warp_identity() {
+        let coefficients = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(warp_fir(&coefficients, 0.0), coefficients.to_vec());
+        let warped = warp_fir(&[0.0, 1.0, 0.0, 0.0], 0.5);
+        // Impulse response of a single allpass section.
+        assert_eq!(warped, vec![-0.5, 0.75, 0.375, 0.1875]);
+    }
+
+    #[test]
+    fn unwarped_table_matches_sincs() {
+        let table = BarkSincTable::<f64>::new(config(0.0)).unwrap();
+        let plain = ScalarInterpolator::<f64>::new(32, 16, 0.9, WindowFunction::BlackmanHarris2);
+        let wave = (0..100).map(|n| (n as f64 * 0.3).sin()).collect::<Vec<_>>();
+        for subindex in [0, 7, 15] {
+            let value = table.get_sinc_interpolated(&wave, 20, subindex);
+            let check = plain.get_sinc_interpolated(&wave, 20, subindex);
+            assert!((value - check).abs() < 1.0e-9);
+        }
+    }
+
+    #[test]
+    fn invalid_warping_coefficient() {
+        assert!(matches!(
+            BarkSincTable::<f64>::new(config(1.0)),
+            Err(ResamplerConstructionError::InvalidWarpingCoefficient(_))
+        ));
+    }
+
+    #[test]
+    fn bark_resampler_dc_gain() {
+        let warping = BarkSincConfig::bark_coefficient(44100.0);
+        assert!((warping - 0.756).abs() < 0.01);
+        let mut resampler = SincFixedIn::<f64>::new_bark(
+            1.2,
+            1.0,
+            SincInterpolationType::Linear,
+            config(warping),
+            256,
+            1,
+        )
+        .unwrap();
+        let waves = vec![vec![1.0f64; 256]];
+        let mut out = Vec::new();
+        for _ in 0..4 {
+            out = resampler.process(&waves, None).unwrap();
+        }
+        for val in out[0].iter() {
+            assert!((val - 1.0).abs() < 0.01, "{}", val);
+        }
+    }
+}
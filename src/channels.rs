@@ -0,0 +1,46 @@
+/// Labels for identifying channels by their role instead of by index.
+///
+/// When used with [Resampler::process_labelled](crate::Resampler::process_labelled),
+/// the labelled channels are mapped to resampler channels in the order
+/// the labels are declared here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChannelLabel {
+    /// Ambisonics W, the omnidirectional component.
+    W,
+    /// Ambisonics X, the front-back component.
+    X,
+    /// Ambisonics Y, the left-right component.
+    Y,
+    /// Ambisonics Z, the up-down component.
+    Z,
+    /// Front left.
+    LFront,
+    /// Front right.
+    RFront,
+    /// Front center.
+    Center,
+    /// Low frequency effects.
+    Lfe,
+    /// Side left.
+    LSide,
+    /// Side right.
+    RSide,
+    /// Rear left.
+    LRear,
+    /// Rear right.
+    RRear,
+    /// Any other channel, identified by a number.
+    Other(usize),
+}
+
+/// The channel format of stereo audio.
+///
+/// Used with [Resampler::process_mid_side](crate::Resampler::process_mid_side)
+/// to select the format of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoFormat {
+    /// Left in channel 0 and right in channel 1.
+    LeftRight,
+    /// Mid, `(L + R) / 2`, in channel 0 and side, `(L - R) / 2`, in channel 1.
+    MidSide,
+}
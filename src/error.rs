@@ -78,11 +78,64 @@ impl fmt::Display for MissingCpuFeature {
 
 impl error::Error for MissingCpuFeature {}
 
+/// The error type returned when combining windows, see [window_product](crate::window_product),
+/// and when reading or using custom windows, see [window_from_csv](crate::window_from_csv).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowError {
+    /// The windows to combine have different lengths.
+    LengthMismatch { first: usize, second: usize },
+    /// A custom window doesn't have the length required by the sinc table.
+    InvalidLength { expected: usize, actual: usize },
+    /// A line of a window file could not be parsed as a number.
+    /// The line number starts from 1.
+    InvalidCoefficient { line: usize, text: String },
+    /// The window file could not be read. Contains the message of the underlying error.
+    ReadFailed(String),
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::LengthMismatch { first, second } => write!(
+                formatter,
+                "Windows must have the same length, got {} and {}",
+                first, second
+            ),
+            Self::InvalidLength { expected, actual } => write!(
+                formatter,
+                "Invalid window length {}, expected {}",
+                actual, expected
+            ),
+            Self::InvalidCoefficient { line, ref text } => write!(
+                formatter,
+                "Invalid window coefficient '{}' on line {}",
+                text, line
+            ),
+            Self::ReadFailed(ref message) => {
+                write!(formatter, "Failed to read window: {}", message)
+            }
+        }
+    }
+}
+
+impl error::Error for WindowError {}
+
 /// The error type returned when constructing [Resampler](crate::Resampler).
+#[rustfmt::skip]
 pub enum ResamplerConstructionError {
     InvalidSampleRate { input: usize, output: usize },
     InvalidRelativeRatio(f64),
     InvalidRatio(f64),
+    InvalidWarpingCoefficient(f64),
+    InvalidDelayLineLength {
+        channel: usize,
+        expected: usize,
+        actual: usize,
+    },
+    InvalidBandEdges {
+        passband: f32,
+        stopband: f32,
+    },
 }
 
 impl fmt::Display for ResamplerConstructionError {
@@ -97,6 +150,15 @@ impl fmt::Display for ResamplerConstructionError {
             Self::InvalidRelativeRatio(provided) => write!(formatter,
                 "Invalid max_resample_ratio_relative provided: {}. max_resample_ratio_relative must be >= 1", provided
             ),
+            Self::InvalidWarpingCoefficient(provided) => write!(formatter,
+                "Invalid warping_coefficient provided: {}. warping_coefficient must be > -1 and < 1", provided
+            ),
+            Self::InvalidDelayLineLength{channel, expected, actual} => write!(formatter,
+                "Invalid delay line length for channel {}, expected {}, got {}", channel, expected, actual
+            ),
+            Self::InvalidBandEdges{passband, stopband} => write!(formatter,
+                "Invalid band edges provided, passband: {}, stopband: {}. The edges must fulfil 0 < passband < stopband <= 1, and the transition band must be wide enough for a sinc length of at most {}", passband, stopband, crate::asynchro_sinc::MAX_BAND_EDGE_SINC_LEN
+            ),
         }
     }
 }
@@ -156,6 +218,47 @@ pub enum ResampleError {
         requested: usize,
     },
     ChunkSizeNotAdjustable,
+    /// Error raised when the number of frames to process in a single call exceeds the limit
+    /// set with [Resampler::set_max_block_frames](crate::Resampler::set_max_block_frames).
+    BlockTooLarge {
+        max: usize,
+        requested: usize,
+    },
+    /// Error raised when the gain envelope passed to
+    /// [Resampler::process_with_envelope](crate::Resampler::process_with_envelope)
+    /// is shorter than the number of output frames.
+    InsufficientEnvelopeLength {
+        expected: usize,
+        actual: usize,
+    },
+    /// Error raised by [OutputLockedResampler](crate::OutputLockedResampler)
+    /// when there is not enough buffered input to produce the next output chunk.
+    InputUnderrun {
+        needed: usize,
+        available: usize,
+    },
+    /// Error raised when a row of the gain matrix passed to
+    /// [SincFixedIn::process_with_gain_matrix](crate::SincFixedIn::process_with_gain_matrix)
+    /// doesn't have one gain per input channel.
+    WrongNumberOfMatrixColumns {
+        row: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// Error raised when the scratch buffer passed to
+    /// [SincFixedIn::process_noalloc](crate::SincFixedIn::process_noalloc)
+    /// is shorter than needed.
+    InsufficientScratchSize {
+        expected: usize,
+        actual: usize,
+    },
+    /// Error raised when the control signal passed to
+    /// [SincFixedIn::process_with_sidechain](crate::SincFixedIn::process_with_sidechain)
+    /// is shorter than the chunk size.
+    InsufficientSidechainLength {
+        expected: usize,
+        actual: usize,
+    },
 }
 
 impl fmt::Display for ResampleError {
@@ -225,6 +328,52 @@ impl fmt::Display for ResampleError {
             Self::ChunkSizeNotAdjustable { .. } => {
                 write!(f, "This resampler does not support changing the chunk size")
             }
+            Self::BlockTooLarge { max, requested } => {
+                write!(
+                    f,
+                    "Block of {} frames is larger than the allowed maximum of {} frames",
+                    requested, max
+                )
+            }
+            Self::InsufficientEnvelopeLength { expected, actual } => {
+                write!(
+                    f,
+                    "Insufficient envelope length {}, expected {}",
+                    actual, expected
+                )
+            }
+            Self::InputUnderrun { needed, available } => {
+                write!(
+                    f,
+                    "Input underrun, {} frames are needed but only {} are available",
+                    needed, available
+                )
+            }
+            Self::WrongNumberOfMatrixColumns {
+                row,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "Wrong number of columns {} in row {} of gain matrix, expected {}",
+                    actual, row, expected
+                )
+            }
+            Self::InsufficientScratchSize { expected, actual } => {
+                write!(
+                    f,
+                    "Insufficient scratch buffer size {}, expected {}",
+                    actual, expected
+                )
+            }
+            Self::InsufficientSidechainLength { expected, actual } => {
+                write!(
+                    f,
+                    "Insufficient sidechain length {}, expected {}",
+                    actual, expected
+                )
+            }
         }
     }
 }
@@ -0,0 +1,295 @@
+use crate::error::{ResampleResult, ResamplerConstructionError};
+use crate::{
+    count_clipped, update_mask_from_buffers, validate_buffers, FilterType, QualityInfo, Resampler,
+    Sample, SincFixedIn, SincInterpolationParameters, VecResampler,
+};
+
+/// A factory for creating resamplers, for code that needs to create resamplers
+/// without depending on a specific resampler type.
+/// This allows for example a test double such as [PassthroughResamplerFactory]
+/// to be used in the unit tests of an audio pipeline,
+/// while the production code uses [SincFixedInFactory].
+pub trait ResamplerFactory<T: Sample>: Send + Sync {
+    /// Create a new resampler for the given ratio between output and input sample rates,
+    /// and the given number of channels.
+    fn create(
+        &self,
+        ratio: f64,
+        channels: usize,
+    ) -> Result<Box<dyn VecResampler<T>>, ResamplerConstructionError>;
+}
+
+/// A [ResamplerFactory] that creates [SincFixedIn] resamplers with the given settings.
+#[derive(Debug, Clone)]
+pub struct SincFixedInFactory {
+    /// The parameters for the sinc interpolation.
+    pub parameters: SincInterpolationParameters,
+    /// Maximum ratio that can be set with [Resampler::set_resample_ratio]
+    /// relative to the ratio the resampler is created with, must be >= 1.0.
+    pub max_resample_ratio_relative: f64,
+    /// Size of the input data in frames.
+    pub chunk_size: usize,
+}
+
+impl<T> ResamplerFactory<T> for SincFixedInFactory
+where
+    T: Sample,
+{
+    fn create(
+        &self,
+        ratio: f64,
+        channels: usize,
+    ) -> Result<Box<dyn VecResampler<T>>, ResamplerConstructionError> {
+        let resampler = SincFixedIn::<T>::new(
+            ratio,
+            self.max_resample_ratio_relative,
+            self.parameters.clone(),
+            self.chunk_size,
+            channels,
+        )?;
+        Ok(Box::new(resampler))
+    }
+}
+
+/// A [ResamplerFactory] that creates [PassthroughResampler] test doubles.
+#[derive(Debug, Clone)]
+pub struct PassthroughResamplerFactory {
+    /// Size of the input and output data in frames.
+    pub chunk_size: usize,
+}
+
+impl<T> ResamplerFactory<T> for PassthroughResamplerFactory
+where
+    T: Sample,
+{
+    fn create(
+        &self,
+        ratio: f64,
+        channels: usize,
+    ) -> Result<Box<dyn VecResampler<T>>, ResamplerConstructionError> {
+        let resampler = PassthroughResampler::<T>::new(ratio, self.chunk_size, channels)?;
+        Ok(Box::new(resampler))
+    }
+}
+
+/// A test double that implements the [Resampler] trait without resampling.
+/// Each call copies a chunk of input frames unchanged to the output,
+/// so that code using a resampler can be tested without the cost of a real resampler.
+///
+/// The resampling ratio is stored, and can be changed without limits,
+/// but it does not affect the output.
+/// The resampler has no delay, and no filter.
+#[derive(Debug, Clone)]
+pub struct PassthroughResampler<T> {
+    nbr_channels: usize,
+    chunk_size: usize,
+    resample_ratio: f64,
+    resample_ratio_original: f64,
+    channel_mask: Vec<bool>,
+    max_block_frames: Option<usize>,
+    clip_ceiling: Option<T>,
+    clipped_samples: usize,
+    flush_remaining: Option<usize>,
+}
+
+impl<T> PassthroughResampler<T>
+where
+    T: Sample,
+{
+    /// Create a new PassthroughResampler.
+    ///
+    /// Parameters are:
+    /// - `resample_ratio`: Ratio reported by the resampler, must be > 0.
+    /// - `chunk_size`: Size of input and output data in frames.
+    /// - `nbr_channels`: Number of channels in input/output.
+    pub fn new(
+        resample_ratio: f64,
+        chunk_size: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        if resample_ratio <= 0.0 {
+            return Err(ResamplerConstructionError::InvalidRatio(resample_ratio));
+        }
+        Ok(PassthroughResampler {
+            nbr_channels,
+            chunk_size,
+            resample_ratio,
+            resample_ratio_original: resample_ratio,
+            channel_mask: vec![true; nbr_channels],
+            max_block_frames: None,
+            clip_ceiling: None,
+            clipped_samples: 0,
+            flush_remaining: None,
+        })
+    }
+
+    /// Get the current resampling ratio, which does not affect the output.
+    pub fn resample_ratio(&self) -> f64 {
+        self.resample_ratio
+    }
+}
+
+impl<T> Resampler<T> for PassthroughResampler<T>
+where
+    T: Sample,
+{
+    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+        &mut self,
+        wave_in: &[Vin],
+        wave_out: &mut [Vout],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)> {
+        self.flush_remaining = None;
+        if let Some(mask) = active_channels_mask {
+            self.channel_mask.copy_from_slice(mask);
+        } else {
+            update_mask_from_buffers(&mut self.channel_mask);
+        };
+        validate_buffers(
+            wave_in,
+            wave_out,
+            &self.channel_mask,
+            self.nbr_channels,
+            self.chunk_size,
+            self.chunk_size,
+            self.max_block_frames,
+        )?;
+        for (chan, active) in self.channel_mask.iter().enumerate() {
+            if *active {
+                wave_out[chan].as_mut()[..self.chunk_size]
+                    .copy_from_slice(&wave_in[chan].as_ref()[..self.chunk_size]);
+            }
+        }
+        self.clipped_samples += count_clipped(
+            wave_out,
+            &self.channel_mask,
+            self.chunk_size,
+            self.clip_ceiling,
+        );
+        Ok((self.chunk_size, self.chunk_size))
+    }
+
+    fn input_frames_max(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn input_frames_next(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn nbr_channels(&self) -> usize {
+        self.nbr_channels
+    }
+
+    fn output_frames_max(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn output_frames_next(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn output_delay(&self) -> usize {
+        0
+    }
+
+    fn set_resample_ratio(&mut self, new_ratio: f64, _ramp: bool) -> ResampleResult<()> {
+        self.resample_ratio = new_ratio;
+        Ok(())
+    }
+
+    fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()> {
+        let new_ratio = self.resample_ratio_original * rel_ratio;
+        Resampler::set_resample_ratio(self, new_ratio, ramp)
+    }
+
+    fn set_max_block_frames(&mut self, max_frames: Option<usize>) {
+        self.max_block_frames = max_frames;
+    }
+
+    fn flush_remaining(&self) -> Option<usize> {
+        self.flush_remaining
+    }
+
+    fn set_flush_remaining(&mut self, remaining: Option<usize>) {
+        self.flush_remaining = remaining;
+    }
+
+    fn quality_info(&self) -> QualityInfo {
+        QualityInfo {
+            filter: FilterType::Passthrough,
+            attenuation_db: None,
+            transition_bandwidth: None,
+            latency: 0,
+        }
+    }
+
+    fn set_clip_ceiling(&mut self, ceiling: Option<T>) {
+        self.clip_ceiling = ceiling;
+    }
+
+    fn clipped_sample_count(&self) -> usize {
+        self.clipped_samples
+    }
+
+    /// The output has the same rate as the input, and this always returns `Some((1, 1))`.
+    fn is_rational(&self) -> Option<(usize, usize)> {
+        Some((1, 1))
+    }
+
+    fn reset(&mut self) {
+        self.clipped_samples = 0;
+        self.flush_remaining = None;
+        self.channel_mask.iter_mut().for_each(|val| *val = true);
+        self.resample_ratio = self.resample_ratio_original;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PassthroughResamplerFactory, ResamplerFactory, SincFixedInFactory};
+    use crate::{SincInterpolationParameters, SincInterpolationType, WindowFunction};
+    use test_log::test;
+
+    // Process one chunk with a resampler from the factory, like a pipeline under test would.
+    fn run_pipeline(factory: &dyn ResamplerFactory<f64>, ratio: f64) -> Vec<Vec<f64>> {
+        let mut resampler = factory.create(ratio, 2).unwrap();
+        let frames = resampler.input_frames_next();
+        let waves = vec![(0..frames).map(|n| n as f64 / frames as f64).collect(); 2];
+        resampler.process(&waves, None).unwrap()
+    }
+
+    #[test]
+    fn passthrough_factory() {
+        let factory = PassthroughResamplerFactory { chunk_size: 256 };
+        let output = run_pipeline(&factory, 2.0);
+        assert_eq!(output.len(), 2);
+        for wave in output.iter() {
+            assert_eq!(wave.len(), 256);
+            for (n, value) in wave.iter().enumerate() {
+                assert_eq!(*value, n as f64 / 256.0);
+            }
+        }
+        assert!(ResamplerFactory::<f64>::create(&factory, 0.0, 2).is_err());
+    }
+
+    #[test]
+    fn sinc_factory() {
+        let factory = SincFixedInFactory {
+            parameters: SincInterpolationParameters {
+                sinc_len: 64,
+                f_cutoff: 0.95,
+                interpolation: SincInterpolationType::Linear,
+                oversampling_factor: 16,
+                window: WindowFunction::BlackmanHarris2,
+            },
+            max_resample_ratio_relative: 1.0,
+            chunk_size: 256,
+        };
+        let output = run_pipeline(&factory, 2.0);
+        assert_eq!(output.len(), 2);
+        // The first chunk is a little shorter than the chunk size multiplied by the ratio.
+        assert!(output[0].len() > 400 && output[0].len() <= 512);
+        assert!(ResamplerFactory::<f64>::create(&factory, -1.0, 2).is_err());
+    }
+}
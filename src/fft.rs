@@ -0,0 +1,156 @@
+//! Helpers for the FFT based synchronous resamplers.
+
+use crate::Sample;
+use num_complex::Complex;
+use num_integer as integer;
+use realfft::{RealFftPlanner, RealToComplex};
+use std::sync::Arc;
+
+/// The prime factors that give an efficient FFT.
+const SMALL_PRIMES: [usize; 4] = [2, 3, 5, 7];
+
+/// Helper function. Check if a value has only small prime factors.
+fn has_small_factors(value: usize) -> bool {
+    if value == 0 {
+        return false;
+    }
+    let mut remaining = value;
+    for prime in SMALL_PRIMES {
+        while remaining % prime == 0 {
+            remaining /= prime;
+        }
+    }
+    remaining == 1
+}
+
+/// Recommend a chunk size for an [FftFixedInOut](crate::FftFixedInOut) resampler,
+/// close to `approx_chunk_size`, that gives efficient FFT lengths.
+///
+/// The input chunk size of an FFT resampler is always a multiple of
+/// `sample_rate_input / gcd(sample_rate_input, sample_rate_output)`,
+/// and the FFT lengths are proportional to the number of such multiples.
+/// This function finds the multiple closest to `approx_chunk_size`
+/// for which the multiplier only has prime factors of 2, 3, 5 and 7.
+/// The returned value is the input chunk size in frames.
+///
+/// Note that the prime factors of the reduced sample rates themselves cannot be changed.
+/// For the common rates, such as 44100 and 48000, these factors are all small.
+pub fn recommend_chunk_size(
+    sample_rate_input: usize,
+    sample_rate_output: usize,
+    approx_chunk_size: usize,
+) -> usize {
+    let gcd = integer::gcd(sample_rate_input, sample_rate_output).max(1);
+    let min_chunk_in = (sample_rate_input / gcd).max(1);
+    let start = ((approx_chunk_size as f64 / min_chunk_in as f64).round() as usize).max(1);
+    let distance_to_target =
+        |multiple: usize| (multiple * min_chunk_in).abs_diff(approx_chunk_size);
+    let mut distance = 0;
+    loop {
+        let lower = start
+            .checked_sub(distance)
+            .filter(|multiple| *multiple >= 1);
+        let upper = start + distance;
+        let best = lower
+            .into_iter()
+            .chain(std::iter::once(upper))
+            .filter(|multiple| has_small_factors(*multiple))
+            .min_by_key(|multiple| distance_to_target(*multiple));
+        if let Some(multiple) = best {
+            debug!(
+                "Recommended chunk size {} for rates {} -> {}, requested {}",
+                multiple * min_chunk_in,
+                sample_rate_input,
+                sample_rate_output,
+                approx_chunk_size
+            );
+            return multiple * min_chunk_in;
+        }
+        distance += 1;
+    }
+}
+
+/// Helper for calculating the magnitude spectrum of blocks of audio,
+/// zero-padded to a fixed FFT length.
+/// The buffers are allocated once, so that the spectra can be calculated while processing.
+pub(crate) struct SpectrumAnalyzer<T> {
+    fft: Arc<dyn RealToComplex<T>>,
+    input: Vec<T>,
+    spectrum: Vec<Complex<T>>,
+    scratch: Vec<Complex<T>>,
+}
+
+impl<T> SpectrumAnalyzer<T>
+where
+    T: Sample,
+{
+    /// Create a new SpectrumAnalyzer for the given FFT length.
+    pub fn new(fft_size: usize) -> Self {
+        let mut planner = RealFftPlanner::<T>::new();
+        let fft = planner.plan_fft_forward(fft_size);
+        SpectrumAnalyzer {
+            input: fft.make_input_vec(),
+            spectrum: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+        }
+    }
+
+    /// Get the number of bins in the spectrum, which is `fft_size / 2 + 1`.
+    pub fn nbr_bins(&self) -> usize {
+        self.spectrum.len()
+    }
+
+    /// Calculate the linear magnitudes of the spectrum of `wave`,
+    /// zero-padded or truncated to the FFT length, and store them in `magnitudes`.
+    pub fn magnitudes(&mut self, wave: &[T], magnitudes: &mut [f32]) {
+        let len = wave.len().min(self.input.len());
+        self.input[..len].copy_from_slice(&wave[..len]);
+        self.input[len..]
+            .iter_mut()
+            .for_each(|value| *value = T::zero());
+        self.fft
+            .process_with_scratch(&mut self.input, &mut self.spectrum, &mut self.scratch)
+            .expect("The buffers have the lengths given by the FFT");
+        for (magnitude, value) in magnitudes.iter_mut().zip(self.spectrum.iter()) {
+            *magnitude = (value.re * value.re + value.im * value.im).sqrt().to_f64() as f32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_small_factors, recommend_chunk_size};
+    use crate::{FftFixedInOut, Resampler};
+    use test_log::test;
+
+    #[test]
+    fn small_factors() {
+        assert!(has_small_factors(1));
+        assert!(has_small_factors(2 * 3 * 5 * 7 * 7));
+        assert!(!has_small_factors(11));
+        assert!(!has_small_factors(2 * 13));
+        assert!(!has_small_factors(0));
+    }
+
+    #[test]
+    fn recommended_sizes_have_small_factors() {
+        for (rate_in, rate_out) in [
+            (44100, 48000),
+            (48000, 44100),
+            (44100, 96000),
+            (8000, 48000),
+        ] {
+            for approx in [1, 100, 1000, 1023, 4096, 10000] {
+                let chunk_size = recommend_chunk_size(rate_in, rate_out, approx);
+                let resampler =
+                    FftFixedInOut::<f64>::new(rate_in, rate_out, chunk_size, 1).unwrap();
+                assert_eq!(resampler.input_frames_next(), chunk_size);
+                // The FFT lengths are twice the input and output chunk sizes.
+                assert!(has_small_factors(2 * chunk_size));
+                assert!(has_small_factors(2 * resampler.output_frames_next()));
+                assert!(chunk_size.abs_diff(approx) <= chunk_size.max(approx) / 2 + 441);
+            }
+        }
+    }
+}
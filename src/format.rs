@@ -0,0 +1,304 @@
+use crate::Sample;
+
+/// The sample formats that can be decoded from raw bytes.
+/// Integer formats are scaled to the range -1.0 to +1.0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Signed 16 bit integer.
+    S16,
+    /// Signed 24 bit integer, packed in 3 bytes.
+    S24,
+    /// Signed 32 bit integer.
+    S32,
+    /// 32 bit float.
+    F32,
+    /// 64 bit float.
+    F64,
+}
+
+/// Byte order of raw sample data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    /// Little endian, least significant byte first.
+    Little,
+    /// Big endian, most significant byte first. This is also known as network byte order.
+    Big,
+}
+
+impl SampleFormat {
+    /// Get the number of bytes used to store a single sample.
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            SampleFormat::S16 => 2,
+            SampleFormat::S24 => 3,
+            SampleFormat::S32 => 4,
+            SampleFormat::F32 => 4,
+            SampleFormat::F64 => 8,
+        }
+    }
+}
+
+/// Helper function. Copy the bytes of a single sample into a little endian array.
+fn to_le_array<const N: usize>(bytes: &[u8], endian: Endian) -> [u8; N] {
+    let mut array = [0u8; N];
+    array.copy_from_slice(&bytes[..N]);
+    if endian == Endian::Big {
+        array.reverse();
+    }
+    array
+}
+
+/// Helper function. Decode a single sample to a float value.
+fn decode_sample(bytes: &[u8], format: SampleFormat, endian: Endian) -> f64 {
+    match format {
+        SampleFormat::S16 => i16::from_le_bytes(to_le_array(bytes, endian)) as f64 / 32768.0,
+        SampleFormat::S24 => {
+            let le: [u8; 3] = to_le_array(bytes, endian);
+            // Place the 24 bits in the upper part of an i32 to get the sign right.
+            let value = i32::from_le_bytes([0, le[0], le[1], le[2]]) >> 8;
+            value as f64 / 8388608.0
+        }
+        SampleFormat::S32 => i32::from_le_bytes(to_le_array(bytes, endian)) as f64 / 2147483648.0,
+        SampleFormat::F32 => f32::from_le_bytes(to_le_array(bytes, endian)) as f64,
+        SampleFormat::F64 => f64::from_le_bytes(to_le_array(bytes, endian)),
+    }
+}
+
+/// Decode raw interleaved sample data into one vector per channel.
+/// Any incomplete frame at the end of the data is ignored.
+pub fn decode_interleaved<T>(
+    bytes: &[u8],
+    format: SampleFormat,
+    endian: Endian,
+    channels: usize,
+) -> Vec<Vec<T>>
+where
+    T: Sample,
+{
+    let sample_size = format.bytes_per_sample();
+    let frame_size = sample_size * channels;
+    let frames = bytes.len().checked_div(frame_size).unwrap_or(0);
+    let mut wave = vec![Vec::with_capacity(frames); channels];
+    for frame in bytes.chunks_exact(frame_size.max(1)).take(frames) {
+        for (sample, chan) in frame.chunks_exact(sample_size).zip(wave.iter_mut()) {
+            chan.push(T::from_f64_saturating(decode_sample(
+                sample, format, endian,
+            )));
+        }
+    }
+    wave
+}
+
+/// Helper function. Encode a single float value into `out`,
+/// that must have the length of one sample.
+/// Integer formats are rounded and clamped to the range of the type.
+#[cfg(feature = "bytes")]
+fn encode_sample(value: f64, format: SampleFormat, endian: Endian, out: &mut [u8]) {
+    let scale_int = |scale: f64, max: f64| (value * scale).round().clamp(-scale, max);
+    let mut le = [0u8; 8];
+    match format {
+        SampleFormat::S16 => {
+            le[..2].copy_from_slice(&(scale_int(32768.0, 32767.0) as i16).to_le_bytes())
+        }
+        SampleFormat::S24 => {
+            le[..4].copy_from_slice(&(scale_int(8388608.0, 8388607.0) as i32).to_le_bytes())
+        }
+        SampleFormat::S32 => {
+            le[..4].copy_from_slice(&(scale_int(2147483648.0, 2147483647.0) as i32).to_le_bytes())
+        }
+        SampleFormat::F32 => le[..4].copy_from_slice(&(value as f32).to_le_bytes()),
+        SampleFormat::F64 => le.copy_from_slice(&value.to_le_bytes()),
+    }
+    out.copy_from_slice(&le[..out.len()]);
+    if endian == Endian::Big {
+        out.reverse();
+    }
+}
+
+/// Encode the first `frames` frames of one vector per channel as raw interleaved sample data,
+/// and append the bytes to `out`.
+#[cfg(feature = "bytes")]
+pub fn encode_interleaved<T>(
+    wave: &[Vec<T>],
+    frames: usize,
+    format: SampleFormat,
+    endian: Endian,
+    out: &mut bytes::BytesMut,
+) where
+    T: Sample,
+{
+    let sample_size = format.bytes_per_sample();
+    let start = out.len();
+    out.resize(start + frames * wave.len() * sample_size, 0);
+    let mut samples = out[start..].chunks_exact_mut(sample_size);
+    for frame in 0..frames {
+        for chan in wave.iter() {
+            if let Some(sample) = samples.next() {
+                encode_sample(chan[frame].to_f64(), format, endian, sample);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_interleaved, Endian, SampleFormat};
+    use crate::WindowFunction;
+    use crate::{
+        Resampler, SincFixedIn, SincFixedOut, SincInterpolationParameters, SincInterpolationType,
+    };
+    use test_log::test;
+
+    #[test]
+    fn decode_formats() {
+        let s16: Vec<Vec<f64>> = decode_interleaved(
+            &[0x00, 0x40, 0x00, 0xc0],
+            SampleFormat::S16,
+            Endian::Little,
+            2,
+        );
+        assert_eq!(s16, vec![vec![0.5], vec![-0.5]]);
+        let s24: Vec<Vec<f64>> =
+            decode_interleaved(&[0xc0, 0x00, 0x00], SampleFormat::S24, Endian::Big, 1);
+        assert_eq!(s24, vec![vec![-0.5]]);
+        let s32: Vec<Vec<f64>> =
+            decode_interleaved(&[0x40, 0x00, 0x00, 0x00], SampleFormat::S32, Endian::Big, 1);
+        assert_eq!(s32, vec![vec![0.5]]);
+        let f32_bytes = 0.25f32.to_be_bytes();
+        let f32s: Vec<Vec<f32>> = decode_interleaved(&f32_bytes, SampleFormat::F32, Endian::Big, 1);
+        assert_eq!(f32s, vec![vec![0.25]]);
+    }
+
+    #[test]
+    fn decode_saturates_out_of_range() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&1.0e300f64.to_le_bytes());
+        bytes.extend_from_slice(&(-1.0e300f64).to_le_bytes());
+        let wave: Vec<Vec<f32>> = decode_interleaved(&bytes, SampleFormat::F64, Endian::Little, 1);
+        assert_eq!(wave, vec![vec![f32::MAX, f32::MIN]]);
+    }
+
+    #[test]
+    fn decode_ignores_incomplete_frame() {
+        let bytes = [0u8; 7];
+        let wave: Vec<Vec<f64>> = decode_interleaved(&bytes, SampleFormat::S16, Endian::Little, 2);
+        assert_eq!(wave[0].len(), 1);
+        assert_eq!(wave[1].len(), 1);
+    }
+
+    #[test]
+    fn process_big_and_little_endian() {
+        let params = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler_le = SincFixedIn::<f64>::new(1.2, 1.0, params, 256, 2).unwrap();
+        let params = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler_be = SincFixedIn::<f64>::new(1.2, 1.0, params, 256, 2).unwrap();
+        let mut bytes_le = Vec::new();
+        let mut bytes_be = Vec::new();
+        for n in 0..2 * 256 {
+            let value = ((n as f64 * 0.05).sin() * 20000.0) as i16;
+            bytes_le.extend_from_slice(&value.to_le_bytes());
+            bytes_be.extend_from_slice(&value.to_be_bytes());
+        }
+        let (out_le, frames_le) = resampler_le
+            .process_from_bytes(&bytes_le, SampleFormat::S16, Endian::Little, 2)
+            .unwrap();
+        let (out_be, frames_be) = resampler_be
+            .process_from_bytes(&bytes_be, SampleFormat::S16, Endian::Big, 2)
+            .unwrap();
+        assert_eq!(out_le, out_be);
+        assert_eq!(frames_le, 256);
+        assert_eq!(frames_be, 256);
+        assert!(out_le[0].iter().any(|v| v.abs() > 0.1));
+    }
+
+    #[test]
+    fn process_from_bytes_surplus_input() {
+        let params = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler = SincFixedOut::<f64>::new(1.2, 1.0, params, 256, 2).unwrap();
+        let mut reference = resampler.try_clone();
+        let frames_next = resampler.input_frames_next();
+        let total_frames = frames_next + 100;
+        let mut bytes = Vec::new();
+        for n in 0..2 * total_frames {
+            let value = ((n as f64 * 0.05).sin() * 20000.0) as i16;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let (output, frames_in) = resampler
+            .process_from_bytes(&bytes, SampleFormat::S16, Endian::Little, 2)
+            .unwrap();
+        assert_eq!(frames_in, frames_next);
+        assert_eq!(output[0].len(), 256);
+        let wave_in = decode_interleaved::<f64>(&bytes, SampleFormat::S16, Endian::Little, 2);
+        let expected = reference.process(&wave_in, None).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn process_to_bytesmut() {
+        let params = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler = SincFixedIn::<f64>::new(1.2, 1.0, params, 256, 2).unwrap();
+        let waves = (0..2)
+            .map(|chan| {
+                (0..256)
+                    .map(|n| 0.5 * (n as f64 * 0.05 + chan as f64).sin())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut reference = resampler.try_clone();
+        let expected = reference.process(&waves, None).unwrap();
+        for format in [SampleFormat::S16, SampleFormat::S24, SampleFormat::F32] {
+            for endian in [Endian::Little, Endian::Big] {
+                resampler.reset();
+                let mut buf = bytes::BytesMut::from(&b"head"[..]);
+                let frames = resampler
+                    .process_to_bytesmut(&waves, format, endian, &mut buf)
+                    .unwrap();
+                assert_eq!(frames, expected[0].len());
+                assert_eq!(&buf[..4], b"head");
+                let decoded: Vec<Vec<f64>> = decode_interleaved(&buf[4..], format, endian, 2);
+                assert_eq!(decoded[0].len(), frames);
+                let tolerance = match format {
+                    SampleFormat::S16 => 1.0 / 32768.0,
+                    SampleFormat::S24 => 1.0 / 8388608.0,
+                    _ => 1.0e-7,
+                };
+                for (dec_chan, exp_chan) in decoded.iter().zip(expected.iter()) {
+                    for (dec, exp) in dec_chan.iter().zip(exp_chan.iter()) {
+                        assert!(
+                            (dec - exp).abs() <= tolerance,
+                            "{:?}: {} != {}",
+                            format,
+                            dec,
+                            exp
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::windows::{make_window, WindowFunction};
+
+/// Number of non-zero odd taps of the halfband filter.
+const HALFBAND_TAPS: usize = 64;
+
+/// A linear phase halfband lowpass filter with the cutoff at half the Nyquist frequency,
+/// used for resampling by factors of two.
+///
+/// Every second tap of a halfband filter is zero, except the center tap which is 0.5.
+/// Only the odd taps are stored, and the even ones are never multiplied.
+/// The filter is applied without delay, by treating the samples outside the input as zeros.
+pub struct HalfbandFilter {
+    /// The taps at the odd offsets from `1 - HALFBAND_TAPS` to `HALFBAND_TAPS - 1`
+    /// from the center, in order of increasing offset.
+    odd_taps: Vec<f64>,
+}
+
+impl HalfbandFilter {
+    /// Create a new filter, made from a sinc windowed by a squared Blackman-Harris window.
+    pub fn new() -> Self {
+        let half_len = HALFBAND_TAPS;
+        let window = make_window::<f64>(2 * half_len, WindowFunction::BlackmanHarris2);
+        let mut odd_taps = (0..HALFBAND_TAPS)
+            .map(|j| {
+                let offset = 2 * j as isize + 1 - HALFBAND_TAPS as isize;
+                let x = std::f64::consts::FRAC_PI_2 * offset as f64;
+                window[(half_len as isize + offset) as usize] * x.sin() / x
+            })
+            .collect::<Vec<_>>();
+        // Normalize the odd taps to sum to 0.5, which together with the center tap gives unity gain at DC.
+        let sum: f64 = odd_taps.iter().sum();
+        odd_taps.iter_mut().for_each(|tap| *tap *= 0.5 / sum);
+        HalfbandFilter { odd_taps }
+    }
+
+    /// Helper function. Sum the odd taps multiplied by the input samples
+    /// at `first_index - j`, for tap `j`.
+    /// The input holds only the samples that meet the odd taps,
+    /// and samples outside of it are treated as zeros.
+    fn odd_sum(&self, input: &[f64], first_index: isize) -> f64 {
+        let last_index = first_index - (HALFBAND_TAPS as isize - 1);
+        if last_index >= 0 && (first_index as usize) < input.len() {
+            // All taps are inside the input, and no bounds checks are needed.
+            return self
+                .odd_taps
+                .iter()
+                .zip(
+                    input[last_index as usize..=first_index as usize]
+                        .iter()
+                        .rev(),
+                )
+                .map(|(tap, value)| tap * value)
+                .sum();
+        }
+        self.odd_taps
+            .iter()
+            .enumerate()
+            .filter_map(|(j, tap)| {
+                let idx = first_index - j as isize;
+                if idx >= 0 && (idx as usize) < input.len() {
+                    Some(tap * input[idx as usize])
+                } else {
+                    None
+                }
+            })
+            .sum()
+    }
+
+    /// Upsample by a factor of two. The even output samples are copies of the input,
+    /// and the odd ones are interpolated by the filter.
+    pub fn upsample(&self, input: &[f64]) -> Vec<f64> {
+        let mut output = Vec::with_capacity(2 * input.len());
+        for (n, value) in input.iter().enumerate() {
+            output.push(*value);
+            // The zero stuffing halves the gain, which is compensated by the factor 2.
+            // The zeros between the input samples meet the even taps, and are skipped.
+            let first_index = (n + HALFBAND_TAPS / 2) as isize;
+            output.push(2.0 * self.odd_sum(input, first_index));
+        }
+        output
+    }
+
+    /// Downsample by a factor of two, keeping the filtered even input samples.
+    pub fn downsample(&self, input: &[f64]) -> Vec<f64> {
+        // Only the odd input samples meet the odd taps.
+        let odd_samples = input.iter().skip(1).step_by(2).copied().collect::<Vec<_>>();
+        (0..input.len())
+            .step_by(2)
+            .map(|n| {
+                let first_index = (n / 2 + HALFBAND_TAPS / 2 - 1) as isize;
+                0.5 * input[n] + self.odd_sum(&odd_samples, first_index)
+            })
+            .collect()
+    }
+}
+
+/// Get the exponent `k` if the ratio is exactly `2^k`, for `k` between -3 and 3 but not zero.
+pub fn power_of_two_exponent(ratio: f64) -> Option<i32> {
+    let exponent = ratio.log2().round() as i32;
+    if exponent != 0 && exponent.abs() <= 3 && 2.0f64.powi(exponent) == ratio {
+        Some(exponent)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{power_of_two_exponent, HalfbandFilter};
+    use test_log::test;
+
+    #[test]
+    fn detect_power_of_two() {
+        assert_eq!(power_of_two_exponent(2.0), Some(1));
+        assert_eq!(power_of_two_exponent(8.0), Some(3));
+        assert_eq!(power_of_two_exponent(0.25), Some(-2));
+        assert_eq!(power_of_two_exponent(1.0), None);
+        assert_eq!(power_of_two_exponent(16.0), None);
+        assert_eq!(power_of_two_exponent(2.0001), None);
+        assert_eq!(power_of_two_exponent(48000.0 / 44100.0), None);
+    }
+
+    #[test]
+    fn up_and_down() {
+        let filter = HalfbandFilter::new();
+        let input = (0..1000)
+            .map(|n| (n as f64 * 0.1).sin())
+            .collect::<Vec<_>>();
+        let upsampled = filter.upsample(&input);
+        assert_eq!(upsampled.len(), 2000);
+        for (n, value) in upsampled.iter().enumerate().skip(200).take(1600) {
+            let expected = (n as f64 * 0.05).sin();
+            assert!((value - expected).abs() < 1.0e-4, "{} {}", value, expected);
+        }
+        let downsampled = filter.downsample(&upsampled);
+        assert_eq!(downsampled.len(), 1000);
+        for (value, expected) in downsampled.iter().zip(input.iter()).skip(100).take(800) {
+            assert!((value - expected).abs() < 1.0e-4);
+        }
+    }
+}
@@ -35,29 +35,105 @@ macro_rules! error { ($($x:tt)*) => (
     }
 ) }
 
+// Tracing wrapper macro, entering a span that lasts until the end of the enclosing block.
+macro_rules! construction_span { ($($x:tt)*) => (
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!($($x)*).entered();
+) }
+
+mod adaptive_rate;
+pub mod analysis;
+mod asynchro_const;
 mod asynchro_fast;
 mod asynchro_sinc;
+mod bark;
+mod channels;
 mod error;
+mod factory;
+#[cfg(feature = "fft_resampler")]
+pub mod fft;
+mod format;
+mod halfband;
 mod interpolation;
+mod metrics;
+mod output_locked;
+mod phase_stepper;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod quality;
 mod sample;
+mod simple;
 mod sinc;
+mod sliding_window;
 #[cfg(feature = "fft_resampler")]
 mod synchro;
 mod windows;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod windows_avx;
 
 pub mod sinc_interpolator;
+pub mod testing;
 
+pub use crate::adaptive_rate::AdaptiveRateResampler;
+pub use crate::asynchro_const::SincFixedInConst;
 pub use crate::asynchro_fast::{FastFixedIn, FastFixedOut, PolynomialDegree};
+#[cfg(feature = "debug-interp")]
+pub use crate::asynchro_sinc::InterpolationDebugFrame;
 pub use crate::asynchro_sinc::{
-    SincFixedIn, SincFixedOut, SincInterpolationParameters, SincInterpolationType,
+    QualityMetrics, SincCheckpoint, SincFixedIn, SincFixedOut, SincInterpolationParameters,
+    SincInterpolationType, SoftClipStats,
 };
+pub use crate::bark::{BarkSincConfig, BarkSincTable};
+pub use crate::channels::{ChannelLabel, StereoFormat};
 pub use crate::error::{
     CpuFeature, MissingCpuFeature, ResampleError, ResampleResult, ResamplerConstructionError,
+    WindowError,
 };
+pub use crate::factory::{
+    PassthroughResampler, PassthroughResamplerFactory, ResamplerFactory, SincFixedInFactory,
+};
+pub use crate::format::{Endian, SampleFormat};
+pub use crate::metrics::ResamplerMetrics;
+pub use crate::output_locked::OutputLockedResampler;
+pub use crate::phase_stepper::PhaseStepper;
+#[cfg(feature = "profiling")]
+pub use crate::profiling::ProfilingReport;
+pub use crate::quality::{FilterType, QualityInfo};
 pub use crate::sample::Sample;
+pub use crate::simple::{
+    downsample_2x, resample_mono, resample_with_crossfade, upsample_2x, Quality, ResampledLoop,
+};
+pub use crate::sliding_window::SlidingWindowProcessor;
 #[cfg(feature = "fft_resampler")]
 pub use crate::synchro::{FftFixedIn, FftFixedInOut, FftFixedOut};
-pub use crate::windows::{calculate_cutoff, WindowFunction};
+#[doc(hidden)]
+pub use crate::windows::bench_make_window;
+#[cfg(feature = "fft_resampler")]
+pub use crate::windows::window_autocorrelation;
+pub use crate::windows::{
+    all_window_functions, calculate_cutoff, window_frequency_response, window_from_csv,
+    window_overlap_add_scale, window_product, WindowFunction,
+};
+
+use std::collections::HashMap;
+
+/// The handling of a new resampling ratio outside the bounds given by the maximum relative ratio,
+/// see [Resampler::set_ratio_overflow_policy].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Return [ResampleError::RatioOutOfBounds] and keep the current ratio. This is the default.
+    Error,
+    /// Clamp the ratio to the nearest bound.
+    Clamp,
+}
+
+/// Container for temporary per-channel values, such as the slices of the channels
+/// in a chunk of input. With the `smallvec` feature, up to two channels are stored inline,
+/// which avoids a heap allocation for mono and stereo.
+#[cfg(feature = "smallvec")]
+pub(crate) type ChannelVec<U> = smallvec::SmallVec<[U; 2]>;
+#[cfg(not(feature = "smallvec"))]
+pub(crate) type ChannelVec<U> = Vec<U>;
 
 /// A resampler that is used to resample a chunk of audio to a new sample rate.
 /// For asynchronous resamplers, the rate can be adjusted as required.
@@ -146,7 +222,7 @@ where
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<(usize, usize)> {
         let frames = self.input_frames_next();
-        let mut wave_in_padded = Vec::with_capacity(self.nbr_channels());
+        let mut wave_in_padded = ChannelVec::with_capacity(self.nbr_channels());
         for _ in 0..self.nbr_channels() {
             wave_in_padded.push(vec![T::zero(); frames]);
         }
@@ -194,6 +270,403 @@ where
         Ok(wave_out)
     }
 
+    /// Resample a buffer of audio to a pre-allocated output buffer, and apply a gain envelope
+    /// to the output. This is useful for fading in or out while resampling.
+    /// The envelope holds one gain value per output frame, and is applied to all active channels.
+    ///
+    /// The envelope must contain at least as many values as returned by
+    /// [output_frames_next](Resampler::output_frames_next), otherwise
+    /// [ResampleError::InsufficientEnvelopeLength] is returned without processing any data.
+    /// Any extra values at the end of the envelope are ignored.
+    ///
+    /// Apart from the envelope, this behaves exactly like
+    /// [process_into_buffer](Resampler::process_into_buffer), and the return value is the same.
+    fn process_with_envelope<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+        &mut self,
+        wave_in: &[Vin],
+        wave_out: &mut [Vout],
+        envelope: &[T],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)> {
+        let needed = self.output_frames_next();
+        if envelope.len() < needed {
+            return Err(ResampleError::InsufficientEnvelopeLength {
+                expected: needed,
+                actual: envelope.len(),
+            });
+        }
+        let (frames_in, frames_out) =
+            self.process_into_buffer(wave_in, wave_out, active_channels_mask)?;
+        for (chan, wave) in wave_out.iter_mut().enumerate() {
+            if active_channels_mask.map(|mask| mask[chan]).unwrap_or(true) {
+                for (value, gain) in wave.as_mut()[..frames_out].iter_mut().zip(envelope) {
+                    *value *= *gain;
+                }
+            }
+        }
+        Ok((frames_in, frames_out))
+    }
+
+    /// This is a convenience method for feeding a downstream processor that needs
+    /// the audio in blocks of a fixed size.
+    /// The input is resampled by [process](Resampler::process), and the output is split
+    /// into sub-blocks of `subblock` frames. The last sub-block is shorter if the number
+    /// of output frames is not a multiple of `subblock`.
+    /// Each sub-block holds one vector per channel.
+    ///
+    /// This method allocates space for the output. It panics if `subblock` is zero.
+    fn process_into_subblocks<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        subblock: usize,
+    ) -> ResampleResult<Vec<Vec<Vec<T>>>> {
+        assert!(subblock > 0, "The sub-block size must be larger than zero");
+        let wave_out = self.process(wave_in, None)?;
+        let frames = wave_out.first().map(|wave| wave.len()).unwrap_or_default();
+        let subblocks = (0..frames)
+            .step_by(subblock)
+            .map(|start| {
+                let end = (start + subblock).min(frames);
+                wave_out
+                    .iter()
+                    .map(|wave| wave[start..end].to_vec())
+                    .collect()
+            })
+            .collect();
+        Ok(subblocks)
+    }
+
+    /// This is a convenience method for processing channels identified by a [ChannelLabel]
+    /// instead of by index, to avoid mixing up the channels.
+    /// The map must contain one entry per channel of the resampler.
+    /// The labels are assigned to the channels of the resampler in the order
+    /// the labels are declared in [ChannelLabel],
+    /// and the output contains the resampled data under the same labels.
+    /// This method allocates space for the output.
+    fn process_labelled(
+        &mut self,
+        wave_in: &HashMap<ChannelLabel, &[T]>,
+    ) -> ResampleResult<HashMap<ChannelLabel, Vec<T>>> {
+        if wave_in.len() != self.nbr_channels() {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: self.nbr_channels(),
+                actual: wave_in.len(),
+            });
+        }
+        let mut labels = wave_in.keys().copied().collect::<Vec<_>>();
+        labels.sort();
+        let ordered = labels
+            .iter()
+            .map(|label| wave_in[label])
+            .collect::<Vec<_>>();
+        let wave_out = self.process(&ordered, None)?;
+        Ok(labels.into_iter().zip(wave_out).collect())
+    }
+
+    /// This is a convenience method for resampling stereo audio in mid/side format.
+    /// The input is left/right stereo, which is converted to mid, `(L + R) / 2`,
+    /// and side, `(L - R) / 2`, before being resampled by [process](Resampler::process).
+    /// The output is returned in the given [StereoFormat].
+    /// For [StereoFormat::LeftRight] it is converted back to left/right after resampling,
+    /// and for [StereoFormat::MidSide] the mid and side channels are returned directly.
+    ///
+    /// The resampler must have two channels, otherwise an error is returned.
+    /// This method allocates space for both the converted input and the output.
+    fn process_mid_side<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        output_format: StereoFormat,
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        if self.nbr_channels() != 2 || wave_in.len() != 2 {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: 2,
+                actual: wave_in.len(),
+            });
+        }
+        let half = T::coerce(0.5);
+        let (left, right) = (wave_in[0].as_ref(), wave_in[1].as_ref());
+        let mid = left
+            .iter()
+            .zip(right)
+            .map(|(l, r)| (*l + *r) * half)
+            .collect::<Vec<_>>();
+        let side = left
+            .iter()
+            .zip(right)
+            .map(|(l, r)| (*l - *r) * half)
+            .collect::<Vec<_>>();
+        let mut wave_out = self.process(&[mid, side], None)?;
+        if output_format == StereoFormat::LeftRight {
+            let (mid, side) = wave_out.split_at_mut(1);
+            for (m, s) in mid[0].iter_mut().zip(side[0].iter_mut()) {
+                let (left, right) = (*m + *s, *m - *s);
+                *m = left;
+                *s = right;
+            }
+        }
+        Ok(wave_out)
+    }
+
+    /// This is a convenience method for processing audio data where all channels are stored
+    /// after each other in a single slice, with a fixed stride between the channels.
+    /// Channel `n` of the input is read from `wave_in[n * in_stride..]`,
+    /// and channel `n` of the output is written to `wave_out[n * out_stride..]`.
+    /// The strides must be large enough to fit the number of frames given by
+    /// [input_frames_next](Resampler::input_frames_next) and
+    /// [output_frames_next](Resampler::output_frames_next),
+    /// otherwise an error is returned.
+    ///
+    /// The return value is the same as for [process_into_buffer](Resampler::process_into_buffer).
+    fn process_strided(
+        &mut self,
+        wave_in: &[T],
+        in_stride: usize,
+        wave_out: &mut [T],
+        out_stride: usize,
+        channels: usize,
+    ) -> ResampleResult<(usize, usize)> {
+        if in_stride < self.input_frames_next() {
+            return Err(ResampleError::InsufficientInputBufferSize {
+                channel: 0,
+                expected: self.input_frames_next(),
+                actual: in_stride,
+            });
+        }
+        if out_stride < self.output_frames_next() {
+            return Err(ResampleError::InsufficientOutputBufferSize {
+                channel: 0,
+                expected: self.output_frames_next(),
+                actual: out_stride,
+            });
+        }
+        let channels_in = wave_in
+            .chunks(in_stride.max(1))
+            .take(channels)
+            .collect::<ChannelVec<_>>();
+        let mut channels_out = wave_out
+            .chunks_mut(out_stride.max(1))
+            .take(channels)
+            .collect::<ChannelVec<_>>();
+        if channels_out.len() < channels {
+            return Err(ResampleError::WrongNumberOfOutputChannels {
+                expected: channels,
+                actual: channels_out.len(),
+            });
+        }
+        self.process_into_buffer(&channels_in, &mut channels_out, None)
+    }
+
+    /// This is a convenience method for resampling a complete clip of audio
+    /// that is longer than the chunk size of the resampler.
+    /// The input is split into chunks of the size given by
+    /// [input_frames_next](Resampler::input_frames_next), which are processed one by one
+    /// and the outputs are concatenated.
+    /// Any remaining frames at the end that are too few to fill a chunk are processed with
+    /// [process_partial_into_buffer](Resampler::process_partial_into_buffer).
+    ///
+    /// The input length is taken as the length of the shortest active channel.
+    /// Note that the resampler is not flushed, meaning that the output is delayed by
+    /// [output_delay](Resampler::output_delay) frames, and that the last frames of the input
+    /// remain in the internal buffers until more data is processed.
+    /// This method allocates space for the complete output.
+    fn process_chunked<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        let channels = self.nbr_channels();
+        if wave_in.len() != channels {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: channels,
+                actual: wave_in.len(),
+            });
+        }
+        let is_active = |chan: usize| active_channels_mask.map(|mask| mask[chan]).unwrap_or(true);
+        let total_frames = wave_in
+            .iter()
+            .enumerate()
+            .filter(|(chan, _)| is_active(*chan))
+            .map(|(_, wave)| wave.as_ref().len())
+            .min()
+            .unwrap_or_default();
+        let mut wave_out = vec![Vec::new(); channels];
+        let mut chunk_out = self.output_buffer_allocate(true);
+        let mut frames_done = 0;
+        while frames_done < total_frames {
+            let chunk_in = wave_in
+                .iter()
+                .map(|wave| {
+                    let wave = wave.as_ref();
+                    &wave[frames_done.min(wave.len())..]
+                })
+                .collect::<ChannelVec<_>>();
+            let remaining = total_frames - frames_done;
+            let (frames_in, frames_out) = if remaining >= self.input_frames_next() {
+                self.process_into_buffer(&chunk_in, &mut chunk_out, active_channels_mask)?
+            } else {
+                let (_, frames_out) = self.process_partial_into_buffer(
+                    Some(&chunk_in[..]),
+                    &mut chunk_out,
+                    active_channels_mask,
+                )?;
+                (remaining, frames_out)
+            };
+            for (chan, (wave, chunk)) in wave_out.iter_mut().zip(chunk_out.iter()).enumerate() {
+                if is_active(chan) {
+                    wave.extend_from_slice(&chunk[..frames_out]);
+                }
+            }
+            frames_done += frames_in;
+        }
+        Ok(wave_out)
+    }
+
+    /// This is a convenience method for processing with a bounded amount of work per call,
+    /// for applications that must return within a fixed time.
+    /// Complete chunks of the input are processed by
+    /// [process_into_buffer](Resampler::process_into_buffer) for as long as the input holds
+    /// enough frames and the next chunk is guaranteed to fit within `max_output_frames`.
+    /// The output is returned together with the number of input frames consumed,
+    /// and the caller should keep the remaining input for the next call.
+    ///
+    /// The input length is taken as the length of the shortest channel.
+    /// No frames are processed if `max_output_frames` is smaller than
+    /// [output_frames_next](Resampler::output_frames_next).
+    /// If processing a chunk fails after other chunks have been processed,
+    /// the output and the consumed frames of those chunks are returned,
+    /// and the error is returned by the next call with the remaining input.
+    /// This method allocates space for the output.
+    fn process_max_frames<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        max_output_frames: usize,
+    ) -> ResampleResult<(Vec<Vec<T>>, usize)> {
+        let channels = self.nbr_channels();
+        if wave_in.len() != channels {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: channels,
+                actual: wave_in.len(),
+            });
+        }
+        let total_frames = wave_in
+            .iter()
+            .map(|wave| wave.as_ref().len())
+            .min()
+            .unwrap_or_default();
+        let mut wave_out = vec![Vec::new(); channels];
+        let mut chunk_out = self.output_buffer_allocate(true);
+        let mut frames_in_done = 0;
+        let mut frames_out_done = 0;
+        while total_frames - frames_in_done >= self.input_frames_next()
+            && frames_out_done + self.output_frames_next() <= max_output_frames
+        {
+            let chunk_in = wave_in
+                .iter()
+                .map(|wave| &wave.as_ref()[frames_in_done..])
+                .collect::<ChannelVec<_>>();
+            let (frames_in, frames_out) =
+                match self.process_into_buffer(&chunk_in, &mut chunk_out, None) {
+                    Ok(frames) => frames,
+                    Err(_) if frames_in_done + frames_out_done > 0 => break,
+                    Err(err) => return Err(err),
+                };
+            for (wave, chunk) in wave_out.iter_mut().zip(chunk_out.iter()) {
+                wave.extend_from_slice(&chunk[..frames_out]);
+            }
+            frames_in_done += frames_in;
+            frames_out_done += frames_out;
+        }
+        Ok((wave_out, frames_in_done))
+    }
+
+    /// This is a convenience method for adaptive resampling, where the ratio is adjusted
+    /// to keep the fill level of a downstream buffer, such as the buffer of an audio output device,
+    /// at a target level.
+    /// Before processing the input with [process](Resampler::process), the ratio is set
+    /// by a proportional controller relative to the original ratio,
+    /// see [set_resample_ratio_relative](Resampler::set_resample_ratio_relative).
+    /// The correction in parts per million is `max_correction_ppm` multiplied by
+    /// the deviation of `current_buffer_fill` from `target_fill`, relative to `target_fill`,
+    /// and is limited to `max_correction_ppm`.
+    /// A buffer that is filled above the target lowers the ratio, and one below the target raises it.
+    /// The ratio is ramped to the new value during the processing.
+    ///
+    /// For synchronous resamplers, this returns [ResampleError::SyncNotAdjustable].
+    /// This method allocates space for the output.
+    fn process_block_with_feedback<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        current_buffer_fill: usize,
+        target_fill: usize,
+        max_correction_ppm: f64,
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        let deviation =
+            (current_buffer_fill as f64 - target_fill as f64) / target_fill.max(1) as f64;
+        let correction_ppm =
+            (max_correction_ppm * deviation).clamp(-max_correction_ppm, max_correction_ppm);
+        self.set_resample_ratio_relative(1.0 - 1.0e-6 * correction_ppm, true)?;
+        self.process(wave_in, None)
+    }
+
+    /// This is a convenience method for processing raw interleaved audio data,
+    /// for example as received from a network stream.
+    /// The bytes are decoded according to the given [SampleFormat] and [Endian],
+    /// and split into `channels` channels before being resampled by [process](Resampler::process).
+    /// Integer sample formats are scaled to the range -1.0 to +1.0.
+    ///
+    /// The `channels` argument must match the number of channels of the resampler,
+    /// and the data must contain at least as many frames as returned by
+    /// [input_frames_next](Resampler::input_frames_next).
+    /// Any incomplete frame at the end of the data is ignored.
+    /// The output is returned together with the number of input frames consumed,
+    /// and the caller should keep the bytes of the remaining frames for the next call.
+    /// Note that this method allocates space for both input and output.
+    fn process_from_bytes(
+        &mut self,
+        bytes: &[u8],
+        format: SampleFormat,
+        endian: Endian,
+        channels: usize,
+    ) -> ResampleResult<(Vec<Vec<T>>, usize)> {
+        if channels != self.nbr_channels() {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: self.nbr_channels(),
+                actual: channels,
+            });
+        }
+        let wave_in = format::decode_interleaved::<T>(bytes, format, endian, channels);
+        let mut wave_out = self.output_buffer_allocate(true);
+        let (frames_in, frames_out) = self.process_into_buffer(&wave_in, &mut wave_out, None)?;
+        for chan_out in wave_out.iter_mut() {
+            chan_out.truncate(frames_out);
+        }
+        Ok((wave_out, frames_in))
+    }
+
+    /// This is a convenience method for producing raw interleaved audio data,
+    /// for example for sending in a network stream.
+    /// The input is resampled by [process](Resampler::process), and the output is encoded
+    /// according to the given [SampleFormat] and [Endian] and appended to `buf`.
+    /// Integer sample formats are scaled from the range -1.0 to +1.0,
+    /// and values outside the range are clamped.
+    /// Returns the number of frames appended.
+    ///
+    /// Note that this method allocates space for the output, and that `buf`
+    /// may need to grow. Only available with the `bytes` feature.
+    #[cfg(feature = "bytes")]
+    fn process_to_bytesmut<V: AsRef<[T]>>(
+        &mut self,
+        wave_in: &[V],
+        format: SampleFormat,
+        endian: Endian,
+        buf: &mut bytes::BytesMut,
+    ) -> ResampleResult<usize> {
+        let wave_out = self.process(wave_in, None)?;
+        let frames = wave_out.first().map(|wave| wave.len()).unwrap_or_default();
+        format::encode_interleaved(&wave_out, frames, format, endian, buf);
+        Ok(frames)
+    }
+
     /// Convenience method for allocating an input buffer suitable for use with
     /// [process_into_buffer](Resampler::process_into_buffer). The buffer's capacity
     /// is big enough to prevent allocating additional heap memory before any call to
@@ -215,6 +688,19 @@ where
     /// [process_into_buffer](Resampler::process_into_buffer) or [process](Resampler::process).
     fn input_frames_next(&self) -> usize;
 
+    /// Get the exact number of frames per channel needed for the next call to
+    /// [process_into_buffer](Resampler::process_into_buffer) or [process](Resampler::process).
+    /// For the resamplers with a fixed output size, such as [SincFixedOut],
+    /// the number depends on the current phase and ratio, and changes after every call.
+    /// Call this immediately before every call to process, and only use the value for that call.
+    ///
+    /// The default implementation returns [input_frames_next](Resampler::input_frames_next),
+    /// which is exact for all the resamplers in this crate.
+    /// For the resamplers with a fixed input size it is always the chunk size.
+    fn input_frames_needed(&self) -> usize {
+        self.input_frames_next()
+    }
+
     /// Get the maximum number of channels this Resampler is configured for.
     fn nbr_channels(&self) -> usize;
 
@@ -247,12 +733,20 @@ where
     /// Get the delay for the resampler, reported as a number of output frames.
     fn output_delay(&self) -> usize;
 
+    /// Get the delay for the resampler in milliseconds,
+    /// calculated from [output_delay](Resampler::output_delay) and the output sample rate.
+    fn output_delay_ms(&self, output_rate: f64) -> f64 {
+        1000.0 * self.output_delay() as f64 / output_rate
+    }
+
     /// Update the resample ratio.
     ///
     /// For asynchronous resamplers, the ratio must be within
     /// `original / maximum` to `original * maximum`, where the original and maximum are the
     /// resampling ratios that were provided to the constructor. Trying to set the ratio
-    /// outside these bounds will return [ResampleError::RatioOutOfBounds].
+    /// outside these bounds will return [ResampleError::RatioOutOfBounds],
+    /// unless the policy set by [set_ratio_overflow_policy](Resampler::set_ratio_overflow_policy)
+    /// is to clamp the ratio.
     ///
     /// For synchronous resamplers, this will always return [ResampleError::SyncNotAdjustable].
     ///
@@ -266,7 +760,9 @@ where
     /// For asynchronous resamplers, the relative ratio must be within
     /// `1 / maximum` to `maximum`, where `maximum` is the maximum
     /// resampling ratio that was provided to the constructor. Trying to set the ratio
-    /// outside these bounds will return [ResampleError::RatioOutOfBounds].
+    /// outside these bounds will return [ResampleError::RatioOutOfBounds],
+    /// unless the policy set by [set_ratio_overflow_policy](Resampler::set_ratio_overflow_policy)
+    /// is to clamp the ratio.
     ///
     /// Ratios above 1.0 slow down the output and lower the pitch, while ratios
     /// below 1.0 speed up the output and raise the pitch.
@@ -274,6 +770,184 @@ where
     /// For synchronous resamplers, this will always return [ResampleError::SyncNotAdjustable].
     fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()>;
 
+    /// Choose how [set_resample_ratio](Resampler::set_resample_ratio) and
+    /// [set_resample_ratio_relative](Resampler::set_resample_ratio_relative)
+    /// handle a ratio outside the allowed range.
+    /// With [OverflowPolicy::Error], which is the default, they return [ResampleError::RatioOutOfBounds].
+    /// With [OverflowPolicy::Clamp], the ratio is silently clamped to the nearest bound.
+    ///
+    /// The synchronous resamplers can't change the ratio, and ignore the policy.
+    /// The default implementation also ignores the policy.
+    fn set_ratio_overflow_policy(&mut self, _policy: OverflowPolicy) {}
+
+    /// Set a limit for the number of frames per channel that may be processed in a single call
+    /// to [process_into_buffer](Resampler::process_into_buffer).
+    /// This is useful when the chunk size is controlled by an untrusted source,
+    /// to avoid processing and allocating for unreasonably large blocks.
+    ///
+    /// When the number of input or output frames needed for the next call exceeds the limit,
+    /// processing returns [ResampleError::BlockTooLarge] without touching the internal state.
+    /// Providing `None` removes the limit, which is the default.
+    ///
+    /// The default implementation ignores the limit,
+    /// for resamplers that don't check the block size.
+    fn set_max_block_frames(&mut self, _max_frames: Option<usize>) {}
+
+    /// Push the remaining delayed frames out from the internal buffers after the end of a stream.
+    /// Call this repeatedly after the last input has been processed.
+    /// Each call processes a block of silence like
+    /// [process_partial_into_buffer](Resampler::process_partial_into_buffer) with `None` as input,
+    /// and returns the number of frames per channel in `wave_out` that hold delayed output.
+    /// When all [output_delay](Resampler::output_delay) frames have been emitted,
+    /// it returns zero without processing.
+    /// Any call to [process_into_buffer](Resampler::process_into_buffer) restarts the count.
+    ///
+    /// The default implementation keeps the count with
+    /// [flush_remaining](Resampler::flush_remaining) and
+    /// [set_flush_remaining](Resampler::set_flush_remaining),
+    /// starting from [output_delay](Resampler::output_delay).
+    /// If the resampler doesn't keep the count, every call emits up to
+    /// [output_delay](Resampler::output_delay) frames,
+    /// and only a resampler without delay returns zero.
+    fn flush_into_buffer<Vout: AsMut<[T]>>(
+        &mut self,
+        wave_out: &mut [Vout],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize> {
+        flush_delayed_frames(self, wave_out, active_channels_mask)
+    }
+
+    /// Check if all buffered frames have been emitted by
+    /// [flush_into_buffer](Resampler::flush_into_buffer) after the end of a stream.
+    /// Returns false while the stream is running, before flushing has started,
+    /// and during flushing.
+    ///
+    /// The default implementation checks if [flush_remaining](Resampler::flush_remaining) is zero,
+    /// and returns false for resamplers that don't keep the count.
+    fn is_drained(&self) -> bool {
+        self.flush_remaining() == Some(0)
+    }
+
+    /// Get the number of delayed frames that remain to be emitted by
+    /// [flush_into_buffer](Resampler::flush_into_buffer),
+    /// or `None` if flushing hasn't started since the last call to
+    /// [process_into_buffer](Resampler::process_into_buffer).
+    ///
+    /// The default implementation always returns `None`,
+    /// for resamplers that don't keep the count.
+    fn flush_remaining(&self) -> Option<usize> {
+        None
+    }
+
+    /// Store the number of delayed frames that remain to be emitted by
+    /// [flush_into_buffer](Resampler::flush_into_buffer).
+    /// Implementations that keep the count reset it to `None` when processing.
+    ///
+    /// The default implementation does nothing.
+    fn set_flush_remaining(&mut self, _remaining: Option<usize>) {}
+
+    /// Get the number of output frames per channel for each of the next `nblocks` calls to
+    /// [process_into_buffer](Resampler::process_into_buffer), assuming that the ratio is not changed.
+    /// For the resamplers with a fixed output size, all values are equal to the chunk size.
+    /// For the resamplers with a fixed input size, the output size varies from block to block,
+    /// and with a rational ratio the sequence is periodic.
+    /// This is useful for allocating the exact output size when rendering a whole stream.
+    ///
+    /// The default implementation repeats [output_frames_next](Resampler::output_frames_next),
+    /// which is exact for resamplers with a fixed output size.
+    fn output_schedule(&self, nblocks: usize) -> Vec<usize> {
+        vec![self.output_frames_next(); nblocks]
+    }
+
+    /// Get a description of the filter used by the resampler,
+    /// with the approximate attenuation, transition bandwidth and latency.
+    /// This is useful for logging the settings of a resampler without knowing its type.
+    ///
+    /// The default implementation reports a [FilterType::Unknown] filter,
+    /// with only the latency given by [output_delay](Resampler::output_delay).
+    fn quality_info(&self) -> QualityInfo {
+        QualityInfo {
+            filter: FilterType::Unknown,
+            attenuation_db: None,
+            transition_bandwidth: None,
+            latency: self.output_delay(),
+        }
+    }
+
+    /// Get an estimate of the number of multiply-accumulate operations needed
+    /// for the largest block that the resampler may process, summed over all channels.
+    /// For the asynchronous resamplers this is calculated from the filter length
+    /// and the maximum number of output frames, see [output_frames_max](Resampler::output_frames_max).
+    /// For the synchronous resamplers it is calculated from the FFT sizes,
+    /// with each transform counted as `N*log2(N)` operations for a length `N`.
+    /// This is meant as an a-priori measure of the cost of a call, for example for real-time scheduling.
+    ///
+    /// The default implementation returns zero, for resamplers without a filter
+    /// or with an unknown cost.
+    fn estimated_mac_per_block(&self) -> u64 {
+        0
+    }
+
+    /// Set the ceiling used for detecting clipping in the output.
+    /// Output samples with an absolute value larger than the ceiling
+    /// are counted by [clipped_sample_count](Resampler::clipped_sample_count).
+    /// No ceiling is set by default, and then the output is not scanned.
+    /// Providing `None` removes the ceiling.
+    ///
+    /// The default implementation ignores the ceiling,
+    /// for resamplers that don't count clipped samples.
+    fn set_clip_ceiling(&mut self, _ceiling: Option<T>) {}
+
+    /// Get the number of output samples, summed over all active channels,
+    /// that exceeded the clip ceiling since the resampler was created or last reset.
+    /// Resampling may produce overshoots that exceed the peak value of the input,
+    /// and this can be used to check if the output needs to be attenuated.
+    ///
+    /// The default implementation always returns zero.
+    fn clipped_sample_count(&self) -> usize {
+        0
+    }
+
+    /// Check if the current resampling ratio is a rational number.
+    /// If it is, the ratio is returned as a reduced fraction `(numerator, denominator)`,
+    /// where the ratio equals `numerator / denominator`.
+    ///
+    /// The synchronous resamplers always have a rational ratio, given by the sample rates.
+    /// For the asynchronous resamplers, the ratio is considered rational if a fraction
+    /// with a denominator no larger than 1048576 matches it within the precision of an [f64].
+    /// For example, resampling from 44100 to 48000 Hz gives `Some((160, 147))`.
+    ///
+    /// The default implementation looks for a fraction matching [target_ratio](Resampler::target_ratio)
+    /// in the same way as the asynchronous resamplers.
+    fn is_rational(&self) -> Option<(usize, usize)> {
+        rational_approximation(self.target_ratio())
+    }
+
+    /// Get the resampling ratio, output sample rate divided by input sample rate,
+    /// that the resampler is set to.
+    /// While the ratio is ramping after a call to [set_resample_ratio](Resampler::set_resample_ratio),
+    /// this is the ratio at the end of the ramp.
+    ///
+    /// The default implementation estimates the ratio from the number of input and output
+    /// frames of the next call, see [input_frames_next](Resampler::input_frames_next)
+    /// and [output_frames_next](Resampler::output_frames_next).
+    fn target_ratio(&self) -> f64 {
+        self.output_frames_next() as f64 / self.input_frames_next() as f64
+    }
+
+    /// Get the output sample rate that is achieved for the given input sample rate.
+    /// When the ratio is rational, see [is_rational](Resampler::is_rational),
+    /// the rate is calculated from the exact fraction.
+    /// This may differ slightly from multiplying the input rate by the nominal ratio,
+    /// since a ratio calculated from two sample rates is generally not exactly representable as an [f64].
+    /// Otherwise the rate is calculated from [target_ratio](Resampler::target_ratio).
+    fn achieved_output_rate(&self, input_rate: f64) -> f64 {
+        match self.is_rational() {
+            Some((numerator, denominator)) => input_rate * numerator as f64 / denominator as f64,
+            None => input_rate * self.target_ratio(),
+        }
+    }
+
     /// Reset the resampler state and clear all internal buffers.
     fn reset(&mut self);
 
@@ -355,6 +1029,9 @@ macro_rules! implement_resampler {
             /// Refer to [Resampler::input_frames_next].
             fn input_frames_next(&self) -> usize;
 
+            /// Refer to [Resampler::input_frames_needed].
+            fn input_frames_needed(&self) -> usize;
+
             /// Refer to [Resampler::nbr_channels].
             fn nbr_channels(&self) -> usize;
 
@@ -375,6 +1052,15 @@ macro_rules! implement_resampler {
 
             /// Refer to [Resampler::set_resample_ratio_relative].
             fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> rubato::ResampleResult<()>;
+
+            /// Refer to [Resampler::set_ratio_overflow_policy].
+            fn set_ratio_overflow_policy(&mut self, policy: rubato::OverflowPolicy);
+
+            /// Refer to [Resampler::quality_info].
+            fn quality_info(&self) -> rubato::QualityInfo;
+
+            /// Refer to [Resampler::estimated_mac_per_block].
+            fn estimated_mac_per_block(&self) -> u64;
         }
 
         impl<T, U> $trait_name<T> for U
@@ -437,6 +1123,10 @@ macro_rules! implement_resampler {
                 rubato::Resampler::input_frames_next(self)
             }
 
+            fn input_frames_needed(&self) -> usize {
+                rubato::Resampler::input_frames_needed(self)
+            }
+
             fn output_delay(&self) -> usize {
                 rubato::Resampler::output_delay(self)
             }
@@ -460,6 +1150,18 @@ macro_rules! implement_resampler {
             fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> rubato::ResampleResult<()> {
                 rubato::Resampler::set_resample_ratio_relative(self, rel_ratio, ramp)
             }
+
+            fn set_ratio_overflow_policy(&mut self, policy: rubato::OverflowPolicy) {
+                rubato::Resampler::set_ratio_overflow_policy(self, policy)
+            }
+
+            fn quality_info(&self) -> rubato::QualityInfo {
+                rubato::Resampler::quality_info(self)
+            }
+
+            fn estimated_mac_per_block(&self) -> u64 {
+                rubato::Resampler::estimated_mac_per_block(self)
+            }
         }
     }
 }
@@ -471,6 +1173,152 @@ fn update_mask_from_buffers(mask: &mut [bool]) {
     mask.iter_mut().for_each(|v| *v = true);
 }
 
+/// The largest denominator considered by [rational_approximation].
+const MAX_RATIONAL_DENOMINATOR: u64 = 1 << 20;
+
+/// Helper to find a fraction equal to the given value within the precision of an f64,
+/// by evaluating the convergents of its continued fraction expansion.
+pub(crate) fn rational_approximation(value: f64) -> Option<(usize, usize)> {
+    if !value.is_finite() || value <= 0.0 {
+        return None;
+    }
+    let tolerance = 4.0 * f64::EPSILON * value;
+    let (mut p_prev, mut q_prev, mut p, mut q) = (0u64, 1u64, 1u64, 0u64);
+    let mut remainder = value;
+    while q <= MAX_RATIONAL_DENOMINATOR {
+        let term = remainder.floor();
+        if term > u32::MAX as f64 {
+            return None;
+        }
+        let p_next = (term as u64).checked_mul(p)?.checked_add(p_prev)?;
+        let q_next = (term as u64).checked_mul(q)?.checked_add(q_prev)?;
+        (p_prev, q_prev, p, q) = (p, q, p_next, q_next);
+        if q <= MAX_RATIONAL_DENOMINATOR && (p as f64 / q as f64 - value).abs() <= tolerance {
+            return Some((p as usize, q as usize));
+        }
+        let fraction = remainder - term;
+        if fraction <= 0.0 {
+            return None;
+        }
+        remainder = 1.0 / fraction;
+    }
+    None
+}
+
+/// Helper function. Check a new ratio against the range from `original / max_relative_ratio`
+/// to `original * max_relative_ratio`. Returns the ratio to use,
+/// which is clamped to the range with [OverflowPolicy::Clamp].
+pub(crate) fn bounded_ratio(
+    new_ratio: f64,
+    original: f64,
+    max_relative_ratio: f64,
+    policy: OverflowPolicy,
+) -> ResampleResult<f64> {
+    if (new_ratio / original >= 1.0 / max_relative_ratio)
+        && (new_ratio / original <= max_relative_ratio)
+    {
+        return Ok(new_ratio);
+    }
+    match policy {
+        OverflowPolicy::Error => Err(ResampleError::RatioOutOfBounds {
+            provided: new_ratio,
+            original,
+            max_relative_ratio,
+        }),
+        OverflowPolicy::Clamp => Ok(new_ratio
+            .max(original / max_relative_ratio)
+            .min(original * max_relative_ratio)),
+    }
+}
+
+/// Helper to repeat the stepping performed by the asynchronous resamplers with a fixed input size,
+/// and count the output frames for each of the next `nblocks` chunks.
+pub(crate) fn stepping_schedule(
+    mut last_index: f64,
+    mut resample_ratio: f64,
+    target_ratio: f64,
+    chunk_size: usize,
+    filter_len: usize,
+    nblocks: usize,
+) -> Vec<usize> {
+    let t_ratio_end = 1.0 / target_ratio;
+    let end_idx = chunk_size as isize - (filter_len as isize + 1) - t_ratio_end.ceil() as isize;
+    (0..nblocks)
+        .map(|_| {
+            let mut t_ratio = 1.0 / resample_ratio;
+            let approximate_nbr_frames =
+                chunk_size as f64 * (0.5 * resample_ratio + 0.5 * target_ratio);
+            let t_ratio_increment = (t_ratio_end - t_ratio) / approximate_nbr_frames;
+            let mut idx = last_index;
+            let mut n = 0;
+            while idx < end_idx as f64 {
+                t_ratio += t_ratio_increment;
+                idx += t_ratio;
+                n += 1;
+            }
+            last_index = idx - chunk_size as f64;
+            resample_ratio = target_ratio;
+            n
+        })
+        .collect()
+}
+
+/// Helper to process blocks of silence until all [output_delay](Resampler::output_delay) frames
+/// after the end of a stream have been emitted.
+/// The count of remaining frames is kept with [Resampler::set_flush_remaining],
+/// and starts from the output delay when [Resampler::flush_remaining] returns `None`.
+pub(crate) fn flush_delayed_frames<T, R, Vout>(
+    resampler: &mut R,
+    wave_out: &mut [Vout],
+    active_channels_mask: Option<&[bool]>,
+) -> ResampleResult<usize>
+where
+    T: Sample,
+    R: Resampler<T> + ?Sized,
+    Vout: AsMut<[T]>,
+{
+    let remaining = resampler
+        .flush_remaining()
+        .unwrap_or_else(|| resampler.output_delay());
+    let frames = if remaining > 0 {
+        let (_, frames_out) = resampler.process_partial_into_buffer(
+            None::<&[&[T]]>,
+            wave_out,
+            active_channels_mask,
+        )?;
+        frames_out.min(remaining)
+    } else {
+        0
+    };
+    resampler.set_flush_remaining(Some(remaining - frames));
+    Ok(frames)
+}
+
+/// Helper to count the output samples with an absolute value above the given ceiling.
+/// Nothing is counted when no ceiling is set.
+pub(crate) fn count_clipped<T: Sample, Vout: AsMut<[T]>>(
+    wave_out: &mut [Vout],
+    mask: &[bool],
+    frames: usize,
+    ceiling: Option<T>,
+) -> usize {
+    let ceiling = match ceiling {
+        Some(ceiling) => ceiling.to_f64(),
+        None => return 0,
+    };
+    wave_out
+        .iter_mut()
+        .zip(mask.iter())
+        .filter(|(_, active)| **active)
+        .map(|(wave, _)| {
+            wave.as_mut()[..frames]
+                .iter()
+                .filter(|sample| sample.abs().to_f64() > ceiling)
+                .count()
+        })
+        .sum()
+}
+
 pub(crate) fn validate_buffers<T, Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
     wave_in: &[Vin],
     wave_out: &mut [Vout],
@@ -478,7 +1326,14 @@ pub(crate) fn validate_buffers<T, Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
     channels: usize,
     min_input_len: usize,
     min_output_len: usize,
+    max_block_frames: Option<usize>,
 ) -> ResampleResult<()> {
+    if let Some(max) = max_block_frames {
+        let requested = min_input_len.max(min_output_len);
+        if requested > max {
+            return Err(ResampleError::BlockTooLarge { max, requested });
+        }
+    }
     if wave_in.len() != channels {
         return Err(ResampleError::WrongNumberOfInputChannels {
             expected: channels,
@@ -520,10 +1375,40 @@ pub(crate) fn validate_buffers<T, Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
                 actual: actual_len,
             });
         }
+        #[cfg(debug_assertions)]
+        for (chan_in, wave_in) in wave_in.iter().enumerate() {
+            assert_distinct_buffers(
+                wave_in.as_ref().as_ptr_range(),
+                wave_out.as_mut().as_ptr_range(),
+                chan_in,
+                chan,
+            );
+        }
     }
     Ok(())
 }
 
+/// Helper to check that an input and an output buffer do not overlap in memory.
+/// Overlapping buffers can only be created with unsafe code, and lead to corrupted output.
+/// This check is only performed in debug builds.
+#[cfg(debug_assertions)]
+fn assert_distinct_buffers<T>(
+    input: std::ops::Range<*const T>,
+    output: std::ops::Range<*const T>,
+    chan_in: usize,
+    chan_out: usize,
+) {
+    assert!(
+        input.start == input.end
+            || output.start == output.end
+            || input.end <= output.start
+            || output.end <= input.start,
+        "Input channel {} and output channel {} overlap in memory, the input and output buffers must be distinct",
+        chan_in,
+        chan_out
+    );
+}
+
 /// Convenience method for allocating a buffer to hold a given number of channels and frames.
 /// The `filled` argument determines if the vectors should be pre-filled with zeros or not.
 /// When false, the vectors are only allocated but returned empty.
@@ -566,9 +1451,10 @@ pub fn buffer_capacity<T: Sample>(buffer: &[Vec<T>]) -> usize {
 #[cfg(test)]
 pub mod tests {
     use crate::{buffer_capacity, buffer_length, make_buffer, resize_buffer, VecResampler};
-    use crate::{FastFixedIn, PolynomialDegree, SincFixedIn, SincFixedOut};
+    use crate::{FastFixedIn, FastFixedOut, PolynomialDegree, SincFixedIn, SincFixedOut};
     #[cfg(feature = "fft_resampler")]
     use crate::{FftFixedIn, FftFixedInOut, FftFixedOut};
+    use crate::{FilterType, SincInterpolationParameters, SincInterpolationType, WindowFunction};
     use test_log::test;
 
     // This tests that a VecResampler can be boxed.
@@ -597,6 +1483,192 @@ pub mod tests {
         resampler.process(&waves, None).unwrap()
     }
 
+    // This tests that all resamplers describe their filter.
+    #[test]
+    fn quality_info() {
+        let params = || SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resamplers: Vec<Box<dyn VecResampler<f64>>> = vec![
+            Box::new(SincFixedIn::<f64>::new(1.5, 1.0, params(), 1024, 2).unwrap()),
+            Box::new(SincFixedOut::<f64>::new(1.5, 1.0, params(), 1024, 2).unwrap()),
+            Box::new(FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap()),
+            Box::new(
+                FastFixedOut::<f64>::new(1.5, 1.0, PolynomialDegree::Septic, 1024, 2).unwrap(),
+            ),
+        ];
+        #[cfg(feature = "fft_resampler")]
+        {
+            resamplers.push(Box::new(
+                FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap(),
+            ));
+            resamplers.push(Box::new(
+                FftFixedOut::<f64>::new(44100, 48000, 1024, 2, 2).unwrap(),
+            ));
+            resamplers.push(Box::new(
+                FftFixedInOut::<f64>::new(44100, 48000, 1024, 2).unwrap(),
+            ));
+        }
+        for resampler in resamplers.iter_mut() {
+            let info = resampler.quality_info();
+            let attenuation = info.attenuation_db.unwrap();
+            let transition = info.transition_bandwidth.unwrap();
+            assert!(attenuation > 0.0);
+            assert!(transition > 0.0 && transition <= 1.0);
+            assert_eq!(info.latency, resampler.output_delay());
+            match info.filter {
+                FilterType::Sinc { sinc_len, .. } => {
+                    assert_eq!(sinc_len, 64);
+                    assert!(transition < 0.5);
+                }
+                FilterType::Polynomial(degree) => {
+                    assert!(
+                        degree == PolynomialDegree::Cubic || degree == PolynomialDegree::Septic
+                    );
+                    assert_eq!(transition, 1.0);
+                }
+                FilterType::Fft { fft_size_in, .. } => assert!(fft_size_in > 0),
+                FilterType::Passthrough | FilterType::Unknown => {
+                    panic!("Only resamplers with known filters were created")
+                }
+            }
+        }
+    }
+
+    // Check the default implementations with a resampler that only has the required methods.
+    mod required_methods {
+        use crate::{FilterType, OverflowPolicy, ResampleError, ResampleResult, Resampler};
+        use test_log::test;
+
+        /// A resampler that only implements the required methods of [Resampler].
+        struct CopyResampler {
+            chunk_size: usize,
+            /// The number of calls to `process_into_buffer` that succeed.
+            calls_left: usize,
+            /// The delay reported by `output_delay`.
+            delay: usize,
+        }
+
+        impl Resampler<f64> for CopyResampler {
+            fn process_into_buffer<Vin: AsRef<[f64]>, Vout: AsMut<[f64]>>(
+                &mut self,
+                wave_in: &[Vin],
+                wave_out: &mut [Vout],
+                _active_channels_mask: Option<&[bool]>,
+            ) -> ResampleResult<(usize, usize)> {
+                if self.calls_left == 0 {
+                    return Err(ResampleError::SyncNotAdjustable);
+                }
+                self.calls_left -= 1;
+                for (wave_in, wave_out) in wave_in.iter().zip(wave_out.iter_mut()) {
+                    wave_out.as_mut()[..self.chunk_size]
+                        .copy_from_slice(&wave_in.as_ref()[..self.chunk_size]);
+                }
+                Ok((self.chunk_size, self.chunk_size))
+            }
+
+            fn input_frames_max(&self) -> usize {
+                self.chunk_size
+            }
+
+            fn input_frames_next(&self) -> usize {
+                self.chunk_size
+            }
+
+            fn nbr_channels(&self) -> usize {
+                1
+            }
+
+            fn output_frames_max(&self) -> usize {
+                self.chunk_size
+            }
+
+            fn output_frames_next(&self) -> usize {
+                self.chunk_size
+            }
+
+            fn output_delay(&self) -> usize {
+                self.delay
+            }
+
+            fn set_resample_ratio(&mut self, _new_ratio: f64, _ramp: bool) -> ResampleResult<()> {
+                Ok(())
+            }
+
+            fn set_resample_ratio_relative(
+                &mut self,
+                _rel_ratio: f64,
+                _ramp: bool,
+            ) -> ResampleResult<()> {
+                Ok(())
+            }
+
+            fn reset(&mut self) {}
+        }
+
+        #[test]
+        fn default_methods() {
+            let mut resampler = CopyResampler {
+                chunk_size: 16,
+                calls_left: usize::MAX,
+                delay: 0,
+            };
+            resampler.set_ratio_overflow_policy(OverflowPolicy::Clamp);
+            resampler.set_max_block_frames(Some(8));
+            resampler.set_clip_ceiling(Some(0.5));
+            let output = resampler.process(&[vec![1.0; 16]], None).unwrap();
+            assert_eq!(output, vec![vec![1.0; 16]]);
+            assert_eq!(resampler.clipped_sample_count(), 0);
+            assert_eq!(resampler.is_rational(), Some((1, 1)));
+            assert_eq!(resampler.achieved_output_rate(44100.0), 44100.0);
+            assert_eq!(resampler.output_schedule(3), vec![16; 3]);
+            assert_eq!(resampler.estimated_mac_per_block(), 0);
+            let info = resampler.quality_info();
+            assert_eq!(info.filter, FilterType::Unknown);
+            assert_eq!(info.latency, 0);
+            let mut wave_out = resampler.output_buffer_allocate(true);
+            assert_eq!(resampler.flush_remaining(), None);
+            assert!(!resampler.is_drained());
+            assert_eq!(resampler.flush_into_buffer(&mut wave_out, None).unwrap(), 0);
+            assert!(!resampler.is_drained());
+        }
+
+        #[test]
+        fn default_flush_uses_delay() {
+            let mut resampler = CopyResampler {
+                chunk_size: 16,
+                calls_left: usize::MAX,
+                delay: 5,
+            };
+            let mut wave_out = resampler.output_buffer_allocate(true);
+            assert!(!resampler.is_drained());
+            // Without a stored count, each flush emits the delayed frames.
+            assert_eq!(resampler.flush_into_buffer(&mut wave_out, None).unwrap(), 5);
+            assert_eq!(resampler.flush_into_buffer(&mut wave_out, None).unwrap(), 5);
+            assert!(!resampler.is_drained());
+        }
+
+        #[test]
+        fn process_max_frames_partial() {
+            let mut resampler = CopyResampler {
+                chunk_size: 16,
+                calls_left: 2,
+                delay: 0,
+            };
+            let input = (0..64).map(|n| n as f64).collect::<Vec<_>>();
+            let (output, frames_in) = resampler.process_max_frames(&[&input], usize::MAX).unwrap();
+            assert_eq!(frames_in, 32);
+            assert_eq!(output, vec![input[..32].to_vec()]);
+            assert!(resampler
+                .process_max_frames(&[&input[32..]], usize::MAX)
+                .is_err());
+        }
+    }
+
     fn impl_send<T: Send>() {
         fn is_send<T: Send>() {}
         is_send::<SincFixedOut<T>>();
@@ -726,4 +1798,27 @@ pub mod tests {
         assert_eq!(buffer_length(&buf4), 10);
         assert_eq!(buffer_capacity(&buf4), 10);
     }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "overlap in memory")]
+    fn overlapping_buffers_panic() {
+        let buffer = vec![0.0f32; 100];
+        let range = buffer.as_ptr_range();
+        let overlapping = buffer[50..].as_ptr_range();
+        crate::assert_distinct_buffers(range, overlapping, 0, 1);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn distinct_buffers_pass() {
+        let buffer = vec![0.0f32; 100];
+        crate::assert_distinct_buffers(
+            buffer[..50].as_ptr_range(),
+            buffer[50..].as_ptr_range(),
+            0,
+            0,
+        );
+        crate::assert_distinct_buffers(buffer[..0].as_ptr_range(), buffer.as_ptr_range(), 0, 0);
+    }
 }
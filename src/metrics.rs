@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+#[cfg(feature = "prometheus")]
+use std::fmt::Write;
+
+/// Counters describing the work done by a resampler,
+/// as returned by [SincFixedIn::metrics](crate::SincFixedIn::metrics).
+///
+/// All counters start at zero when the resampler is created, and are cleared by
+/// [reset](crate::Resampler::reset).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResamplerMetrics {
+    /// Number of input frames processed for each channel.
+    /// Inactive channels are not counted.
+    pub frames_processed: Vec<u64>,
+    /// Total time spent in [process_into_buffer](crate::Resampler::process_into_buffer).
+    /// The time is only measured with the `profiling` or `prometheus` feature,
+    /// since reading the clock adds overhead to every call, and is not supported on all targets.
+    /// Without them, this stays at zero.
+    pub process_duration: Duration,
+    /// Number of successful calls to [process_into_buffer](crate::Resampler::process_into_buffer).
+    pub process_calls: u64,
+    /// Number of calls that were rejected because an input or output buffer was too short.
+    pub xruns: u64,
+}
+
+impl ResamplerMetrics {
+    /// Create a new set of metrics for the given number of channels, with all counters at zero.
+    pub(crate) fn new(channels: usize) -> Self {
+        ResamplerMetrics {
+            frames_processed: vec![0; channels],
+            process_duration: Duration::ZERO,
+            process_calls: 0,
+            xruns: 0,
+        }
+    }
+
+    /// Record a successful call that processed `frames` input frames for the active channels.
+    pub(crate) fn record(&mut self, frames: usize, channel_mask: &[bool]) {
+        for (count, active) in self.frames_processed.iter_mut().zip(channel_mask.iter()) {
+            if *active {
+                *count += frames as u64;
+            }
+        }
+        self.process_calls += 1;
+    }
+
+    /// Add the time spent in a call to the total processing time.
+    #[cfg(any(feature = "profiling", feature = "prometheus"))]
+    pub(crate) fn record_duration(&mut self, duration: Duration) {
+        self.process_duration += duration;
+    }
+
+    /// Record a call that was rejected because a buffer was too short.
+    pub(crate) fn record_xrun(&mut self) {
+        self.xruns += 1;
+    }
+
+    /// Set all counters to zero.
+    pub(crate) fn clear(&mut self) {
+        self.frames_processed
+            .iter_mut()
+            .for_each(|count| *count = 0);
+        self.process_duration = Duration::ZERO;
+        self.process_calls = 0;
+        self.xruns = 0;
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    /// The processing time is exported as a summary without quantiles,
+    /// `rubato_process_duration_seconds`, with `_sum` and `_count` samples.
+    /// Only available with the `prometheus` feature.
+    #[cfg(feature = "prometheus")]
+    pub fn export_prometheus(&self) -> String {
+        let mut text = String::new();
+        // Writing to a String can't fail, so the results are ignored.
+        let _ = writeln!(
+            text,
+            "# HELP rubato_frames_processed_total Number of input frames processed."
+        );
+        let _ = writeln!(text, "# TYPE rubato_frames_processed_total counter");
+        for (channel, count) in self.frames_processed.iter().enumerate() {
+            let _ = writeln!(
+                text,
+                "rubato_frames_processed_total{{channel=\"{}\"}} {}",
+                channel, count
+            );
+        }
+        let _ = writeln!(
+            text,
+            "# HELP rubato_process_duration_seconds Time spent processing."
+        );
+        let _ = writeln!(text, "# TYPE rubato_process_duration_seconds summary");
+        let _ = writeln!(
+            text,
+            "rubato_process_duration_seconds_sum {}",
+            self.process_duration.as_secs_f64()
+        );
+        let _ = writeln!(
+            text,
+            "rubato_process_duration_seconds_count {}",
+            self.process_calls
+        );
+        let _ = writeln!(
+            text,
+            "# HELP rubato_xrun_total Number of calls rejected because of a too short buffer."
+        );
+        let _ = writeln!(text, "# TYPE rubato_xrun_total counter");
+        let _ = writeln!(text, "rubato_xrun_total {}", self.xruns);
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResamplerMetrics;
+    use std::time::Duration;
+    use test_log::test;
+
+    #[test]
+    fn record_and_clear() {
+        let mut metrics = ResamplerMetrics::new(2);
+        metrics.record(100, &[true, true]);
+        metrics.record(100, &[true, false]);
+        metrics.record_xrun();
+        assert_eq!(metrics.frames_processed, vec![200, 100]);
+        assert_eq!(metrics.process_duration, Duration::ZERO);
+        assert_eq!(metrics.process_calls, 2);
+        assert_eq!(metrics.xruns, 1);
+        metrics.clear();
+        assert_eq!(metrics, ResamplerMetrics::new(2));
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn prometheus_text() {
+        let mut metrics = ResamplerMetrics::new(2);
+        metrics.record(256, &[true, false]);
+        metrics.record_duration(Duration::from_millis(500));
+        metrics.record_xrun();
+        let text = metrics.export_prometheus();
+        let samples = text
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            samples,
+            vec![
+                "rubato_frames_processed_total{channel=\"0\"} 256",
+                "rubato_frames_processed_total{channel=\"1\"} 0",
+                "rubato_process_duration_seconds_sum 0.5",
+                "rubato_process_duration_seconds_count 1",
+                "rubato_xrun_total 1",
+            ]
+        );
+        assert!(text.contains("# TYPE rubato_xrun_total counter\n"));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::error::{ResampleError, ResampleResult};
+use crate::{Resampler, Sample, SincFixedOut};
+
+/// A wrapper around [SincFixedOut] for driving an output with a fixed callback size,
+/// such as an audio device, from an input that arrives in chunks of varying size.
+///
+/// Input is pushed in blocks of any size with [push_input](OutputLockedResampler::push_input),
+/// and is stored in an internal queue. Each call to
+/// [pull_output](OutputLockedResampler::pull_output) then takes the number of frames
+/// the resampler needs from the queue, and always produces exactly
+/// [output_frames](OutputLockedResampler::output_frames) frames.
+/// When the queue does not hold enough frames, [ResampleError::InputUnderrun] is returned
+/// and nothing is consumed, so the call can be retried once more input has arrived.
+///
+/// The queue is preallocated to hold a few chunks of input.
+/// Pushing more data than that may cause it to grow.
+pub struct OutputLockedResampler<T> {
+    resampler: SincFixedOut<T>,
+    queue: Vec<Vec<T>>,
+    capacity: usize,
+}
+
+impl<T> OutputLockedResampler<T>
+where
+    T: Sample,
+{
+    /// Create a new OutputLockedResampler.
+    /// The number of output frames per call is given by the chunk size of the `resampler`.
+    pub fn new(resampler: SincFixedOut<T>) -> Self {
+        let capacity = 4 * resampler.input_frames_max();
+        let queue = vec![Vec::with_capacity(capacity); resampler.nbr_channels()];
+        OutputLockedResampler {
+            resampler,
+            queue,
+            capacity,
+        }
+    }
+
+    /// Get the number of frames produced by each call to
+    /// [pull_output](OutputLockedResampler::pull_output).
+    pub fn output_frames(&self) -> usize {
+        self.resampler.output_frames_next()
+    }
+
+    /// Get the number of input frames currently waiting in the queue.
+    pub fn buffered_frames(&self) -> usize {
+        self.queue
+            .first()
+            .map(|chan| chan.len())
+            .unwrap_or_default()
+    }
+
+    /// Get the number of input frames waiting in the queue, and the number of frames
+    /// the queue was preallocated for, as a tuple `(buffered, capacity)`.
+    /// This can be used for applying backpressure to the source of the input.
+    /// Pushing more frames than the capacity is allowed, but makes the queue grow,
+    /// and then the number of buffered frames exceeds the capacity.
+    pub fn buffer_occupancy(&self) -> (usize, usize) {
+        (self.buffered_frames(), self.capacity)
+    }
+
+    /// Add a block of input frames to the queue.
+    /// All channels must contain the same number of frames.
+    pub fn push_input<V: AsRef<[T]>>(&mut self, wave_in: &[V]) -> ResampleResult<()> {
+        if wave_in.len() != self.queue.len() {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: self.queue.len(),
+                actual: wave_in.len(),
+            });
+        }
+        let frames = wave_in[0].as_ref().len();
+        for (channel, wave) in wave_in.iter().enumerate() {
+            if wave.as_ref().len() != frames {
+                return Err(ResampleError::InsufficientInputBufferSize {
+                    channel,
+                    expected: frames,
+                    actual: wave.as_ref().len(),
+                });
+            }
+        }
+        for (chan, wave) in self.queue.iter_mut().zip(wave_in.iter()) {
+            chan.extend_from_slice(wave.as_ref());
+        }
+        Ok(())
+    }
+
+    /// Produce exactly [output_frames](OutputLockedResampler::output_frames) frames
+    /// of output, using input from the queue.
+    /// Returns [ResampleError::InputUnderrun] if the queue holds too few frames.
+    pub fn pull_output<V: AsMut<[T]>>(&mut self, wave_out: &mut [V]) -> ResampleResult<usize> {
+        let needed = self.resampler.input_frames_next();
+        let available = self.buffered_frames();
+        if available < needed {
+            return Err(ResampleError::InputUnderrun { needed, available });
+        }
+        let (frames_in, frames_out) =
+            self.resampler
+                .process_into_buffer(&self.queue, wave_out, None)?;
+        for chan in self.queue.iter_mut() {
+            chan.drain(..frames_in);
+        }
+        Ok(frames_out)
+    }
+
+    /// Clear the queue and reset the wrapped resampler.
+    pub fn reset(&mut self) {
+        self.queue.iter_mut().for_each(|chan| chan.clear());
+        self.resampler.reset();
+    }
+
+    /// Get a reference to the wrapped resampler.
+    pub fn resampler(&self) -> &SincFixedOut<T> {
+        &self.resampler
+    }
+
+    /// Get a mutable reference to the wrapped resampler, for example for adjusting the ratio.
+    pub fn resampler_mut(&mut self) -> &mut SincFixedOut<T> {
+        &mut self.resampler
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputLockedResampler;
+    use crate::{
+        ResampleError, Resampler, SincFixedOut, SincInterpolationParameters, SincInterpolationType,
+        WindowFunction,
+    };
+    use rand::Rng;
+    use test_log::test;
+
+    fn make_resampler() -> OutputLockedResampler<f64> {
+        let params = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let resampler = SincFixedOut::<f64>::new(48000.0 / 44100.0, 1.1, params, 256, 2).unwrap();
+        OutputLockedResampler::new(resampler)
+    }
+
+    #[test]
+    fn fixed_output_with_jittery_input() {
+        let mut resampler = make_resampler();
+        let mut rng = rand::thread_rng();
+        let mut wave_out = vec![vec![0.0; 256]; 2];
+        let mut pulled = 0;
+        for _ in 0..200 {
+            let frames = rng.gen_range(100..400);
+            resampler.push_input(&vec![vec![0.1; frames]; 2]).unwrap();
+            while resampler.buffered_frames() >= resampler.resampler().input_frames_next() {
+                assert_eq!(resampler.pull_output(&mut wave_out).unwrap(), 256);
+                pulled += 1;
+            }
+        }
+        assert!(pulled > 150);
+    }
+
+    #[test]
+    fn underrun() {
+        let mut resampler = make_resampler();
+        let mut wave_out = vec![vec![0.0; 256]; 2];
+        resampler.push_input(&vec![vec![0.1; 100]; 2]).unwrap();
+        assert!(matches!(
+            resampler.pull_output(&mut wave_out),
+            Err(ResampleError::InputUnderrun { available: 100, .. })
+        ));
+        assert_eq!(resampler.buffered_frames(), 100);
+        resampler.push_input(&vec![vec![0.1; 300]; 2]).unwrap();
+        assert_eq!(resampler.pull_output(&mut wave_out).unwrap(), 256);
+    }
+
+    #[test]
+    fn buffer_occupancy() {
+        let mut resampler = make_resampler();
+        let capacity = 4 * resampler.resampler().input_frames_max();
+        assert_eq!(resampler.buffer_occupancy(), (0, capacity));
+        resampler.push_input(&vec![vec![0.1; 300]; 2]).unwrap();
+        assert_eq!(resampler.buffer_occupancy(), (300, capacity));
+        let needed = resampler.resampler().input_frames_next();
+        let mut wave_out = vec![vec![0.0; 256]; 2];
+        resampler.pull_output(&mut wave_out).unwrap();
+        assert_eq!(resampler.buffer_occupancy(), (300 - needed, capacity));
+        resampler.reset();
+        assert_eq!(resampler.buffer_occupancy(), (0, capacity));
+    }
+}
@@ -0,0 +1,120 @@
+/// A fractional read position for implementing custom interpolation.
+///
+/// This is a standalone helper and is not used by the resamplers themselves.
+/// At a constant ratio, it steps through the input in the same way as the asynchronous
+/// resamplers, so that starting from the same position it visits the same input positions.
+/// It does not follow the gradual ratio changes made when ramping is enabled in
+/// [Resampler::set_resample_ratio](crate::Resampler::set_resample_ratio).
+///
+/// The phase is the position of the next output frame, measured in input frames
+/// from the current input frame. It is kept in the range 0.0 to 1.0.
+/// For every output frame, the phase advances by the reciprocal of the resampling ratio,
+/// and the read pointer into the input moves by the integer part of the new phase.
+///
+/// The interval between two input frames is divided into `n_phases` sub-phases,
+/// which corresponds to the oversampling factor of a sinc table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseStepper {
+    ratio: f64,
+    phase: f64,
+    n_phases: usize,
+}
+
+impl PhaseStepper {
+    /// Create a new PhaseStepper starting at phase zero.
+    ///
+    /// Parameters are:
+    /// - `ratio`: Ratio between output and input sample rates, must be > 0.
+    /// - `n_phases`: Number of sub-phases between two input frames, must be > 0.
+    pub fn new(ratio: f64, n_phases: usize) -> Self {
+        assert!(ratio > 0.0, "The ratio must be larger than zero");
+        assert!(
+            n_phases > 0,
+            "The number of phases must be larger than zero"
+        );
+        PhaseStepper {
+            ratio,
+            phase: 0.0,
+            n_phases,
+        }
+    }
+
+    /// Advance by one output frame.
+    /// Returns a tuple `(integer_advance, fractional_phase)`, where `integer_advance`
+    /// is the number of input frames the read pointer moves,
+    /// and `fractional_phase` is the new phase.
+    pub fn advance(&mut self) -> (usize, f64) {
+        let position = self.phase + 1.0 / self.ratio;
+        let integer_advance = position.floor();
+        self.phase = position - integer_advance;
+        (integer_advance as usize, self.phase)
+    }
+
+    /// Set the current phase. Any integer part is discarded,
+    /// so that for example 2.25 gives a phase of 0.25.
+    pub fn reset_to(&mut self, phase: f64) {
+        self.phase = phase - phase.floor();
+    }
+
+    /// Change the resampling ratio. The phase is kept.
+    pub fn set_ratio(&mut self, ratio: f64) {
+        assert!(ratio > 0.0, "The ratio must be larger than zero");
+        self.ratio = ratio;
+    }
+
+    /// Get the current phase.
+    pub fn phase(&self) -> f64 {
+        self.phase
+    }
+
+    /// Get the resampling ratio.
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Get the number of sub-phases between two input frames.
+    pub fn n_phases(&self) -> usize {
+        self.n_phases
+    }
+
+    /// Get the current phase as the index of the sub-phase at or before it,
+    /// and the fraction of the distance to the next sub-phase.
+    pub fn sub_phase(&self) -> (usize, f64) {
+        let scaled = self.phase * self.n_phases as f64;
+        let index = scaled.floor();
+        (index as usize, scaled - index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PhaseStepper;
+    use test_log::test;
+
+    #[test]
+    fn advance_upsampling() {
+        let mut stepper = PhaseStepper::new(2.0, 4);
+        assert_eq!(stepper.advance(), (0, 0.5));
+        assert_eq!(stepper.sub_phase(), (2, 0.0));
+        assert_eq!(stepper.advance(), (1, 0.0));
+        assert_eq!(stepper.advance(), (0, 0.5));
+    }
+
+    #[test]
+    fn advance_matches_ratio() {
+        let mut stepper = PhaseStepper::new(0.75, 16);
+        stepper.reset_to(2.25);
+        assert_eq!(stepper.phase(), 0.25);
+        let mut total = 0;
+        for _ in 0..3000 {
+            let (integer_advance, phase) = stepper.advance();
+            assert!((0.0..1.0).contains(&phase));
+            total += integer_advance;
+        }
+        // 3000 output frames at ratio 0.75 correspond to 4000 input frames.
+        assert_eq!(total, 4000);
+        assert!((stepper.phase() - 0.25).abs() < 1.0e-9);
+        let (index, fraction) = stepper.sub_phase();
+        assert!((index as f64 + fraction - 4.0).abs() < 1.0e-6);
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+/// Summary of the time spent per call to
+/// [process_into_buffer](crate::Resampler::process_into_buffer),
+/// as returned by [SincFixedIn::profiling_report](crate::SincFixedIn::profiling_report).
+///
+/// The percentiles are estimated from a histogram, and are given as the upper limit
+/// of the bucket containing the percentile. All times are in microseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfilingReport {
+    /// Median time per call.
+    pub p50_us: f64,
+    /// 99th percentile of the time per call.
+    pub p99_us: f64,
+    /// 99.9th percentile of the time per call.
+    pub p999_us: f64,
+    /// Longest time for a single call.
+    pub max_us: f64,
+    /// Number of calls recorded.
+    pub call_count: u64,
+}
+
+/// A histogram of call durations with a fixed set of buckets.
+/// Recording a duration does not allocate.
+pub struct LatencyHistogram {
+    bucket_limits_us: Vec<f64>,
+    counts: Vec<u64>,
+    max_us: f64,
+    call_count: u64,
+}
+
+impl LatencyHistogram {
+    /// Create a new histogram with the given bucket upper limits in microseconds.
+    /// The limits are sorted, and an extra bucket is added for values above the largest limit.
+    pub fn new(bucket_limits_us: &[f64]) -> Self {
+        let mut limits = bucket_limits_us.to_vec();
+        limits.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let counts = vec![0; limits.len() + 1];
+        LatencyHistogram {
+            bucket_limits_us: limits,
+            counts,
+            max_us: 0.0,
+            call_count: 0,
+        }
+    }
+
+    /// Default buckets, logarithmically spaced with four buckets per decade from 1 us to 1 s.
+    pub fn default_limits() -> Vec<f64> {
+        (0..=24).map(|n| 10.0f64.powf(n as f64 / 4.0)).collect()
+    }
+
+    /// Record the duration of one call.
+    pub fn record(&mut self, duration: Duration) {
+        let micros = duration.as_secs_f64() * 1.0e6;
+        let bucket = self
+            .bucket_limits_us
+            .partition_point(|limit| *limit < micros);
+        self.counts[bucket] += 1;
+        self.call_count += 1;
+        if micros > self.max_us {
+            self.max_us = micros;
+        }
+    }
+
+    /// Estimate the given percentile, as the upper limit of the bucket containing it.
+    /// Values in the overflow bucket are reported as the maximum recorded value.
+    fn percentile(&self, fraction: f64) -> f64 {
+        if self.call_count == 0 {
+            return 0.0;
+        }
+        let wanted = (fraction * self.call_count as f64).ceil().max(1.0) as u64;
+        let mut accumulated = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            accumulated += count;
+            if accumulated >= wanted {
+                return self
+                    .bucket_limits_us
+                    .get(bucket)
+                    .map(|limit| limit.min(self.max_us))
+                    .unwrap_or(self.max_us);
+            }
+        }
+        self.max_us
+    }
+
+    /// Make a report from the recorded values.
+    pub fn report(&self) -> ProfilingReport {
+        ProfilingReport {
+            p50_us: self.percentile(0.5),
+            p99_us: self.percentile(0.99),
+            p999_us: self.percentile(0.999),
+            max_us: self.max_us,
+            call_count: self.call_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LatencyHistogram;
+    use std::time::Duration;
+    use test_log::test;
+
+    #[test]
+    fn histogram_percentiles() {
+        let mut histogram = LatencyHistogram::new(&[10.0, 100.0, 1000.0]);
+        for _ in 0..990 {
+            histogram.record(Duration::from_micros(5));
+        }
+        for _ in 0..9 {
+            histogram.record(Duration::from_micros(50));
+        }
+        histogram.record(Duration::from_micros(5000));
+        let report = histogram.report();
+        assert_eq!(report.call_count, 1000);
+        assert_eq!(report.p50_us, 10.0);
+        assert_eq!(report.p99_us, 10.0);
+        assert_eq!(report.p999_us, 100.0);
+        assert_eq!(report.max_us, 5000.0);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::PolynomialDegree;
+
+/// The kind of filter used by a resampler, see [QualityInfo].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    /// Windowed sinc interpolation, used by [SincFixedIn](crate::SincFixedIn)
+    /// and [SincFixedOut](crate::SincFixedOut).
+    Sinc {
+        /// Length of the sinc interpolation filter.
+        sinc_len: usize,
+        /// The number of intermediate points between two input frames.
+        oversampling_factor: usize,
+    },
+    /// Polynomial interpolation without an anti-aliasing filter, used by
+    /// [FastFixedIn](crate::FastFixedIn) and [FastFixedOut](crate::FastFixedOut).
+    Polynomial(PolynomialDegree),
+    /// Windowed sinc filter applied in the frequency domain, used by the synchronous resamplers.
+    Fft {
+        /// Size of the forward FFT.
+        fft_size_in: usize,
+        /// Size of the inverse FFT.
+        fft_size_out: usize,
+    },
+    /// No filter, used by the [PassthroughResampler](crate::PassthroughResampler) test double.
+    Passthrough,
+    /// A filter that isn't described by the resampler,
+    /// as reported by the default implementation of [quality_info](crate::Resampler::quality_info).
+    Unknown,
+}
+
+/// A description of the filter used by a resampler, as returned by
+/// [quality_info](crate::Resampler::quality_info).
+/// The values are approximations meant for logging and comparing settings,
+/// not measurements of the actual response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityInfo {
+    /// The kind of filter.
+    pub filter: FilterType,
+    /// Approximate stopband attenuation in dB.
+    /// For the sinc filters, this is the side lobe level of the window function.
+    /// For the polynomial interpolators, which have no stopband,
+    /// it is the attenuation of the first image of a tone at a quarter of the input sample rate.
+    /// This is `None` when a resampler uses a custom interpolator.
+    pub attenuation_db: Option<f64>,
+    /// Approximate width of the transition band, relative to the Nyquist frequency
+    /// of the lower one of the input and output sample rates.
+    /// The polynomial interpolators roll off over the whole band, and give 1.0.
+    /// This is `None` when a resampler uses a custom interpolator.
+    pub transition_bandwidth: Option<f64>,
+    /// The delay of the resampler as a number of output frames,
+    /// see [output_delay](crate::Resampler::output_delay).
+    pub latency: usize,
+}
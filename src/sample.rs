@@ -48,6 +48,49 @@ where
     /// Calculate the cosine of `self`.
     fn cos(self) -> Self;
 
+    /// Calculate the exponential function of `self`.
+    fn exp(self) -> Self;
+
+    /// Calculate the square root of `self`.
+    fn sqrt(self) -> Self;
+
+    /// Calculate the absolute value of `self`.
+    fn abs(self) -> Self;
+
+    /// Raise `self` to an integer power.
+    /// The default implementation uses repeated multiplication,
+    /// which is suitable for the small exponents used in window functions.
+    fn powi(self, n: i32) -> Self {
+        let mut result = Self::one();
+        for _ in 0..n.unsigned_abs() {
+            result *= self;
+        }
+        if n < 0 {
+            Self::one() / result
+        } else {
+            result
+        }
+    }
+
+    /// Raise `self` to a floating point power.
+    fn powf(self, exp: Self) -> Self;
+
+    /// Convert an [f64] value into the current type.
+    /// Values outside the range of the type are clamped to the largest finite
+    /// positive or negative value.
+    fn from_f64_saturating(value: f64) -> Self;
+
+    /// Convert `self` into an [f64] value.
+    fn to_f64(self) -> f64;
+
+    /// Get the larger of `self` and `other`.
+    /// If one of the values is NaN, the other one is returned.
+    fn max(self, other: Self) -> Self;
+
+    /// Get the smaller of `self` and `other`.
+    /// If one of the values is NaN, the other one is returned.
+    fn min(self, other: Self) -> Self;
+
     /// Coerce `value` into the current type.
     ///
     /// Coercions are governed through the private `CoerceFrom` trait.
@@ -69,6 +112,42 @@ impl Sample for f32 {
     fn cos(self) -> Self {
         f32::cos(self)
     }
+
+    fn exp(self) -> Self {
+        f32::exp(self)
+    }
+
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        f32::powi(self, n)
+    }
+
+    fn powf(self, exp: Self) -> Self {
+        f32::powf(self, exp)
+    }
+
+    fn max(self, other: Self) -> Self {
+        f32::max(self, other)
+    }
+
+    fn min(self, other: Self) -> Self {
+        f32::min(self, other)
+    }
+
+    fn from_f64_saturating(value: f64) -> Self {
+        value.clamp(f32::MIN as f64, f32::MAX as f64) as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
 }
 
 impl Sample for f64 {
@@ -81,6 +160,42 @@ impl Sample for f64 {
     fn cos(self) -> Self {
         f64::cos(self)
     }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        f64::powi(self, n)
+    }
+
+    fn powf(self, exp: Self) -> Self {
+        f64::powf(self, exp)
+    }
+
+    fn max(self, other: Self) -> Self {
+        f64::max(self, other)
+    }
+
+    fn min(self, other: Self) -> Self {
+        f64::min(self, other)
+    }
+
+    fn from_f64_saturating(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
 }
 
 /// The trait used to coerce a value infallibly from one type to another.
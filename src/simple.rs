@@ -0,0 +1,362 @@
+use crate::halfband::{power_of_two_exponent, HalfbandFilter};
+use crate::{
+    calculate_cutoff, FastFixedIn, PolynomialDegree, Resampler, SincFixedIn,
+    SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+
+/// The quality setting for [resample_mono].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    /// Polynomial interpolation without anti-aliasing, using [FastFixedIn].
+    /// This is very fast, but gives aliasing artefacts.
+    Low,
+    /// Sinc interpolation with a sinc length of 128 and linear interpolation
+    /// between the intermediate points.
+    Medium,
+    /// Sinc interpolation with a sinc length of 256 and cubic interpolation
+    /// between the intermediate points.
+    High,
+}
+
+/// Chunk size used by [resample_mono].
+const CHUNK_SIZE: usize = 1024;
+
+/// Resample a complete clip of mono audio from one sample rate to another.
+///
+/// This takes care of splitting the input into chunks, flushing the resampler at the end,
+/// and removing the delay through the resampler.
+/// The output has the length `input.len() * to / from`, rounded to the nearest integer.
+///
+/// When the ratio is 2, 4 or 8, or the reciprocal of one of them, the [Quality::Medium]
+/// and [Quality::High] settings use a cascade of halfband filters instead of a sinc resampler.
+/// This is much faster, and the filters give a similar quality.
+/// The output is aligned in time with what the sinc resampler would give.
+/// Only this function and [resample_with_crossfade] make this substitution,
+/// the resampler types such as [SincFixedIn] always use their own filters.
+///
+/// Panics if either of the sample rates is zero.
+pub fn resample_mono(input: &[f32], from: u32, to: u32, quality: Quality) -> Vec<f32> {
+    assert!(from > 0 && to > 0, "Sample rates must be larger than zero");
+    resample_with_ratio(input, to as f64 / from as f64, quality)
+}
+
+/// Downsample a complete clip of mono audio by a factor of two, using a halfband filter.
+///
+/// This is the fastest way to halve the sample rate, since only every second tap
+/// of the filter needs to be evaluated, and only for the kept samples.
+/// The filter has no delay, and the samples outside the clip are treated as zeros,
+/// so there is nothing to flush.
+/// The output has the length `input.len() / 2`, rounded up.
+pub fn downsample_2x(input: &[f32]) -> Vec<f32> {
+    let wave = input.iter().map(|value| *value as f64).collect::<Vec<_>>();
+    HalfbandFilter::new()
+        .downsample(&wave)
+        .into_iter()
+        .map(|value| value as f32)
+        .collect()
+}
+
+/// Upsample a complete clip of mono audio by a factor of two, using a halfband filter.
+///
+/// The even output samples are copies of the input samples,
+/// and only the odd ones need to be interpolated.
+/// As for [downsample_2x], the filter has no delay and there is nothing to flush.
+/// The output has the length `2 * input.len()`.
+pub fn upsample_2x(input: &[f32]) -> Vec<f32> {
+    let wave = input.iter().map(|value| *value as f64).collect::<Vec<_>>();
+    HalfbandFilter::new()
+        .upsample(&wave)
+        .into_iter()
+        .map(|value| value as f32)
+        .collect()
+}
+
+/// A resampled loop, as returned by [resample_with_crossfade].
+#[derive(Debug, Clone)]
+pub struct ResampledLoop {
+    /// The resampled audio data, with the crossfade applied.
+    pub data: Vec<f32>,
+    /// The start of the loop in the resampled data.
+    pub loop_start: usize,
+    /// The end of the loop in the resampled data, the first frame after the loop.
+    pub loop_end: usize,
+    /// The length of the crossfade in the resampled data.
+    pub xfade_len: usize,
+}
+
+/// Resample a mono clip containing a loop, and apply an equal-power crossfade
+/// at the end of the loop, so that the resampled loop plays seamlessly.
+///
+/// The loop plays the frames from `loop_start` up to, but not including, `loop_end`.
+/// In the last `xfade_len` frames of the loop, the audio is faded out while
+/// the audio just before `loop_start` is faded in, with gains that follow
+/// a quarter period of a cosine and a sine.
+/// When playback then jumps from the end to the start of the loop, it continues
+/// from where the faded in audio left off.
+///
+/// The clip is first resampled with [Quality::High] and the given `ratio`
+/// between the output and input sample rates.
+/// The loop points and the crossfade length are scaled by the ratio and rounded,
+/// and the crossfade is then applied to the resampled data.
+/// This keeps the crossfade aligned with the loop points,
+/// and makes the crossfade gains exact at the new sample rate.
+///
+/// Panics if the ratio is not positive, if the loop points are outside the clip,
+/// or if the crossfade is longer than either the loop or the audio before the loop.
+pub fn resample_with_crossfade(
+    input: &[f32],
+    loop_start: usize,
+    loop_end: usize,
+    xfade_len: usize,
+    ratio: f64,
+) -> ResampledLoop {
+    assert!(ratio > 0.0, "The ratio must be larger than zero");
+    assert!(
+        loop_start < loop_end && loop_end <= input.len(),
+        "The loop {}..{} must be within the clip of {} frames",
+        loop_start,
+        loop_end,
+        input.len()
+    );
+    assert!(
+        xfade_len <= loop_start && xfade_len <= loop_end - loop_start,
+        "The crossfade of {} frames is longer than the loop or the audio before it",
+        xfade_len
+    );
+    let mut data = resample_with_ratio(input, ratio, Quality::High);
+    let new_start = (loop_start as f64 * ratio).round() as usize;
+    let new_end = ((loop_end as f64 * ratio).round() as usize).min(data.len());
+    let new_xfade = ((xfade_len as f64 * ratio).round() as usize)
+        .min(new_start)
+        .min(new_end - new_start);
+    let fade_start = new_end - new_xfade;
+    for n in 0..new_xfade {
+        let angle = std::f32::consts::FRAC_PI_2 * (n + 1) as f32 / new_xfade as f32;
+        let fading_in = data[new_start - new_xfade + n];
+        data[fade_start + n] = data[fade_start + n] * angle.cos() + fading_in * angle.sin();
+    }
+    ResampledLoop {
+        data,
+        loop_start: new_start,
+        loop_end: new_end,
+        xfade_len: new_xfade,
+    }
+}
+
+/// Helper function. Resample a complete mono clip with the given ratio and quality.
+/// With sinc quality, ratios of 2, 4 and 8 and their reciprocals use a cascade
+/// of halfband filters instead, which is much cheaper.
+fn resample_with_ratio(input: &[f32], ratio: f64, quality: Quality) -> Vec<f32> {
+    let new_length = (input.len() as f64 * ratio).round() as usize;
+    if quality != Quality::Low {
+        if let Some(exponent) = power_of_two_exponent(ratio) {
+            return resample_halfband(input, exponent, new_length);
+        }
+    }
+    resample_general(input, ratio, quality, new_length)
+}
+
+/// Helper function. Resample a mono clip by a factor `2^exponent`,
+/// using one halfband stage per factor of two.
+///
+/// The halfband filters place output frame `n` at input position `n / ratio`,
+/// while the sinc resampler places it at `(n + 1) / ratio - 1`.
+/// That is where frame `n + 1` ends up when a zero is added before the input,
+/// so the cascade is run on the padded input and the first output frame is dropped.
+fn resample_halfband(input: &[f32], exponent: i32, new_length: usize) -> Vec<f32> {
+    let filter = HalfbandFilter::new();
+    let mut wave = std::iter::once(0.0)
+        .chain(input.iter().map(|value| *value as f64))
+        .collect::<Vec<_>>();
+    for _ in 0..exponent.abs() {
+        wave = if exponent > 0 {
+            filter.upsample(&wave)
+        } else {
+            filter.downsample(&wave)
+        };
+    }
+    wave.remove(0);
+    wave.resize(new_length, 0.0);
+    wave.into_iter().map(|value| value as f32).collect()
+}
+
+/// Helper function. Resample a mono clip with a resampler of the given quality.
+fn resample_general(input: &[f32], ratio: f64, quality: Quality, new_length: usize) -> Vec<f32> {
+    match quality {
+        Quality::Low => {
+            let resampler =
+                FastFixedIn::<f32>::new(ratio, 1.0, PolynomialDegree::Cubic, CHUNK_SIZE, 1)
+                    .expect("Ratio was already validated");
+            let delay = resampler.output_delay();
+            resample_clip(resampler, input, new_length, delay)
+        }
+        // The sinc resampler outputs fewer frames for the first chunk instead of
+        // delaying the output, so there is no delay to remove.
+        Quality::Medium | Quality::High => {
+            resample_clip(sinc_resampler(ratio, quality), input, new_length, 0)
+        }
+    }
+}
+
+/// Helper function. Create a sinc resampler with the parameters for the given quality.
+fn sinc_resampler(ratio: f64, quality: Quality) -> SincFixedIn<f32> {
+    let (sinc_len, oversampling_factor, interpolation) = if quality == Quality::High {
+        (256, 256, SincInterpolationType::Cubic)
+    } else {
+        (128, 128, SincInterpolationType::Linear)
+    };
+    let window = WindowFunction::BlackmanHarris2;
+    let parameters = SincInterpolationParameters {
+        sinc_len,
+        f_cutoff: calculate_cutoff(sinc_len, window),
+        interpolation,
+        oversampling_factor,
+        window,
+    };
+    SincFixedIn::<f32>::new(ratio, 1.0, parameters, CHUNK_SIZE, 1)
+        .expect("Ratio was already validated")
+}
+
+/// Helper function. Resample a mono clip, flush the resampler and remove `delay` frames
+/// from the start of the output.
+fn resample_clip<R: Resampler<f32>>(
+    mut resampler: R,
+    input: &[f32],
+    new_length: usize,
+    delay: usize,
+) -> Vec<f32> {
+    let mut output = resampler
+        .process_chunked(&[input], None)
+        .expect("Buffers are allocated by the resampler")
+        .remove(0);
+    while output.len() < new_length + delay {
+        let flushed = resampler
+            .process_partial::<&[f32]>(None, None)
+            .expect("Buffers are allocated by the resampler");
+        output.extend_from_slice(&flushed[0]);
+    }
+    output.drain(..delay);
+    output.truncate(new_length);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        downsample_2x, resample_general, resample_mono, resample_with_crossfade, upsample_2x,
+        Quality,
+    };
+    use crate::testing::signals::sine;
+    use test_log::test;
+
+    #[test]
+    fn halfband_fast_path() {
+        let input = sine::<f32>(1000.0, 11025.0, 4000, 1).remove(0);
+        let fast = resample_mono(&input, 11025, 44100, Quality::High);
+        assert_eq!(fast.len(), 16000);
+        // Like the sinc resampler, output frame n is at input position (n + 1) / 4 - 1.
+        let omega = 2.0 * std::f64::consts::PI * 1000.0 / 11025.0;
+        for (n, value) in fast.iter().enumerate().skip(1000).take(14000) {
+            let expected = (omega * ((n + 1) as f64 / 4.0 - 1.0)).sin();
+            assert!((*value as f64 - expected).abs() < 1.0e-5);
+        }
+        // The difference to the general resampler is the error of the sinc filter.
+        let general = resample_general(&input, 4.0, Quality::High, 16000);
+        for n in 1000..15000 {
+            let (value, expected) = (fast[n], general[n]);
+            assert!(
+                (value - expected).abs() < 5.0e-3,
+                "{}: {} != {}",
+                n,
+                value,
+                expected
+            );
+        }
+
+        let output = resample_mono(&fast, 44100, 11025, Quality::High);
+        assert_eq!(output.len(), 4000);
+        for (value, expected) in output.iter().zip(input.iter()).skip(250).take(3500) {
+            assert!((value - expected).abs() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn halve_and_double() {
+        // A tone above the new Nyquist frequency should be removed, not aliased.
+        let high = sine::<f32>(17000.0, 48000.0, 4800, 1).remove(0);
+        let aliased = downsample_2x(&high);
+        assert_eq!(aliased.len(), 2400);
+        let peak = aliased[200..2200]
+            .iter()
+            .fold(0.0f32, |peak, value| peak.max(value.abs()));
+        assert!(peak < 1.0e-3, "{}", peak);
+
+        let input = sine::<f32>(1000.0, 48000.0, 4801, 1).remove(0);
+        let halved = downsample_2x(&input);
+        assert_eq!(halved.len(), 2401);
+        let output = upsample_2x(&halved);
+        assert_eq!(output.len(), 4802);
+        for (value, expected) in output.iter().zip(input.iter()).skip(200).take(4400) {
+            assert!((value - expected).abs() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn resample_mono_tone() {
+        let input = sine::<f32>(1000.0, 44100.0, 44100, 1).remove(0);
+        for quality in [Quality::Low, Quality::Medium, Quality::High] {
+            let output = resample_mono(&input, 44100, 48000, quality);
+            assert_eq!(output.len(), 48000);
+        }
+        let output = resample_mono(&input, 44100, 48000, Quality::High);
+        // Fit a 1 kHz sine and cosine to the middle part of the output,
+        // and compare the residual with the fitted tone.
+        let middle = &output[1000..47000];
+        let omega = 2.0 * std::f64::consts::PI * 1000.0 / 48000.0;
+        let (mut sin_sum, mut cos_sum) = (0.0, 0.0);
+        for (n, value) in middle.iter().enumerate() {
+            let phase = omega * (n + 1000) as f64;
+            sin_sum += *value as f64 * phase.sin();
+            cos_sum += *value as f64 * phase.cos();
+        }
+        let sin_amp = 2.0 * sin_sum / middle.len() as f64;
+        let cos_amp = 2.0 * cos_sum / middle.len() as f64;
+        let mut residual = 0.0;
+        let mut tone = 0.0;
+        for (n, value) in middle.iter().enumerate() {
+            let phase = omega * (n + 1000) as f64;
+            let fitted = sin_amp * phase.sin() + cos_amp * phase.cos();
+            residual += (*value as f64 - fitted).powi(2);
+            tone += fitted.powi(2);
+        }
+        let thd = (residual / tone).sqrt();
+        assert!(thd < 1.0e-3, "THD {} is too high", thd);
+        assert!((sin_amp.hypot(cos_amp) - 1.0).abs() < 0.01);
+        // The output is aligned with the input, up to the fraction of a frame
+        // from placing output frame n at input position (n + 1) / ratio - 1.
+        assert!(cos_amp.abs() < 0.02, "{}", cos_amp);
+    }
+
+    #[test]
+    fn crossfade_loop() {
+        let input = vec![0.5; 20000];
+        let resampled = resample_with_crossfade(&input, 8000, 16000, 2000, 1.5);
+        assert_eq!(resampled.data.len(), 30000);
+        assert_eq!(resampled.loop_start, 12000);
+        assert_eq!(resampled.loop_end, 24000);
+        assert_eq!(resampled.xfade_len, 3000);
+        // With a constant input, the crossfade region holds the sum of the fade out
+        // and fade in curves, cos + sin, of an equal-power crossfade.
+        let fade_start = resampled.loop_end - resampled.xfade_len;
+        for n in 0..resampled.xfade_len {
+            let angle = std::f32::consts::FRAC_PI_2 * (n + 1) as f32 / 3000.0;
+            let expected = 0.5 * (angle.cos() + angle.sin());
+            let value = resampled.data[fade_start + n];
+            assert!((value - expected).abs() < 1.0e-3, "{} {}", value, expected);
+        }
+        // The end of the loop continues seamlessly into the start.
+        let last = resampled.data[resampled.loop_end - 1];
+        let before_start = resampled.data[resampled.loop_start - 1];
+        assert!((last - before_start).abs() < 1.0e-6);
+    }
+}
@@ -23,18 +23,51 @@ pub fn make_sincs<T>(
 where
     T: Sample,
 {
-    let totpoints = npoints * factor;
+    let y = make_windowed_sinc(npoints, factor, f_cutoff, windowfunc);
+    split_sincs(&y, npoints, factor)
+}
+
+/// Helper function. Make a single windowed sinc with `npoints * factor` points.
+pub fn make_windowed_sinc<T>(
+    npoints: usize,
+    factor: usize,
+    f_cutoff: f32,
+    windowfunc: WindowFunction,
+) -> Vec<T>
+where
+    T: Sample,
+{
+    let window = make_window::<T>(npoints * factor, windowfunc);
+    apply_sinc(&window, factor, f_cutoff)
+}
+
+/// Helper function. Multiply a window of `npoints * factor` points with a sinc.
+pub fn apply_sinc<T>(window: &[T], factor: usize, f_cutoff: f32) -> Vec<T>
+where
+    T: Sample,
+{
+    let totpoints = window.len();
     let mut y = Vec::with_capacity(totpoints);
-    let window = make_window::<T>(totpoints, windowfunc);
-    let mut sum = T::zero();
-    for (x, w) in window.iter().enumerate().take(totpoints) {
+    for (x, w) in window.iter().enumerate() {
         let val = *w
             * sinc(
                 (T::coerce(x) - T::coerce(totpoints / 2)) * T::coerce(f_cutoff) / T::coerce(factor),
             );
-        sum += val;
         y.push(val);
     }
+    y
+}
+
+/// Helper function. Normalize an oversampled sinc and split it into `factor` sincs
+/// of `npoints` points each.
+pub fn split_sincs<T>(y: &[T], npoints: usize, factor: usize) -> Vec<Vec<T>>
+where
+    T: Sample,
+{
+    let mut sum = T::zero();
+    for val in y.iter() {
+        sum += *val;
+    }
     sum /= T::coerce(factor);
     debug!(
         "Generate sincs, length: {}, oversampling: {}, normalized by: {:?}",
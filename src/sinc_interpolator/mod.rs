@@ -1,4 +1,5 @@
-use crate::sinc::make_sincs;
+use crate::error::WindowError;
+use crate::sinc::{apply_sinc, make_sincs, split_sincs};
 use crate::windows::WindowFunction;
 use crate::Sample;
 
@@ -49,10 +50,51 @@ interpolator! {
 }
 
 /// Functions for making the scalar product with a sinc.
-pub trait SincInterpolator<T>: Send {
+///
+/// The interpolator of a resampler is shared with the resamplers created from it by
+/// `try_clone`, which may be used from other threads. Implementations must therefore be `Sync`
+/// as well as `Send`. Earlier versions only required `Send`,
+/// and a custom interpolator that is not `Sync` must be changed,
+/// for example by replacing any `Cell` or `RefCell` with an atomic or a `Mutex`.
+pub trait SincInterpolator<T>: Send + Sync {
     /// Make the scalar product between the waveform starting at `index` and the sinc of `subindex`.
     fn get_sinc_interpolated(&self, wave: &[T], index: usize, subindex: usize) -> T;
 
+    /// Make the scalar products between two waveforms starting at `index` and the sinc of `subindex`.
+    /// Implementations may read the sinc coefficients once and apply them to both waveforms.
+    /// The default implementation calls [get_sinc_interpolated](SincInterpolator::get_sinc_interpolated)
+    /// once per waveform.
+    fn get_sinc_interpolated_pair(
+        &self,
+        wave_a: &[T],
+        wave_b: &[T],
+        index: usize,
+        subindex: usize,
+    ) -> (T, T) {
+        (
+            self.get_sinc_interpolated(wave_a, index, subindex),
+            self.get_sinc_interpolated(wave_b, index, subindex),
+        )
+    }
+
+    /// Make the scalar product between the waveform starting at `index` and a sinc that is
+    /// linearly interpolated between the sincs of `subindex` and the following one,
+    /// where `frac` is the fractional position between them.
+    /// The default implementation calculates the two scalar products and interpolates between them.
+    fn get_sinc_interpolated_lin(&self, wave: &[T], index: usize, subindex: usize, frac: T) -> T
+    where
+        T: Sample,
+    {
+        let (next_index, next_subindex) = if subindex + 1 < self.nbr_sincs() {
+            (index, subindex + 1)
+        } else {
+            (index + 1, 0)
+        };
+        let y0 = self.get_sinc_interpolated(wave, index, subindex);
+        let y1 = self.get_sinc_interpolated(wave, next_index, next_subindex);
+        y0 + frac * (y1 - y0)
+    }
+
     /// Get sinc length.
     fn len(&self) -> usize;
 
@@ -63,11 +105,24 @@ pub trait SincInterpolator<T>: Send {
 
     /// Get number of sincs used for oversampling.
     fn nbr_sincs(&self) -> usize;
+
+    /// Get the number of bytes used for storing the sinc tables.
+    /// The default implementation assumes that a single table of
+    /// [nbr_sincs](SincInterpolator::nbr_sincs) sincs with [len](SincInterpolator::len)
+    /// values each is stored.
+    fn table_memory_usage(&self) -> usize {
+        std::mem::size_of::<T>() * self.len() * self.nbr_sincs()
+    }
 }
 
 /// A plain scalar interpolator.
+///
+/// When created with [ScalarInterpolator::new_separable], it also stores the differences
+/// between neighboring sincs. This allows linear interpolation between sincs to be done
+/// with a single scalar product instead of two, at the cost of twice the memory for the tables.
 pub struct ScalarInterpolator<T> {
     sincs: Vec<Vec<T>>,
+    diffs: Option<Vec<Vec<T>>>,
     length: usize,
     nbr_sincs: usize,
 }
@@ -117,6 +172,108 @@ where
         }
     }
 
+    /// Calculate the scalar products of two input waves and the selected sinc filter,
+    /// reading each sinc coefficient once.
+    fn get_sinc_interpolated_pair(
+        &self,
+        wave_a: &[T],
+        wave_b: &[T],
+        index: usize,
+        subindex: usize,
+    ) -> (T, T) {
+        assert!(
+            (index + self.length) < wave_a.len().min(wave_b.len()),
+            "Tried to interpolate for index {}, max for the given input is {}",
+            index,
+            wave_a.len().min(wave_b.len()) - self.length - 1
+        );
+        assert!(
+            subindex < self.nbr_sincs,
+            "Tried to use sinc subindex {}, max is {}",
+            subindex,
+            self.nbr_sincs - 1
+        );
+        let wave_a = &wave_a[index..(index + self.length)];
+        let wave_b = &wave_b[index..(index + self.length)];
+        let sinc = &self.sincs[subindex];
+        unsafe {
+            let mut acc_a0 = T::zero();
+            let mut acc_a1 = T::zero();
+            let mut acc_a2 = T::zero();
+            let mut acc_a3 = T::zero();
+            let mut acc_b0 = T::zero();
+            let mut acc_b1 = T::zero();
+            let mut acc_b2 = T::zero();
+            let mut acc_b3 = T::zero();
+            let mut idx = 0;
+            for _ in 0..sinc.len() / 4 {
+                let s0 = *sinc.get_unchecked(idx);
+                let s1 = *sinc.get_unchecked(idx + 1);
+                let s2 = *sinc.get_unchecked(idx + 2);
+                let s3 = *sinc.get_unchecked(idx + 3);
+                acc_a0 += *wave_a.get_unchecked(idx) * s0;
+                acc_a1 += *wave_a.get_unchecked(idx + 1) * s1;
+                acc_a2 += *wave_a.get_unchecked(idx + 2) * s2;
+                acc_a3 += *wave_a.get_unchecked(idx + 3) * s3;
+                acc_b0 += *wave_b.get_unchecked(idx) * s0;
+                acc_b1 += *wave_b.get_unchecked(idx + 1) * s1;
+                acc_b2 += *wave_b.get_unchecked(idx + 2) * s2;
+                acc_b3 += *wave_b.get_unchecked(idx + 3) * s3;
+                idx += 4;
+            }
+            (
+                acc_a0 + acc_a1 + acc_a2 + acc_a3,
+                acc_b0 + acc_b1 + acc_b2 + acc_b3,
+            )
+        }
+    }
+
+    /// Calculate the scalar product of an input wave and a sinc interpolated between two sincs.
+    /// Uses the difference tables if available, otherwise it falls back to two scalar products.
+    fn get_sinc_interpolated_lin(&self, wave: &[T], index: usize, subindex: usize, frac: T) -> T {
+        let diffs = match &self.diffs {
+            Some(diffs) if subindex + 1 < self.nbr_sincs => diffs,
+            _ => {
+                let (next_index, next_subindex) = if subindex + 1 < self.nbr_sincs {
+                    (index, subindex + 1)
+                } else {
+                    (index + 1, 0)
+                };
+                let y0 = self.get_sinc_interpolated(wave, index, subindex);
+                let y1 = self.get_sinc_interpolated(wave, next_index, next_subindex);
+                return y0 + frac * (y1 - y0);
+            }
+        };
+        assert!(
+            (index + self.length) < wave.len(),
+            "Tried to interpolate for index {}, max for the given input is {}",
+            index,
+            wave.len() - self.length - 1
+        );
+        let wave_cut = &wave[index..(index + self.length)];
+        let sinc = &self.sincs[subindex];
+        let diff = &diffs[subindex];
+        unsafe {
+            let mut acc0 = T::zero();
+            let mut acc1 = T::zero();
+            let mut acc2 = T::zero();
+            let mut acc3 = T::zero();
+            let mut idx = 0;
+            for _ in 0..wave_cut.len() / 4 {
+                acc0 += *wave_cut.get_unchecked(idx)
+                    * (*sinc.get_unchecked(idx) + frac * *diff.get_unchecked(idx));
+                acc1 += *wave_cut.get_unchecked(idx + 1)
+                    * (*sinc.get_unchecked(idx + 1) + frac * *diff.get_unchecked(idx + 1));
+                acc2 += *wave_cut.get_unchecked(idx + 2)
+                    * (*sinc.get_unchecked(idx + 2) + frac * *diff.get_unchecked(idx + 2));
+                acc3 += *wave_cut.get_unchecked(idx + 3)
+                    * (*sinc.get_unchecked(idx + 3) + frac * *diff.get_unchecked(idx + 3));
+                idx += 4;
+            }
+            acc0 + acc1 + acc2 + acc3
+        }
+    }
+
     fn len(&self) -> usize {
         self.length
     }
@@ -124,6 +281,15 @@ where
     fn nbr_sincs(&self) -> usize {
         self.nbr_sincs
     }
+
+    fn table_memory_usage(&self) -> usize {
+        let diffs_len = self
+            .diffs
+            .as_ref()
+            .map(|diffs| diffs.iter().map(|diff| diff.len()).sum())
+            .unwrap_or(0);
+        std::mem::size_of::<T>() * (self.length * self.nbr_sincs + diffs_len)
+    }
 }
 
 impl<T> ScalarInterpolator<T>
@@ -145,19 +311,84 @@ where
     ) -> Self {
         assert!(sinc_len % 8 == 0, "Sinc length must be a multiple of 8");
         let sincs = make_sincs(sinc_len, oversampling_factor, f_cutoff, window);
+        Self::from_sincs(sincs)
+    }
+
+    /// Create a new ScalarInterpolator using a custom window,
+    /// for example one read by [window_from_csv](crate::window_from_csv).
+    /// The window must have `sinc_len * oversampling_factor` points,
+    /// otherwise [WindowError::InvalidLength] is returned.
+    ///
+    /// The other parameters are the same as for [ScalarInterpolator::new].
+    pub fn new_with_window(
+        sinc_len: usize,
+        oversampling_factor: usize,
+        f_cutoff: f32,
+        window: &[T],
+    ) -> Result<Self, WindowError> {
+        assert!(sinc_len % 8 == 0, "Sinc length must be a multiple of 8");
+        if window.len() != sinc_len * oversampling_factor {
+            return Err(WindowError::InvalidLength {
+                expected: sinc_len * oversampling_factor,
+                actual: window.len(),
+            });
+        }
+        let y = apply_sinc(window, oversampling_factor, f_cutoff);
+        Ok(Self::from_sincs(split_sincs(
+            &y,
+            sinc_len,
+            oversampling_factor,
+        )))
+    }
+
+    /// Create a new ScalarInterpolator from a set of precalculated sincs.
+    pub(crate) fn from_sincs(sincs: Vec<Vec<T>>) -> Self {
+        let length = sincs[0].len();
+        assert!(length % 8 == 0, "Sinc length must be a multiple of 8");
+        let nbr_sincs = sincs.len();
         Self {
             sincs,
-            length: sinc_len,
-            nbr_sincs: oversampling_factor,
+            diffs: None,
+            length,
+            nbr_sincs,
         }
     }
+
+    /// Create a new ScalarInterpolator that also stores the differences between neighboring sincs.
+    /// This speeds up [SincInterpolationType::Linear](crate::SincInterpolationType::Linear)
+    /// interpolation, and gives the same output as [ScalarInterpolator::new]
+    /// apart from rounding errors.
+    ///
+    /// The parameters are the same as for [ScalarInterpolator::new].
+    pub fn new_separable(
+        sinc_len: usize,
+        oversampling_factor: usize,
+        f_cutoff: f32,
+        window: WindowFunction,
+    ) -> Self {
+        let mut interpolator = Self::new(sinc_len, oversampling_factor, f_cutoff, window);
+        let diffs = interpolator
+            .sincs
+            .windows(2)
+            .map(|pair| {
+                pair[1]
+                    .iter()
+                    .zip(pair[0].iter())
+                    .map(|(next, current)| *next - *current)
+                    .collect()
+            })
+            .collect();
+        interpolator.diffs = Some(diffs);
+        interpolator
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::ScalarInterpolator;
     use super::SincInterpolator;
-    use crate::WindowFunction;
+    use crate::windows::make_window;
+    use crate::{WindowError, WindowFunction};
     use num_traits::Float;
     use rand::Rng;
     use test_log::test;
@@ -207,4 +438,80 @@ mod tests {
         let check = get_sinc_interpolated(&wave, 333, &interpolator.sincs[123]);
         assert!((value - check).abs() < 1.0e-6);
     }
+
+    #[test]
+    fn test_scalar_interpolator_pair() {
+        let mut rng = rand::thread_rng();
+        let wave_a = (0..2048).map(|_| rng.gen::<f64>()).collect::<Vec<_>>();
+        let wave_b = (0..2048).map(|_| rng.gen::<f64>()).collect::<Vec<_>>();
+        let interpolator =
+            ScalarInterpolator::<f64>::new(256, 256, 0.947_337_15, WindowFunction::BlackmanHarris2);
+        let (value_a, value_b) =
+            interpolator.get_sinc_interpolated_pair(&wave_a, &wave_b, 333, 123);
+        let check_a = get_sinc_interpolated(&wave_a, 333, &interpolator.sincs[123]);
+        let check_b = get_sinc_interpolated(&wave_b, 333, &interpolator.sincs[123]);
+        assert!((value_a - check_a).abs() < 1.0e-9);
+        assert!((value_b - check_b).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn test_separable_interpolator_lin() {
+        let mut rng = rand::thread_rng();
+        let mut wave = Vec::new();
+        for _ in 0..2048 {
+            wave.push(rng.gen::<f64>());
+        }
+        let sinc_len = 256;
+        let f_cutoff = 0.947_337_15;
+        let oversampling_factor = 256;
+        let window = WindowFunction::BlackmanHarris2;
+
+        let plain = ScalarInterpolator::<f64>::new(sinc_len, oversampling_factor, f_cutoff, window);
+        let separable = ScalarInterpolator::<f64>::new_separable(
+            sinc_len,
+            oversampling_factor,
+            f_cutoff,
+            window,
+        );
+        for subindex in [0, 123, 255] {
+            let value = separable.get_sinc_interpolated_lin(&wave, 333, subindex, 0.3);
+            let check = plain.get_sinc_interpolated_lin(&wave, 333, subindex, 0.3);
+            assert!((value - check).abs() < 1.0e-9);
+        }
+    }
+
+    #[test]
+    fn test_interpolator_with_window() {
+        let sinc_len = 64;
+        let f_cutoff = 0.9;
+        let oversampling_factor = 16;
+        let window = WindowFunction::Blackman;
+
+        let reference =
+            ScalarInterpolator::<f64>::new(sinc_len, oversampling_factor, f_cutoff, window);
+        let custom = ScalarInterpolator::<f64>::new_with_window(
+            sinc_len,
+            oversampling_factor,
+            f_cutoff,
+            &make_window(sinc_len * oversampling_factor, window),
+        )
+        .unwrap();
+        for (sinc, check) in custom.sincs.iter().zip(reference.sincs.iter()) {
+            for (value, expected) in sinc.iter().zip(check.iter()) {
+                assert!((value - expected).abs() < 1.0e-12);
+            }
+        }
+        assert!(matches!(
+            ScalarInterpolator::<f64>::new_with_window(
+                sinc_len,
+                oversampling_factor,
+                f_cutoff,
+                &[1.0; 100]
+            ),
+            Err(WindowError::InvalidLength {
+                expected: 1024,
+                actual: 100
+            })
+        ));
+    }
 }
@@ -19,8 +19,8 @@ use core::arch::x86_64::{
 static FEATURES: &[CpuFeature] = &[CpuFeature::Avx, CpuFeature::Fma];
 
 /// Trait governing what can be done with an AvxSample.
-pub trait AvxSample: Sized + Send {
-    type Sinc: Send;
+pub trait AvxSample: Sized + Send + Sync {
+    type Sinc: Send + Sync;
 
     /// Pack sincs into a vector.
     ///
@@ -13,8 +13,8 @@ use core::arch::aarch64::{vaddq_f64, vfmaq_f64, vld1q_f64, vmovq_n_f64, vst1q_f6
 static FEATURES: &[CpuFeature] = &[CpuFeature::Neon];
 
 /// Trait governing what can be done with an NeonSample.
-pub trait NeonSample: Sized + Send {
-    type Sinc: Send;
+pub trait NeonSample: Sized + Send + Sync {
+    type Sinc: Send + Sync;
 
     /// Pack sincs into a vector.
     ///
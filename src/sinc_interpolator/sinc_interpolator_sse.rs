@@ -15,8 +15,8 @@ use core::arch::x86_64::{
 static FEATURES: &[CpuFeature] = &[CpuFeature::Sse3];
 
 /// Trait governing what can be done with an SseSample.
-pub trait SseSample: Sized + Send {
-    type Sinc: Send;
+pub trait SseSample: Sized + Send + Sync {
+    type Sinc: Send + Sync;
 
     /// Pack sincs into a vector.
     ///
@@ -0,0 +1,182 @@
+use crate::windows::{make_window, window_overlap_add_scale, WindowFunction};
+use crate::Sample;
+
+/// A helper for processing a single channel of audio in overlapping windowed frames,
+/// for example for spectral processing via a short-time Fourier transform.
+///
+/// The input is collected in a buffer holding one frame.
+/// Every time `hop_size` new samples have arrived, the frame is multiplied by the window
+/// and passed to a user supplied function, that may modify it in place.
+/// The returned frames are then scaled and summed by overlap-add to form the output.
+///
+/// The scaling is given by [window_overlap_add_scale], meaning that
+/// the output is a delayed copy of the input if the frames are not modified.
+/// The delay is given by [delay](SlidingWindowProcessor::delay).
+pub struct SlidingWindowProcessor<T> {
+    window: Vec<T>,
+    hop_size: usize,
+    scale: T,
+    input_buffer: Vec<T>,
+    output_buffer: Vec<T>,
+    frame: Vec<T>,
+    new_samples: usize,
+}
+
+impl<T> SlidingWindowProcessor<T>
+where
+    T: Sample,
+{
+    /// Create a new SlidingWindowProcessor.
+    ///
+    /// Parameters are:
+    /// - `frame_size`: Length of each frame in samples.
+    /// - `hop_size`: Number of samples between the starts of two consecutive frames.
+    /// - `window`: Window function to apply to each frame.
+    ///
+    /// Panics if `hop_size` is zero or larger than `frame_size / 2`.
+    pub fn new(frame_size: usize, hop_size: usize, window: WindowFunction) -> Self {
+        let scale = window_overlap_add_scale::<T>(window, frame_size, hop_size);
+        debug!(
+            "Create new SlidingWindowProcessor, frame_size: {}, hop_size: {}, window: {:?}",
+            frame_size, hop_size, window
+        );
+        SlidingWindowProcessor {
+            window: make_window::<T>(frame_size, window),
+            hop_size,
+            scale,
+            input_buffer: vec![T::zero(); frame_size],
+            output_buffer: vec![T::zero(); frame_size],
+            frame: vec![T::zero(); frame_size],
+            new_samples: 0,
+        }
+    }
+
+    /// Process a block of input samples of any length.
+    /// The function `frame_fn` is called once for every completed hop,
+    /// with the windowed frame as argument.
+    ///
+    /// The output is returned in a new vector, holding `hop_size` samples per completed hop.
+    /// Any samples remaining after the last completed hop are kept until the next call.
+    pub fn process<F>(&mut self, wave_in: &[T], mut frame_fn: F) -> Vec<T>
+    where
+        F: FnMut(&mut [T]),
+    {
+        let frame_size = self.window.len();
+        let mut wave_out = Vec::with_capacity(wave_in.len() + self.hop_size);
+        for value in wave_in.iter() {
+            self.input_buffer[frame_size - self.hop_size + self.new_samples] = *value;
+            self.new_samples += 1;
+            if self.new_samples < self.hop_size {
+                continue;
+            }
+            for ((frame, input), window) in self
+                .frame
+                .iter_mut()
+                .zip(self.input_buffer.iter())
+                .zip(self.window.iter())
+            {
+                *frame = *input * *window;
+            }
+            frame_fn(&mut self.frame);
+            for (output, frame) in self.output_buffer.iter_mut().zip(self.frame.iter()) {
+                *output += *frame * self.scale;
+            }
+            // The first hop of the output will not receive any more contributions.
+            wave_out.extend_from_slice(&self.output_buffer[..self.hop_size]);
+            self.output_buffer.copy_within(self.hop_size.., 0);
+            for value in self.output_buffer[frame_size - self.hop_size..].iter_mut() {
+                *value = T::zero();
+            }
+            self.input_buffer.copy_within(self.hop_size.., 0);
+            self.new_samples = 0;
+        }
+        trace!(
+            "Sliding window processing, {} samples in, {} samples out",
+            wave_in.len(),
+            wave_out.len()
+        );
+        wave_out
+    }
+
+    /// Get the delay from input to output in samples.
+    pub fn delay(&self) -> usize {
+        self.window.len() - self.hop_size
+    }
+
+    /// Get the hop size in samples.
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// Get the frame size in samples.
+    pub fn frame_size(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Reset the processor state and clear all buffers.
+    pub fn reset(&mut self) {
+        self.input_buffer
+            .iter_mut()
+            .for_each(|val| *val = T::zero());
+        self.output_buffer
+            .iter_mut()
+            .for_each(|val| *val = T::zero());
+        self.new_samples = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlidingWindowProcessor;
+    use crate::testing::signals::white_noise;
+    use crate::WindowFunction;
+    use test_log::test;
+
+    #[test]
+    fn reconstructs_unmodified_frames() {
+        for (window, hop_size) in [
+            (WindowFunction::Hann, 512),
+            (WindowFunction::Hann, 256),
+            (WindowFunction::Blackman, 256),
+        ] {
+            let mut processor = SlidingWindowProcessor::<f64>::new(1024, hop_size, window);
+            let input = white_noise::<f64>(1, 10000, 1).remove(0);
+            let mut output = Vec::new();
+            let mut frames = 0;
+            // Process in blocks that don't match the hop size.
+            for block in input.chunks(700) {
+                output.extend(processor.process(block, |frame| {
+                    assert_eq!(frame.len(), 1024);
+                    frames += 1;
+                }));
+            }
+            assert_eq!(frames, 10000 / hop_size);
+            assert_eq!(output.len(), frames * hop_size);
+            let delay = processor.delay();
+            assert_eq!(delay, 1024 - hop_size);
+            for (n, value) in output.iter().enumerate().skip(delay) {
+                assert!(
+                    (value - input[n - delay]).abs() < 1.0e-9,
+                    "{:?}, sample {}: {} != {}",
+                    window,
+                    n,
+                    value,
+                    input[n - delay]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn modified_frames() {
+        let mut processor = SlidingWindowProcessor::<f64>::new(256, 64, WindowFunction::Hann);
+        let input = vec![1.0; 2048];
+        let output = processor.process(&input, |frame| {
+            frame.iter_mut().for_each(|val| *val *= 0.5);
+        });
+        assert_eq!(output.len(), 2048);
+        for value in output.iter().skip(processor.delay()) {
+            assert!((value - 0.5).abs() < 1.0e-9);
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use crate::error::ResamplerConstructionError;
 use crate::sinc::make_sincs;
-use crate::windows::WindowFunction;
+use crate::windows::{approximate_attenuation, WindowFunction};
 use num_complex::Complex;
 use num_integer as integer;
 use num_traits::Zero;
 use std::sync::Arc;
 
 use crate::error::{ResampleError, ResampleResult};
-use crate::{calculate_cutoff, update_mask_from_buffers, validate_buffers, Resampler, Sample};
+use crate::{
+    calculate_cutoff, count_clipped, update_mask_from_buffers, validate_buffers, FilterType,
+    QualityInfo, Resampler, Sample,
+};
 use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
 
 /// A helper for resampling a single chunk of data.
@@ -23,6 +26,7 @@ struct FftResampler<T> {
     input_f: Vec<Complex<T>>,
     output_f: Vec<Complex<T>>,
     output_buf: Vec<T>,
+    spectrum: Option<Vec<f32>>,
 }
 
 /// A synchronous resampler that needs a fixed number of audio frames for input
@@ -39,7 +43,12 @@ pub struct FftFixedIn<T> {
     overlaps: Vec<Vec<T>>,
     input_buffers: Vec<Vec<T>>,
     channel_mask: Vec<bool>,
+    max_block_frames: Option<usize>,
+    clip_ceiling: Option<T>,
+    clipped_samples: usize,
+    flush_remaining: Option<usize>,
     saved_frames: usize,
+    spectra: Vec<Vec<f32>>,
     resampler: FftResampler<T>,
 }
 
@@ -57,8 +66,13 @@ pub struct FftFixedOut<T> {
     overlaps: Vec<Vec<T>>,
     output_buffers: Vec<Vec<T>>,
     channel_mask: Vec<bool>,
+    max_block_frames: Option<usize>,
+    clip_ceiling: Option<T>,
+    clipped_samples: usize,
+    flush_remaining: Option<usize>,
     saved_frames: usize,
     frames_needed: usize,
+    spectra: Vec<Vec<f32>>,
     resampler: FftResampler<T>,
 }
 
@@ -74,7 +88,12 @@ pub struct FftFixedInOut<T> {
     chunk_size_out: usize,
     fft_size_in: usize,
     channel_mask: Vec<bool>,
+    max_block_frames: Option<usize>,
+    clip_ceiling: Option<T>,
+    clipped_samples: usize,
+    flush_remaining: Option<usize>,
     overlaps: Vec<Vec<T>>,
+    spectra: Vec<Vec<f32>>,
     resampler: FftResampler<T>,
 }
 
@@ -133,9 +152,58 @@ where
             input_f,
             output_f,
             output_buf,
+            spectrum: None,
         }
     }
 
+    /// Enable or disable storing the magnitude spectrum of each filtered block.
+    fn set_spectrum_capture(&mut self, enable: bool) {
+        self.spectrum = if enable {
+            Some(vec![0.0; self.fft_size_out + 1])
+        } else {
+            None
+        };
+    }
+
+    /// Copy the magnitude spectrum of the last filtered block to `target`,
+    /// if capturing is enabled.
+    fn copy_spectrum(&self, target: &mut Vec<f32>) {
+        if let Some(spectrum) = &self.spectrum {
+            target.clear();
+            target.extend_from_slice(spectrum);
+        }
+    }
+
+    /// Describe the filter, see [Resampler::quality_info].
+    /// The filter is designed for the smaller one of the two FFT sizes.
+    fn quality_info(&self, latency: usize) -> QualityInfo {
+        let filter_len = self.fft_size_in.min(self.fft_size_out);
+        QualityInfo {
+            filter: FilterType::Fft {
+                fft_size_in: self.fft_size_in,
+                fft_size_out: self.fft_size_out,
+            },
+            attenuation_db: Some(approximate_attenuation(WindowFunction::BlackmanHarris2)),
+            transition_bandwidth: Some(
+                1.0 - calculate_cutoff::<f64>(filter_len, WindowFunction::BlackmanHarris2),
+            ),
+            latency,
+        }
+    }
+
+    /// Estimate the number of multiply-accumulate operations for processing
+    /// `nbr_units` chunks of `fft_size_in` frames in each of `nbr_channels` channels,
+    /// see [Resampler::estimated_mac_per_block].
+    /// The spectrum is multiplied by the filter using four operations per complex value.
+    fn estimated_mac(&self, nbr_units: usize, nbr_channels: usize) -> u64 {
+        let transform = |len: usize| len as f64 * (len as f64).log2();
+        let filter_len = self.fft_size_in.min(self.fft_size_out) + 1;
+        let per_unit = transform(2 * self.fft_size_in)
+            + transform(2 * self.fft_size_out)
+            + 4.0 * filter_len as f64;
+        (per_unit * (nbr_units * nbr_channels) as f64).ceil() as u64
+    }
+
     /// Resample a small chunk.
     fn resample_unit(&mut self, wave_in: &[T], wave_out: &mut [T], overlap: &mut [T]) {
         // Copy to input buffer and clear padding area.
@@ -172,6 +240,12 @@ where
         for val in self.output_f[new_len..].iter_mut() {
             *val = Complex::zero();
         }
+        if let Some(spectrum) = self.spectrum.as_mut() {
+            // The inverse FFT uses the spectrum as scratch, store the magnitudes before it.
+            for (magnitude, value) in spectrum.iter_mut().zip(self.output_f.iter()) {
+                *magnitude = (value.re * value.re + value.im * value.im).sqrt().to_f64() as f32;
+            }
+        }
         // IFFT result, store result and overlap.
         self.ifft
             .process_with_scratch(
@@ -229,10 +303,43 @@ where
             chunk_size_out: fft_size_out,
             fft_size_in,
             overlaps,
+            spectra: vec![Vec::new(); nbr_channels],
             resampler,
             channel_mask,
+            max_block_frames: None,
+            clip_ceiling: None,
+            clipped_samples: 0,
+            flush_remaining: None,
         })
     }
+
+    /// Enable or disable capturing the magnitude spectra computed while processing.
+    /// When enabled, [last_block_spectrum](FftFixedInOut::last_block_spectrum) returns the spectrum
+    /// of the last block of each channel.
+    /// Enabling allocates storage for the spectra, and should be done before processing starts.
+    /// Capturing is disabled by default.
+    pub fn set_spectrum_capture(&mut self, enable: bool) {
+        self.resampler.set_spectrum_capture(enable);
+        let len = if enable { self.fft_size_out() + 1 } else { 0 };
+        self.spectra = vec![vec![0.0; len]; self.nbr_channels];
+    }
+
+    /// Get the magnitude spectra of the filtered signal, one vector per channel,
+    /// from the last FFT of the most recent call to [process_into_buffer](Resampler::process_into_buffer).
+    /// The FFT is the inverse transform that produces the output, and the spectrum has
+    /// `fft_size_out + 1` bins, where bin `k` is at `k * fs_out / (2 * fft_size_out)`
+    /// for an output sample rate of `fs_out`.
+    /// The values are the linear magnitudes of the spectrum, before the unnormalized inverse FFT.
+    /// The vectors are empty unless capturing is enabled with
+    /// [set_spectrum_capture](FftFixedInOut::set_spectrum_capture).
+    pub fn last_block_spectrum(&self) -> Vec<Vec<f32>> {
+        self.spectra.clone()
+    }
+
+    /// Helper function. Get the size of the inverse FFT.
+    fn fft_size_out(&self) -> usize {
+        self.resampler.fft_size_out
+    }
 }
 
 impl<T> Resampler<T> for FftFixedInOut<T>
@@ -245,6 +352,7 @@ where
         wave_out: &mut [Vout],
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<(usize, usize)> {
+        self.flush_remaining = None;
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -258,6 +366,7 @@ where
             self.nbr_channels,
             self.chunk_size_in,
             self.chunk_size_out,
+            self.max_block_frames,
         )?;
 
         for (channel, active) in self.channel_mask.iter().enumerate() {
@@ -268,7 +377,14 @@ where
                     &mut self.overlaps[channel],
                 )
             }
+            self.resampler.copy_spectrum(&mut self.spectra[channel]);
         }
+        self.clipped_samples += count_clipped(
+            wave_out,
+            &self.channel_mask,
+            self.chunk_size_out,
+            self.clip_ceiling,
+        );
         Ok((self.chunk_size_in, self.chunk_size_out))
     }
 
@@ -308,7 +424,52 @@ where
         Err(ResampleError::SyncNotAdjustable)
     }
 
+    fn target_ratio(&self) -> f64 {
+        self.resampler.fft_size_out as f64 / self.resampler.fft_size_in as f64
+    }
+
+    fn is_rational(&self) -> Option<(usize, usize)> {
+        let gcd = integer::gcd(self.resampler.fft_size_in, self.resampler.fft_size_out);
+        Some((
+            self.resampler.fft_size_out / gcd,
+            self.resampler.fft_size_in / gcd,
+        ))
+    }
+
+    fn flush_remaining(&self) -> Option<usize> {
+        self.flush_remaining
+    }
+
+    fn set_flush_remaining(&mut self, remaining: Option<usize>) {
+        self.flush_remaining = remaining;
+    }
+
+    fn quality_info(&self) -> QualityInfo {
+        self.resampler.quality_info(self.output_delay())
+    }
+
+    fn estimated_mac_per_block(&self) -> u64 {
+        self.resampler.estimated_mac(
+            self.chunk_size_in / self.resampler.fft_size_in,
+            self.nbr_channels,
+        )
+    }
+
+    fn set_max_block_frames(&mut self, max_frames: Option<usize>) {
+        self.max_block_frames = max_frames;
+    }
+
+    fn set_clip_ceiling(&mut self, ceiling: Option<T>) {
+        self.clip_ceiling = ceiling;
+    }
+
+    fn clipped_sample_count(&self) -> usize {
+        self.clipped_samples
+    }
+
     fn reset(&mut self) {
+        self.clipped_samples = 0;
+        self.flush_remaining = None;
         self.overlaps
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
@@ -370,10 +531,43 @@ where
             output_buffers,
             saved_frames,
             frames_needed,
+            spectra: vec![Vec::new(); nbr_channels],
             resampler,
             channel_mask,
+            max_block_frames: None,
+            clip_ceiling: None,
+            clipped_samples: 0,
+            flush_remaining: None,
         })
     }
+
+    /// Enable or disable capturing the magnitude spectra computed while processing.
+    /// When enabled, [last_block_spectrum](FftFixedOut::last_block_spectrum) returns the spectrum
+    /// of the last block of each channel.
+    /// Enabling allocates storage for the spectra, and should be done before processing starts.
+    /// Capturing is disabled by default.
+    pub fn set_spectrum_capture(&mut self, enable: bool) {
+        self.resampler.set_spectrum_capture(enable);
+        let len = if enable { self.fft_size_out() + 1 } else { 0 };
+        self.spectra = vec![vec![0.0; len]; self.nbr_channels];
+    }
+
+    /// Get the magnitude spectra of the filtered signal, one vector per channel,
+    /// from the last FFT of the most recent call to [process_into_buffer](Resampler::process_into_buffer).
+    /// The FFT is the inverse transform that produces the output, and the spectrum has
+    /// `fft_size_out + 1` bins, where bin `k` is at `k * fs_out / (2 * fft_size_out)`
+    /// for an output sample rate of `fs_out`.
+    /// The values are the linear magnitudes of the spectrum, before the unnormalized inverse FFT.
+    /// The vectors are empty unless capturing is enabled with
+    /// [set_spectrum_capture](FftFixedOut::set_spectrum_capture).
+    pub fn last_block_spectrum(&self) -> Vec<Vec<f32>> {
+        self.spectra.clone()
+    }
+
+    /// Helper function. Get the size of the inverse FFT.
+    fn fft_size_out(&self) -> usize {
+        self.resampler.fft_size_out
+    }
 }
 
 impl<T> Resampler<T> for FftFixedOut<T>
@@ -386,6 +580,7 @@ where
         wave_out: &mut [Vout],
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<(usize, usize)> {
+        self.flush_remaining = None;
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -399,6 +594,7 @@ where
             self.nbr_channels,
             self.frames_needed,
             self.chunk_size_out,
+            self.max_block_frames,
         )?;
 
         for (chan, active) in self.channel_mask.iter().enumerate() {
@@ -413,6 +609,7 @@ where
                 {
                     self.resampler
                         .resample_unit(in_chunk, out_chunk, &mut self.overlaps[chan]);
+                    self.resampler.copy_spectrum(&mut self.spectra[chan]);
                 }
             }
         }
@@ -444,6 +641,12 @@ where
         let input_frames_used = self.frames_needed;
         let chunks_needed = (frames_needed_out as f32 / self.fft_size_out as f32).ceil() as usize;
         self.frames_needed = chunks_needed * self.fft_size_in;
+        self.clipped_samples += count_clipped(
+            wave_out,
+            &self.channel_mask,
+            self.chunk_size_out,
+            self.clip_ceiling,
+        );
         Ok((input_frames_used, self.chunk_size_out))
     }
 
@@ -483,7 +686,52 @@ where
         Err(ResampleError::SyncNotAdjustable)
     }
 
+    fn target_ratio(&self) -> f64 {
+        self.resampler.fft_size_out as f64 / self.resampler.fft_size_in as f64
+    }
+
+    fn is_rational(&self) -> Option<(usize, usize)> {
+        let gcd = integer::gcd(self.resampler.fft_size_in, self.resampler.fft_size_out);
+        Some((
+            self.resampler.fft_size_out / gcd,
+            self.resampler.fft_size_in / gcd,
+        ))
+    }
+
+    fn flush_remaining(&self) -> Option<usize> {
+        self.flush_remaining
+    }
+
+    fn set_flush_remaining(&mut self, remaining: Option<usize>) {
+        self.flush_remaining = remaining;
+    }
+
+    fn quality_info(&self) -> QualityInfo {
+        self.resampler.quality_info(self.output_delay())
+    }
+
+    fn estimated_mac_per_block(&self) -> u64 {
+        self.resampler.estimated_mac(
+            self.input_frames_max() / self.resampler.fft_size_in,
+            self.nbr_channels,
+        )
+    }
+
+    fn set_max_block_frames(&mut self, max_frames: Option<usize>) {
+        self.max_block_frames = max_frames;
+    }
+
+    fn set_clip_ceiling(&mut self, ceiling: Option<T>) {
+        self.clip_ceiling = ceiling;
+    }
+
+    fn clipped_sample_count(&self) -> usize {
+        self.clipped_samples
+    }
+
     fn reset(&mut self) {
+        self.clipped_samples = 0;
+        self.flush_remaining = None;
         self.overlaps
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
@@ -547,10 +795,43 @@ where
             overlaps,
             input_buffers,
             saved_frames,
+            spectra: vec![Vec::new(); nbr_channels],
             resampler,
             channel_mask,
+            max_block_frames: None,
+            clip_ceiling: None,
+            clipped_samples: 0,
+            flush_remaining: None,
         })
     }
+
+    /// Enable or disable capturing the magnitude spectra computed while processing.
+    /// When enabled, [last_block_spectrum](FftFixedIn::last_block_spectrum) returns the spectrum
+    /// of the last block of each channel.
+    /// Enabling allocates storage for the spectra, and should be done before processing starts.
+    /// Capturing is disabled by default.
+    pub fn set_spectrum_capture(&mut self, enable: bool) {
+        self.resampler.set_spectrum_capture(enable);
+        let len = if enable { self.fft_size_out() + 1 } else { 0 };
+        self.spectra = vec![vec![0.0; len]; self.nbr_channels];
+    }
+
+    /// Get the magnitude spectra of the filtered signal, one vector per channel,
+    /// from the last FFT of the most recent call to [process_into_buffer](Resampler::process_into_buffer).
+    /// The FFT is the inverse transform that produces the output, and the spectrum has
+    /// `fft_size_out + 1` bins, where bin `k` is at `k * fs_out / (2 * fft_size_out)`
+    /// for an output sample rate of `fs_out`.
+    /// The values are the linear magnitudes of the spectrum, before the unnormalized inverse FFT.
+    /// The vectors are empty unless capturing is enabled with
+    /// [set_spectrum_capture](FftFixedIn::set_spectrum_capture).
+    pub fn last_block_spectrum(&self) -> Vec<Vec<f32>> {
+        self.spectra.clone()
+    }
+
+    /// Helper function. Get the size of the inverse FFT.
+    fn fft_size_out(&self) -> usize {
+        self.resampler.fft_size_out
+    }
 }
 
 impl<T> Resampler<T> for FftFixedIn<T>
@@ -563,6 +844,7 @@ where
         wave_out: &mut [Vout],
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<(usize, usize)> {
+        self.flush_remaining = None;
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -581,6 +863,7 @@ where
             self.nbr_channels,
             self.chunk_size_in,
             needed_len,
+            self.max_block_frames,
         )?;
 
         // Copy new samples to input buffer.
@@ -609,6 +892,7 @@ where
                 {
                     self.resampler
                         .resample_unit(in_chunk, out_chunk, &mut self.overlaps[chan]);
+                    self.resampler.copy_spectrum(&mut self.spectra[chan]);
                 }
             }
         }
@@ -625,6 +909,8 @@ where
             }
         }
         self.saved_frames = extra;
+        self.clipped_samples +=
+            count_clipped(wave_out, &self.channel_mask, needed_len, self.clip_ceiling);
         Ok((self.chunk_size_in, needed_len))
     }
 
@@ -669,7 +955,63 @@ where
         Err(ResampleError::SyncNotAdjustable)
     }
 
+    fn target_ratio(&self) -> f64 {
+        self.resampler.fft_size_out as f64 / self.resampler.fft_size_in as f64
+    }
+
+    fn is_rational(&self) -> Option<(usize, usize)> {
+        let gcd = integer::gcd(self.resampler.fft_size_in, self.resampler.fft_size_out);
+        Some((
+            self.resampler.fft_size_out / gcd,
+            self.resampler.fft_size_in / gcd,
+        ))
+    }
+
+    fn flush_remaining(&self) -> Option<usize> {
+        self.flush_remaining
+    }
+
+    fn set_flush_remaining(&mut self, remaining: Option<usize>) {
+        self.flush_remaining = remaining;
+    }
+
+    fn output_schedule(&self, nblocks: usize) -> Vec<usize> {
+        let mut saved_frames = self.saved_frames;
+        (0..nblocks)
+            .map(|_| {
+                let subchunks = (saved_frames + self.chunk_size_in) / self.fft_size_in;
+                saved_frames = saved_frames + self.chunk_size_in - subchunks * self.fft_size_in;
+                subchunks * self.fft_size_out
+            })
+            .collect()
+    }
+
+    fn quality_info(&self) -> QualityInfo {
+        self.resampler.quality_info(self.output_delay())
+    }
+
+    fn estimated_mac_per_block(&self) -> u64 {
+        self.resampler.estimated_mac(
+            self.output_frames_max() / self.resampler.fft_size_out,
+            self.nbr_channels,
+        )
+    }
+
+    fn set_max_block_frames(&mut self, max_frames: Option<usize>) {
+        self.max_block_frames = max_frames;
+    }
+
+    fn set_clip_ceiling(&mut self, ceiling: Option<T>) {
+        self.clip_ceiling = ceiling;
+    }
+
+    fn clipped_sample_count(&self) -> usize {
+        self.clipped_samples
+    }
+
     fn reset(&mut self) {
+        self.clipped_samples = 0;
+        self.flush_remaining = None;
         self.overlaps
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
@@ -685,7 +1027,8 @@ where
 mod tests {
     use crate::check_output;
     use crate::synchro::{FftFixedIn, FftFixedInOut, FftFixedOut, FftResampler};
-    use crate::Resampler;
+    use crate::testing::signals::sine;
+    use crate::{FilterType, Resampler};
     use rand::Rng;
     use test_log::test;
 
@@ -710,6 +1053,57 @@ mod tests {
         assert!((maxval - 1.0).abs() < 0.1);
     }
 
+    #[test]
+    fn last_block_spectrum_fio() {
+        let mut resampler = FftFixedInOut::<f64>::new(44100, 48000, 1024, 2).unwrap();
+        assert_eq!(resampler.last_block_spectrum(), vec![Vec::<f32>::new(); 2]);
+        resampler.set_spectrum_capture(true);
+        let frames = resampler.input_frames_next();
+        let waves = sine::<f64>(4800.0, 44100.0, frames, 2);
+        resampler.process(&waves, None).unwrap();
+        let spectra = resampler.last_block_spectrum();
+        // The FFT sizes are 1029 -> 1120, and the bins are 48000 / 2240 Hz apart.
+        assert_eq!(spectra[0].len(), 1121);
+        let peak = spectra[0]
+            .iter()
+            .enumerate()
+            .fold((0, 0.0), |(idx, max), (n, value)| {
+                if *value > max {
+                    (n, *value)
+                } else {
+                    (idx, max)
+                }
+            });
+        assert_eq!(peak.0, 224);
+        assert_eq!(spectra[0], spectra[1]);
+    }
+
+    #[test]
+    fn exact_ratio_with_mixed_radix_sizes() {
+        // The FFT sizes are multiples of the reduced sample rates, and not rounded
+        // to powers of two, so that the ratio is exact.
+        for (rate_in, rate_out) in [
+            (44100, 48000),
+            (48000, 44100),
+            (44100, 96000),
+            (8000, 11025),
+        ] {
+            let resampler = FftFixedInOut::<f64>::new(rate_in, rate_out, 1024, 1).unwrap();
+            assert_eq!(
+                resampler.input_frames_next() * rate_out,
+                resampler.output_frames_next() * rate_in
+            );
+        }
+        let resampler = FftFixedInOut::<f64>::new(44100, 48000, 1024, 1).unwrap();
+        assert_eq!(
+            resampler.quality_info().filter,
+            FilterType::Fft {
+                fft_size_in: 1029,
+                fft_size_out: 1120
+            }
+        );
+    }
+
     #[test]
     fn make_resampler_fio() {
         // asking for 1024 give the nearest which is 1029 -> 1120
@@ -1038,4 +1432,40 @@ mod tests {
             assert_eq!(resampler.input_frames_max(), expected_max_in_len);
         }
     }
+
+    #[test]
+    fn is_rational() {
+        let resampler = FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap();
+        assert_eq!(resampler.is_rational(), Some((160, 147)));
+        let resampler = FftFixedOut::<f64>::new(96000, 32000, 1024, 2, 2).unwrap();
+        assert_eq!(resampler.is_rational(), Some((1, 3)));
+        let resampler = FftFixedInOut::<f64>::new(44100, 44100, 1024, 2).unwrap();
+        assert_eq!(resampler.is_rational(), Some((1, 1)));
+    }
+
+    #[test]
+    fn output_schedule() {
+        let mut resampler = FftFixedIn::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        let schedule = resampler.output_schedule(10);
+        let waves = vec![vec![0.0f64; 1000]; 2];
+        let mut wave_out = resampler.output_buffer_allocate(true);
+        let mut total_output_frames = 0;
+        for expected in schedule.iter() {
+            let (_, frames_out) = resampler
+                .process_into_buffer(&waves, &mut wave_out, None)
+                .unwrap();
+            assert_eq!(frames_out, *expected);
+            total_output_frames += frames_out;
+        }
+        assert_eq!(schedule.iter().sum::<usize>(), total_output_frames);
+
+        let resampler = FftFixedOut::<f64>::new(44100, 48000, 1024, 2, 2).unwrap();
+        assert_eq!(resampler.output_schedule(3), vec![1024; 3]);
+    }
+
+    #[test]
+    fn achieved_output_rate() {
+        let resampler = FftFixedOut::<f64>::new(96000, 32000, 1024, 2, 2).unwrap();
+        assert_eq!(resampler.achieved_output_rate(96000.0), 32000.0);
+    }
 }
@@ -0,0 +1,3 @@
+//! Helpers for testing resamplers.
+
+pub mod signals;
@@ -0,0 +1,134 @@
+//! Generators for test signals.
+//!
+//! All generators return one vector per channel, with the same signal in all channels.
+
+use crate::Sample;
+
+/// Helper function. Copy a single channel into the given number of channels.
+fn to_channels<T: Sample>(wave: Vec<T>, channels: usize) -> Vec<Vec<T>> {
+    vec![wave; channels]
+}
+
+/// Generate a sine wave with amplitude 1.0 and the given frequency in Hz.
+pub fn sine<T>(frequency: f64, sample_rate: f64, frames: usize, channels: usize) -> Vec<Vec<T>>
+where
+    T: Sample,
+{
+    let omega = 2.0 * std::f64::consts::PI * frequency / sample_rate;
+    let wave = (0..frames)
+        .map(|n| T::coerce((omega * n as f64).sin()))
+        .collect();
+    to_channels(wave, channels)
+}
+
+/// Generate a sine sweep with amplitude 1.0, where the frequency increases linearly
+/// from `f_start` to `f_end` Hz over the length of the signal.
+pub fn linear_sweep<T>(
+    f_start: f64,
+    f_end: f64,
+    sample_rate: f64,
+    frames: usize,
+    channels: usize,
+) -> Vec<Vec<T>>
+where
+    T: Sample,
+{
+    let duration = frames as f64 / sample_rate;
+    let rate = (f_end - f_start) / duration;
+    let wave = (0..frames)
+        .map(|n| {
+            let time = n as f64 / sample_rate;
+            let phase = 2.0 * std::f64::consts::PI * (f_start * time + 0.5 * rate * time * time);
+            T::coerce(phase.sin())
+        })
+        .collect();
+    to_channels(wave, channels)
+}
+
+/// Generate an impulse, with a single sample of value 1.0 at the given position
+/// and zeros everywhere else.
+/// If the position is outside the signal, all samples are zero.
+pub fn impulse<T>(position: usize, frames: usize, channels: usize) -> Vec<Vec<T>>
+where
+    T: Sample,
+{
+    let mut wave = vec![T::zero(); frames];
+    if let Some(value) = wave.get_mut(position) {
+        *value = T::one();
+    }
+    to_channels(wave, channels)
+}
+
+/// Generate white noise, uniformly distributed between -1.0 and 1.0.
+/// The noise is generated with a simple xorshift generator,
+/// and the same seed always gives the same signal.
+pub fn white_noise<T>(seed: u64, frames: usize, channels: usize) -> Vec<Vec<T>>
+where
+    T: Sample,
+{
+    // The xorshift state must not be zero.
+    let mut state = seed.max(1);
+    let wave = (0..frames)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let uniform = (state >> 11) as f64 / (1u64 << 53) as f64;
+            T::coerce(2.0 * uniform - 1.0)
+        })
+        .collect();
+    to_channels(wave, channels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{impulse, linear_sweep, sine, white_noise};
+    use test_log::test;
+
+    #[test]
+    fn sine_frequency() {
+        let wave = sine::<f64>(1000.0, 48000.0, 48000, 2);
+        assert_eq!(wave.len(), 2);
+        assert_eq!(wave[0], wave[1]);
+        let crossings = wave[0]
+            .windows(2)
+            .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+            .count();
+        assert!((999..=1000).contains(&crossings), "{}", crossings);
+    }
+
+    #[test]
+    fn sweep_frequency_increases() {
+        let wave = linear_sweep::<f64>(100.0, 1000.0, 48000.0, 48000, 1);
+        let count = |part: &[f64]| {
+            part.windows(2)
+                .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+                .count()
+        };
+        // The average frequency is 145 Hz in the first tenth, and 955 Hz in the last.
+        assert!((14..=15).contains(&count(&wave[0][..4800])));
+        assert!((95..=96).contains(&count(&wave[0][43200..])));
+    }
+
+    #[test]
+    fn single_impulse() {
+        let wave = impulse::<f32>(10, 100, 2);
+        for chan in wave.iter() {
+            assert_eq!(chan.len(), 100);
+            assert_eq!(chan.iter().filter(|value| **value != 0.0).count(), 1);
+            assert_eq!(chan[10], 1.0);
+        }
+    }
+
+    #[test]
+    fn noise_is_repeatable() {
+        let first = white_noise::<f64>(42, 1000, 1);
+        let second = white_noise::<f64>(42, 1000, 1);
+        let other = white_noise::<f64>(43, 1000, 1);
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert!(first[0].iter().all(|value| value.abs() <= 1.0));
+        let mean = first[0].iter().sum::<f64>() / 1000.0;
+        assert!(mean.abs() < 0.1);
+    }
+}
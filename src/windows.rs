@@ -1,4 +1,10 @@
+use crate::error::WindowError;
 use crate::Sample;
+#[cfg(feature = "fft_resampler")]
+use num_complex::Complex;
+#[cfg(feature = "fft_resampler")]
+use realfft::RealFftPlanner;
+use std::io::{BufRead, BufReader, Read};
 
 /// Different window functions that can be used to window the sinc function.
 #[derive(Debug, Clone, Copy)]
@@ -15,6 +21,60 @@ pub enum WindowFunction {
     Hann,
     /// Squared Hann. Slower rolloff and higher attenuation than simple Hann.
     Hann2,
+    /// Planck-taper. Flat in the middle, with tapered edges that are smooth in all derivatives.
+    /// The `epsilon` parameter gives the fraction of the window used for each taper,
+    /// and should be between 0.0 (rectangular) and 0.5 (no flat part).
+    PlanckTaper { epsilon: f64 },
+    /// Hann-Poisson. A Hann window multiplied by an exponential taper.
+    /// The inner value is the exponential parameter `alpha`.
+    /// For `alpha >= 2` the frequency response has no side lobes,
+    /// and it decreases monotonically outside the main lobe.
+    HannPoisson(f64),
+    /// The product of two window functions, see [window_product].
+    /// Combining two windows gives a wider main lobe than either of them alone,
+    /// and this can be used to make custom trade-offs between rolloff and attenuation.
+    /// The windows are given as static references to keep this type [Copy], for example
+    /// `WindowFunction::Product(&WindowFunction::BlackmanHarris, &WindowFunction::HannPoisson(2.0))`.
+    Product(&'static WindowFunction, &'static WindowFunction),
+    /// A general cosine-sum window, `sum(a[k] * cos(2 * pi * k * n / N))` for `n` in `0..N`.
+    /// The Hann, Blackman and Blackman-Harris windows are special cases,
+    /// for example Blackman is `WindowFunction::CosineSum(&[0.42, -0.5, 0.08])`.
+    /// The window is not normalized, and the coefficients should alternate in sign
+    /// and have absolute values that sum to one, to give a peak value of one in the middle.
+    /// The coefficients are given as a static slice to keep this type [Copy].
+    CosineSum(&'static [f64]),
+}
+
+/// All available window functions, see [all_window_functions].
+const ALL_WINDOW_FUNCTIONS: [WindowFunction; 10] = [
+    WindowFunction::Blackman,
+    WindowFunction::Blackman2,
+    WindowFunction::BlackmanHarris,
+    WindowFunction::BlackmanHarris2,
+    WindowFunction::Hann,
+    WindowFunction::Hann2,
+    WindowFunction::PlanckTaper { epsilon: 0.1 },
+    WindowFunction::HannPoisson(2.0),
+    WindowFunction::Product(
+        &WindowFunction::BlackmanHarris,
+        &WindowFunction::HannPoisson(2.0),
+    ),
+    WindowFunction::CosineSum(&NUTTALL_COEFFICIENTS),
+];
+
+/// The coefficients of the Nuttall window, used as the example of [WindowFunction::CosineSum]
+/// in [all_window_functions].
+const NUTTALL_COEFFICIENTS: [f64; 4] = [0.355768, -0.487396, 0.144232, -0.012604];
+
+/// Get a list of all available window functions, for example for iterating over them
+/// in tests, benchmarks or user interfaces.
+/// The variants that take a parameter are included once, with a default value.
+/// These are `epsilon = 0.1` for [WindowFunction::PlanckTaper],
+/// and `alpha = 2.0` for [WindowFunction::HannPoisson].
+/// [WindowFunction::Product] is included as the product of Blackman-Harris and Hann-Poisson,
+/// and [WindowFunction::CosineSum] with the coefficients of the Nuttall window.
+pub const fn all_window_functions() -> &'static [WindowFunction] {
+    &ALL_WINDOW_FUNCTIONS
 }
 
 /// Helper function. Standard Blackman-Harris window.
@@ -24,6 +84,12 @@ where
     T: Sample,
 {
     trace!("Making a BlackmanHarris windows with {} points", npoints);
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if let Some(window) =
+        crate::windows_avx::cosine_series(npoints, &[0.35875, -0.48829, 0.14128, -0.01168])
+    {
+        return window.into_iter().map(T::coerce).collect();
+    }
     let mut window = vec![T::zero(); npoints];
     let pi2 = T::coerce(2.0) * T::PI;
     let pi4 = T::coerce(4.0) * T::PI;
@@ -48,6 +114,10 @@ where
     T: Sample,
 {
     trace!("Making a Blackman windows with {} points", npoints);
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if let Some(window) = crate::windows_avx::cosine_series(npoints, &[0.42, -0.5, 0.08]) {
+        return window.into_iter().map(T::coerce).collect();
+    }
     let mut window = vec![T::zero(); npoints];
     let pi2 = T::coerce(2.0) * T::PI;
     let pi4 = T::coerce(4.0) * T::PI;
@@ -69,6 +139,10 @@ where
     T: Sample,
 {
     trace!("Making a Hann windows with {} points", npoints);
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if let Some(window) = crate::windows_avx::cosine_series(npoints, &[0.5, -0.5]) {
+        return window.into_iter().map(T::coerce).collect();
+    }
     let mut window = vec![T::zero(); npoints];
     let pi2 = T::coerce(2.0) * T::PI;
     let np_f = T::coerce(npoints);
@@ -80,8 +154,82 @@ where
     window
 }
 
+/// Helper function. General cosine-sum window.
+// The window created is periodic.
+pub fn cosine_sum<T>(npoints: usize, coefficients: &[f64]) -> Vec<T>
+where
+    T: Sample,
+{
+    trace!(
+        "Making a CosineSum windows with {} points and {} coefficients",
+        npoints,
+        coefficients.len()
+    );
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if !coefficients.is_empty() {
+        if let Some(window) = crate::windows_avx::cosine_series(npoints, coefficients) {
+            return window.into_iter().map(T::coerce).collect();
+        }
+    }
+    let mut window = vec![T::zero(); npoints];
+    let pi2 = T::coerce(2.0) * T::PI;
+    let np_f = T::coerce(npoints);
+    for (x, item) in window.iter_mut().enumerate() {
+        let phase = pi2 * T::coerce(x) / np_f;
+        *item = coefficients
+            .iter()
+            .enumerate()
+            .fold(T::zero(), |acc, (k, coeff)| {
+                acc + T::coerce(*coeff) * (T::coerce(k) * phase).cos()
+            });
+    }
+    window
+}
+
+/// Helper function. Planck-taper window.
+// The window created is periodic.
+pub fn planck_taper<T>(npoints: usize, epsilon: T) -> Vec<T>
+where
+    T: Sample,
+{
+    trace!("Making a PlanckTaper windows with {} points", npoints);
+    let mut window = vec![T::one(); npoints];
+    let np_f = T::coerce(npoints);
+    let taper_len = epsilon * np_f;
+    for (x, item) in window.iter_mut().enumerate() {
+        let x_float = T::coerce(x);
+        // Distance to the nearest end, where the periodic window reaches zero.
+        let dist = x_float.min(np_f - x_float);
+        if dist == T::zero() {
+            *item = T::zero();
+        } else if dist.to_f64() < taper_len.to_f64() {
+            let z = taper_len * (T::one() / dist + T::one() / (dist - taper_len));
+            *item = T::one() / (T::one() + z.exp());
+        }
+    }
+    window
+}
+
+/// Helper function. Hann-Poisson window.
+// The window created is periodic.
+pub fn hann_poisson<T>(npoints: usize, alpha: f64) -> Vec<T>
+where
+    T: Sample,
+{
+    trace!("Making a HannPoisson windows with {} points", npoints);
+    let mut window = hann::<T>(npoints);
+    let np_f = T::coerce(npoints);
+    let alpha = T::coerce(alpha);
+    for (x, item) in window.iter_mut().enumerate() {
+        let x_float = T::coerce(x);
+        let dist = (np_f - T::coerce(2.0) * x_float).abs();
+        *item *= (-alpha * dist / np_f).exp();
+    }
+    window
+}
+
 /// Make the selected window function.
-pub fn make_window<T>(npoints: usize, windowfunc: WindowFunction) -> Vec<T>
+pub(crate) fn make_window<T>(npoints: usize, windowfunc: WindowFunction) -> Vec<T>
 where
     T: Sample,
 {
@@ -91,71 +239,498 @@ where
         }
         WindowFunction::Blackman | WindowFunction::Blackman2 => blackman::<T>(npoints),
         WindowFunction::Hann | WindowFunction::Hann2 => hann::<T>(npoints),
+        WindowFunction::PlanckTaper { epsilon } => planck_taper::<T>(npoints, T::coerce(epsilon)),
+        WindowFunction::HannPoisson(alpha) => hann_poisson::<T>(npoints, alpha),
+        WindowFunction::Product(first, second) => window_product(
+            &make_window::<T>(npoints, *first),
+            &make_window::<T>(npoints, *second),
+            false,
+        )
+        .expect("The windows have the same length"),
+        WindowFunction::CosineSum(coefficients) => cosine_sum::<T>(npoints, coefficients),
     };
     match windowfunc {
         WindowFunction::Blackman2 | WindowFunction::BlackmanHarris2 | WindowFunction::Hann2 => {
-            window.iter_mut().for_each(|y| *y = *y * *y);
+            window.iter_mut().for_each(|y| *y = y.powi(2));
         }
         _ => {}
     };
     window
 }
 
+/// Make the selected window function, for the window benchmarks.
+/// This is not part of the public API.
+#[doc(hidden)]
+pub fn bench_make_window<T>(npoints: usize, windowfunc: WindowFunction) -> Vec<T>
+where
+    T: Sample,
+{
+    make_window(npoints, windowfunc)
+}
+
+/// Helper function. Evaluate the selected window function at point `x` of a window
+/// with `npoints` points, without allocating.
+/// This gives the same values as [make_window], up to rounding.
+pub(crate) fn window_value(npoints: usize, x: usize, windowfunc: WindowFunction) -> f64 {
+    let np_f = npoints as f64;
+    let x_float = x as f64;
+    let phase = 2.0 * std::f64::consts::PI * x_float / np_f;
+    let cosine_sum = |coefficients: &[f64]| {
+        coefficients
+            .iter()
+            .enumerate()
+            .fold(0.0, |acc, (k, coeff)| {
+                acc + coeff * (k as f64 * phase).cos()
+            })
+    };
+    let value = match windowfunc {
+        WindowFunction::BlackmanHarris | WindowFunction::BlackmanHarris2 => {
+            cosine_sum(&[0.35875, -0.48829, 0.14128, -0.01168])
+        }
+        WindowFunction::Blackman | WindowFunction::Blackman2 => cosine_sum(&[0.42, -0.5, 0.08]),
+        WindowFunction::Hann | WindowFunction::Hann2 => cosine_sum(&[0.5, -0.5]),
+        WindowFunction::PlanckTaper { epsilon } => {
+            let taper_len = epsilon * np_f;
+            let dist = x_float.min(np_f - x_float);
+            if dist == 0.0 {
+                0.0
+            } else if dist < taper_len {
+                let z = taper_len * (1.0 / dist + 1.0 / (dist - taper_len));
+                1.0 / (1.0 + z.exp())
+            } else {
+                1.0
+            }
+        }
+        WindowFunction::HannPoisson(alpha) => {
+            cosine_sum(&[0.5, -0.5]) * (-alpha * (np_f - 2.0 * x_float).abs() / np_f).exp()
+        }
+        WindowFunction::Product(first, second) => {
+            window_value(npoints, x, *first) * window_value(npoints, x, *second)
+        }
+        WindowFunction::CosineSum(coefficients) => cosine_sum(coefficients),
+    };
+    match windowfunc {
+        WindowFunction::Blackman2 | WindowFunction::BlackmanHarris2 | WindowFunction::Hann2 => {
+            value * value
+        }
+        _ => value,
+    }
+}
+
+/// Multiply two windows of the same length element-wise, to form a new window.
+/// When `normalize` is true, the result is scaled to a peak value of 1.0.
+/// Returns [WindowError::LengthMismatch] if the lengths differ.
+pub fn window_product<T>(first: &[T], second: &[T], normalize: bool) -> Result<Vec<T>, WindowError>
+where
+    T: Sample,
+{
+    if first.len() != second.len() {
+        return Err(WindowError::LengthMismatch {
+            first: first.len(),
+            second: second.len(),
+        });
+    }
+    let mut window = first
+        .iter()
+        .zip(second.iter())
+        .map(|(a, b)| *a * *b)
+        .collect::<Vec<T>>();
+    if normalize {
+        let peak = window
+            .iter()
+            .fold(T::zero(), |peak, value| peak.max(value.abs()));
+        if peak != T::zero() {
+            window.iter_mut().for_each(|value| *value /= peak);
+        }
+    }
+    Ok(window)
+}
+
+/// Read window coefficients from a text file with one value per line,
+/// for example as exported from MATLAB or scipy.
+/// Empty lines are skipped, and a trailing comma after the value is allowed.
+/// The window can then be used with
+/// [ScalarInterpolator::new_with_window](crate::sinc_interpolator::ScalarInterpolator::new_with_window).
+/// Returns [WindowError::InvalidCoefficient] for a line that isn't a number,
+/// and [WindowError::ReadFailed] if reading fails.
+pub fn window_from_csv<T>(reader: impl Read) -> Result<Vec<T>, WindowError>
+where
+    T: Sample,
+{
+    let mut window = Vec::new();
+    for (idx, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.map_err(|err| WindowError::ReadFailed(err.to_string()))?;
+        let text = line.trim().trim_end_matches(',').trim_end();
+        if text.is_empty() {
+            continue;
+        }
+        let value = text
+            .parse::<f64>()
+            .map_err(|_| WindowError::InvalidCoefficient {
+                line: idx + 1,
+                text: text.to_string(),
+            })?;
+        window.push(T::coerce(value));
+    }
+    Ok(window)
+}
+
+/// Calculate the circular autocorrelation of a window,
+/// where the value at lag `k` is the sum of `window[n] * window[(n + k) % len]` over all `n`.
+/// The autocorrelation determines the structure of the sidebands in STFT processing.
+///
+/// The autocorrelation is calculated with FFT, as the inverse transform
+/// of the squared magnitude of the spectrum of the window.
+#[cfg(feature = "fft_resampler")]
+pub fn window_autocorrelation<T>(window: &[T]) -> Vec<T>
+where
+    T: Sample,
+{
+    let npoints = window.len();
+    if npoints == 0 {
+        return Vec::new();
+    }
+    let mut planner = RealFftPlanner::<T>::new();
+    let fft = planner.plan_fft_forward(npoints);
+    let ifft = planner.plan_fft_inverse(npoints);
+    let mut input = window.to_vec();
+    let mut spectrum = fft.make_output_vec();
+    fft.process(&mut input, &mut spectrum)
+        .expect("The buffers have the lengths given by the FFT");
+    for value in spectrum.iter_mut() {
+        *value = Complex::new(value.norm_sqr(), T::zero());
+    }
+    let mut output = ifft.make_output_vec();
+    ifft.process(&mut spectrum, &mut output)
+        .expect("The buffers have the lengths given by the FFT");
+    // The inverse transform is not normalized.
+    let scale = T::one() / T::coerce(npoints);
+    output.iter_mut().for_each(|value| *value *= scale);
+    output
+}
+
+/// Calculate the frequency response of a window, for evaluating a built-in or custom window.
+/// The response is evaluated at `nbins` frequencies evenly spaced from zero up to and including
+/// the Nyquist frequency. Each point is a tuple of the normalized frequency, in cycles per sample
+/// from 0.0 to 0.5, and the magnitude in dB relative to the response at zero frequency.
+///
+/// The response is evaluated directly from the definition of the discrete-time Fourier transform,
+/// which takes a time proportional to `window.len() * nbins`.
+pub fn window_frequency_response<T>(window: &[T], nbins: usize) -> Vec<(T, T)>
+where
+    T: Sample,
+{
+    let dc_gain = window
+        .iter()
+        .fold(0.0, |acc, value| acc + value.to_f64())
+        .abs();
+    (0..nbins)
+        .map(|bin| {
+            let freq = if nbins > 1 {
+                0.5 * bin as f64 / (nbins - 1) as f64
+            } else {
+                0.0
+            };
+            let (re, im) = window
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (n, value)| {
+                    let angle = 2.0 * std::f64::consts::PI * freq * n as f64;
+                    let value = value.to_f64();
+                    (re + value * angle.cos(), im - value * angle.sin())
+                });
+            let magnitude = (re * re + im * im).sqrt() / dc_gain;
+            (T::coerce(freq), T::coerce(20.0 * magnitude.log10()))
+        })
+        .collect()
+}
+
+/// Calculate the factor for scaling a window, so that copies of the window
+/// overlapped with the given hop size sum to unity.
+/// This is needed when using the window for overlap-add reconstruction, for example in STFT processing.
+///
+/// The overlap-add gain is evaluated numerically, by summing the overlapping copies
+/// of the window at each position within one hop and taking the average.
+/// For windows that do not sum to a constant at this hop size,
+/// the result gives unity gain on average.
+/// This includes hop sizes larger than half the window,
+/// where fewer than two copies overlap at some positions.
+///
+/// Panics if `hop_size` is zero or larger than `npoints`.
+pub fn window_overlap_add_scale<T>(window: WindowFunction, npoints: usize, hop_size: usize) -> T
+where
+    T: Sample,
+{
+    assert!(
+        hop_size > 0 && hop_size <= npoints,
+        "The hop size {} must be larger than zero and at most the window length {}",
+        hop_size,
+        npoints
+    );
+    let values = make_window::<T>(npoints, window);
+    let mut total = T::zero();
+    for offset in 0..hop_size {
+        for value in values.iter().skip(offset).step_by(hop_size) {
+            total += *value;
+        }
+    }
+    T::coerce(hop_size) / total
+}
+
+/// Step size in bins for the numerical sweeps of the spectrum of a cosine-sum window.
+const COSINE_SUM_SWEEP_STEP: f64 = 0.01;
+
+/// Number of bins after the main lobe that are searched for the highest side lobe
+/// of a cosine-sum window.
+const COSINE_SUM_SIDE_LOBE_BINS: f64 = 40.0;
+
+/// Helper function. Evaluate the spectrum of a long cosine-sum window at a frequency in bins.
+/// Centered around its middle, the window is a sum of cosines with alternating signs.
+/// Each cosine gives two sinc functions shifted by `k` bins, and the spectrum is real.
+fn cosine_sum_spectrum(coefficients: &[f64], bins: f64) -> f64 {
+    let sinc = |x: f64| {
+        if x == 0.0 {
+            1.0
+        } else {
+            (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x)
+        }
+    };
+    coefficients
+        .iter()
+        .enumerate()
+        .map(|(k, coeff)| {
+            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+            if k == 0 {
+                coeff * sinc(bins)
+            } else {
+                0.5 * sign * coeff * (sinc(bins - k as f64) + sinc(bins + k as f64))
+            }
+        })
+        .sum()
+}
+
+/// Helper function. Find half the width of the main lobe of a cosine-sum window in bins,
+/// by sweeping the spectrum from DC to the first minimum of the magnitude.
+fn cosine_sum_main_lobe(coefficients: &[f64]) -> f64 {
+    let limit = 2.0 * coefficients.len() as f64 + COSINE_SUM_SIDE_LOBE_BINS;
+    let mut bins = 0.0;
+    let mut previous = cosine_sum_spectrum(coefficients, bins).abs();
+    while bins < limit {
+        let value = cosine_sum_spectrum(coefficients, bins + COSINE_SUM_SWEEP_STEP).abs();
+        if value > previous {
+            break;
+        }
+        previous = value;
+        bins += COSINE_SUM_SWEEP_STEP;
+    }
+    bins
+}
+
+/// Helper function. Find the level of the highest side lobe of a cosine-sum window
+/// in dB relative to the main lobe, as a positive number, by sweeping the spectrum.
+fn cosine_sum_side_lobe_level(coefficients: &[f64]) -> f64 {
+    let main_lobe = cosine_sum_main_lobe(coefficients);
+    let peak = cosine_sum_spectrum(coefficients, 0.0).abs();
+    let steps = (COSINE_SUM_SIDE_LOBE_BINS / COSINE_SUM_SWEEP_STEP) as usize;
+    let side_lobe = (0..steps)
+        .map(|n| {
+            cosine_sum_spectrum(coefficients, main_lobe + n as f64 * COSINE_SUM_SWEEP_STEP).abs()
+        })
+        .fold(0.0, f64::max);
+    -20.0 * (side_lobe / peak).log10()
+}
+
+/// Helper function. Get the coefficients `(k1, k2, k3)` of the cutoff approximation
+/// used by [calculate_cutoff].
+fn cutoff_coefficients(windowfunc: WindowFunction) -> (f64, f64, f64) {
+    // Coefficient values generated by utils/cutoff_fit_cubic.py,
+    // and checked against the fitting criterion by the verify_coefficients test.
+    match windowfunc {
+        WindowFunction::BlackmanHarris => (8.041443677716476, 55.9506779343387, 898.0287985384213),
+        WindowFunction::BlackmanHarris2 => {
+            (13.745202940783823, 121.73532586374934, 5964.163279612051)
+        }
+        WindowFunction::Blackman => (6.159598046201173, 18.926415097606878, 653.4247430458968),
+        WindowFunction::Blackman2 => (9.506235102129398, 79.13120634953742, 1502.2316160588925),
+        WindowFunction::Hann => (3.3481080887677166, 10.106519434875038, 78.96345249024414),
+        WindowFunction::Hann2 => (5.38751148378734, 29.69451915489501, 184.82117462266237),
+        WindowFunction::PlanckTaper { epsilon } => {
+            let scale = 2.0 * epsilon.clamp(0.0, 0.5);
+            (
+                3.3481080887677166 * scale,
+                10.106519434875038 * scale,
+                78.96345249024414 * scale,
+            )
+        }
+        // This window has no side lobe minima, instead the fit places
+        // the -20 dB point at the end of the transition band at the Nyquist frequency.
+        WindowFunction::HannPoisson(_) => {
+            (4.357507508083769, 14.427755003121966, 59.30013820021294)
+        }
+        // The spectrum of the product is the convolution of the two spectra,
+        // and the width of the main lobe is approximately the sum of the two widths.
+        WindowFunction::Product(first, second) => {
+            let (a1, a2, a3) = cutoff_coefficients(*first);
+            let (b1, b2, b3) = cutoff_coefficients(*second);
+            (a1 + b1, a2 + b2, a3 + b3)
+        }
+        // The first minimum of the response is placed at the Nyquist frequency
+        // when the transition band is as wide as the main lobe of the window.
+        // This gives the first order term only.
+        WindowFunction::CosineSum(coefficients) => {
+            (2.0 * cosine_sum_main_lobe(coefficients), 0.0, 0.0)
+        }
+    }
+}
+
+/// Helper to get the approximate side lobe level of a window function in dB, as a positive number.
+/// Squaring a window roughly doubles the level, and for a product of two windows
+/// the levels are added in the same way.
+pub(crate) fn approximate_attenuation(windowfunc: WindowFunction) -> f64 {
+    match windowfunc {
+        WindowFunction::Blackman => 58.1,
+        WindowFunction::Blackman2 => 116.2,
+        WindowFunction::BlackmanHarris => 92.0,
+        WindowFunction::BlackmanHarris2 => 184.0,
+        WindowFunction::Hann => 31.5,
+        WindowFunction::Hann2 => 63.0,
+        // Between rectangular for epsilon = 0 and Hann for epsilon = 0.5.
+        WindowFunction::PlanckTaper { epsilon } => 13.3 + 36.4 * epsilon.clamp(0.0, 0.5),
+        // The Hann-Poisson window has no side lobes for alpha >= 2,
+        // and the Hann level is used as a conservative estimate.
+        WindowFunction::HannPoisson(_) => 31.5,
+        WindowFunction::Product(first, second) => {
+            approximate_attenuation(*first) + approximate_attenuation(*second)
+        }
+        WindowFunction::CosineSum(coefficients) => cosine_sum_side_lobe_level(coefficients),
+    }
+}
+
 /// Calculate a suitable relative cutoff frequency for the given sinc length using the given window function.
 /// The result is based on an approximation, which gives good results for sinc lengths from 32 to 2048.
+/// Shorter sinc lengths are outside the range used when fitting the approximation,
+/// and a warning is logged if one is given.
+///
+/// For [WindowFunction::PlanckTaper] there is no fitted approximation.
+/// Instead, the coefficients for Hann are scaled by `2 * epsilon`,
+/// going from the rectangular window at `epsilon = 0` to a Hann-like window at `epsilon = 0.5`.
+///
+/// For [WindowFunction::HannPoisson], the approximation is fitted for `alpha = 2`
+/// and is used for all values of `alpha`.
+///
+/// For [WindowFunction::Product], the coefficients of the two windows are added,
+/// since the main lobe of the product is approximately as wide as the two main lobes together.
+///
+/// For [WindowFunction::CosineSum], the width of the main lobe is found by a numerical sweep
+/// of the spectrum of the window, and the transition band is made equally wide.
+/// This is close to the fitted approximations for sinc lengths of 128 and longer,
+/// but does not include the corrections needed for short sincs.
 pub fn calculate_cutoff<T>(npoints: usize, windowfunc: WindowFunction) -> T
 where
     T: Sample,
 {
-    // Coefficient values generated by cutoff_fit_cubic.py
-    let (k1, k2, k3) = match windowfunc {
-        WindowFunction::BlackmanHarris => (
-            T::coerce(8.041443677716476),
-            T::coerce(55.9506779343387),
-            T::coerce(898.0287985384213),
-        ),
-        WindowFunction::BlackmanHarris2 => (
-            T::coerce(13.745202940783823),
-            T::coerce(121.73532586374934),
-            T::coerce(5964.163279612051),
-        ),
-        WindowFunction::Blackman => (
-            T::coerce(6.159598046201173),
-            T::coerce(18.926415097606878),
-            T::coerce(653.4247430458968),
-        ),
-        WindowFunction::Blackman2 => (
-            T::coerce(9.506235102129398),
-            T::coerce(79.13120634953742),
-            T::coerce(1502.2316160588925),
-        ),
-        WindowFunction::Hann => (
-            T::coerce(3.3481080887677166),
-            T::coerce(10.106519434875038),
-            T::coerce(78.96345249024414),
-        ),
-        WindowFunction::Hann2 => (
-            T::coerce(5.38751148378734),
-            T::coerce(29.69451915489501),
-            T::coerce(184.82117462266237),
-        ),
-    };
+    let (k1, k2, k3) = cutoff_coefficients(windowfunc);
+    let (k1, k2, k3) = (T::coerce(k1), T::coerce(k2), T::coerce(k3));
     let one = T::one();
     let npoints_t = T::coerce(npoints);
-    one / (k1 / npoints_t
-        + k2 / (npoints_t * npoints_t)
-        + k3 / (npoints_t * npoints_t * npoints_t)
-        + one)
+    let cutoff = one / (k1 / npoints_t + k2 / npoints_t.powi(2) + k3 / npoints_t.powi(3) + one);
+    if npoints < 32 {
+        warn!(
+            "Sinc length {} is shorter than the recommended minimum of 32. The cutoff approximation is only fitted for lengths 32 to 2048, and the estimated additional error in the position of the stopband edge is {} of the Nyquist frequency, which may lead to increased aliasing.",
+            npoints,
+            stopband_edge(npoints, windowfunc, cutoff.to_f64())
+                .map(|edge| format!("{:+.1}%", 100.0 * (edge - 1.0)))
+                .unwrap_or_else(|| "more than +50%".to_string())
+        );
+    }
+    cutoff
+}
+
+/// Helper function. Calculate the magnitude of the response of a kernel
+/// that is oversampled by `factor`, at the frequency `freq` relative to the Nyquist frequency
+/// of the original sample rate.
+#[cfg(any(test, feature = "log"))]
+fn kernel_response(kernel: &[f64], factor: usize, freq: f64) -> f64 {
+    let omega = std::f64::consts::PI * freq / factor as f64;
+    let (re, im) = kernel
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(re, im), (n, value)| {
+            let angle = omega * n as f64;
+            (re + value * angle.cos(), im - value * angle.sin())
+        });
+    (re * re + im * im).sqrt()
+}
+
+/// Helper function. Find the edge of the stopband of a windowed sinc with the given length
+/// and relative cutoff, using the criterion of utils/cutoff_fit_cubic.py.
+/// The sinc is oversampled 10 times, and the edge is the first minimum of the response,
+/// or the -20 dB point for [WindowFunction::HannPoisson].
+/// The edge is given relative to the Nyquist frequency, where the fitted cutoff places it.
+/// Returns `None` if no edge is found below 1.5 times the Nyquist frequency.
+#[cfg(any(test, feature = "log"))]
+pub(crate) fn stopband_edge(
+    sinc_len: usize,
+    windowfunc: WindowFunction,
+    cutoff: f64,
+) -> Option<f64> {
+    let factor = 10;
+    let npoints = sinc_len * factor;
+    let kernel = make_window::<f64>(npoints, windowfunc)
+        .iter()
+        .enumerate()
+        .map(|(n, value)| {
+            let x =
+                std::f64::consts::PI * (n as f64 - npoints as f64 / 2.0) * cutoff / factor as f64;
+            if x == 0.0 {
+                *value
+            } else {
+                value * x.sin() / x
+            }
+        })
+        .collect::<Vec<_>>();
+    let dc_gain = kernel_response(&kernel, factor, 0.0);
+    // Step through the transition band, starting from the cutoff.
+    let step = 1.0e-4;
+    let mut freq = cutoff;
+    let mut previous = kernel_response(&kernel, factor, freq);
+    while freq < 1.5 {
+        let next = kernel_response(&kernel, factor, freq + step);
+        if let WindowFunction::HannPoisson(_) = windowfunc {
+            if 20.0 * (next / dc_gain).log10() < -20.0 {
+                return Some(freq + 0.5 * step);
+            }
+        } else if next > previous {
+            return Some(freq);
+        }
+        previous = next;
+        freq += step;
+    }
+    None
 }
 
 #[cfg(test)]
 mod tests {
     extern crate approx;
+    use crate::error::WindowError;
+    use crate::windows::all_window_functions;
+    use crate::windows::approximate_attenuation;
     use crate::windows::blackman;
     use crate::windows::blackman_harris;
     use crate::windows::calculate_cutoff;
+    use crate::windows::cutoff_coefficients;
     use crate::windows::hann;
+    use crate::windows::hann_poisson;
     use crate::windows::make_window;
+    use crate::windows::planck_taper;
+    use crate::windows::stopband_edge;
+    #[cfg(feature = "fft_resampler")]
+    use crate::windows::window_autocorrelation;
+    use crate::windows::window_frequency_response;
+    use crate::windows::window_from_csv;
+    use crate::windows::window_overlap_add_scale;
+    use crate::windows::window_product;
+    use crate::windows::window_value;
     use crate::windows::WindowFunction;
     use approx::assert_abs_diff_eq;
     use test_log::test;
@@ -196,6 +771,94 @@ mod tests {
         assert!(wnd[15] < 0.1);
     }
 
+    #[test]
+    fn test_hann_poisson() {
+        let wnd = hann_poisson::<f64>(16, 2.0);
+        let wnd_hann = hann::<f64>(16);
+        assert_abs_diff_eq!(wnd[8], 1.0, epsilon = 0.000001);
+        assert!(wnd[0] < 0.000001);
+        assert_abs_diff_eq!(wnd[4], wnd_hann[4] * (-1.0f64).exp(), epsilon = 0.000001);
+        assert_abs_diff_eq!(wnd[3], wnd[13], epsilon = 0.000001);
+        // With alpha = 0 it is a plain Hann window.
+        let wnd = make_window::<f64>(16, WindowFunction::HannPoisson(0.0));
+        for (value, expected) in wnd.iter().zip(wnd_hann.iter()) {
+            assert_abs_diff_eq!(value, expected, epsilon = 0.000001);
+        }
+    }
+
+    #[test]
+    fn window_values() {
+        for windowfunc in all_window_functions() {
+            let window = make_window::<f64>(100, *windowfunc);
+            for (x, expected) in window.iter().enumerate() {
+                let value = window_value(100, x, *windowfunc);
+                assert_abs_diff_eq!(value, expected, epsilon = 1.0e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_planck_taper() {
+        let npoints = 4096;
+        let wnd = planck_taper::<f64>(npoints, 0.1);
+        assert_eq!(wnd[0], 0.0);
+        assert_abs_diff_eq!(wnd[npoints / 2], 1.0, epsilon = 0.000001);
+        assert_abs_diff_eq!(wnd[410], 1.0, epsilon = 0.000001);
+        assert_abs_diff_eq!(wnd[205], 0.5, epsilon = 0.01);
+        assert_abs_diff_eq!(wnd[1], wnd[npoints - 1], epsilon = 0.000001);
+        // The window and its first derivatives should not have any abrupt jumps,
+        // in particular not where the tapers meet the flat part.
+        let mut diff = wnd.clone();
+        for (order, limit) in [0.01, 0.0001, 0.00001].iter().enumerate() {
+            diff = diff.windows(2).map(|pair| pair[1] - pair[0]).collect();
+            let max_step = diff.iter().fold(0.0f64, |acc, val| acc.max(val.abs()));
+            assert!(
+                max_step < *limit,
+                "Derivative {} has a step of {}",
+                order + 1,
+                max_step
+            );
+        }
+    }
+
+    #[test]
+    fn test_overlap_add_scale() {
+        let scale = window_overlap_add_scale::<f64>(WindowFunction::Hann, 1024, 512);
+        assert_abs_diff_eq!(scale, 1.0, epsilon = 0.000001);
+        let scale = window_overlap_add_scale::<f64>(WindowFunction::Hann, 1024, 256);
+        assert_abs_diff_eq!(scale, 0.5, epsilon = 0.000001);
+        // Overlapping scaled copies of the window should sum to unity.
+        let npoints = 1024;
+        let hop_size = 128;
+        let wnd = make_window::<f64>(npoints, WindowFunction::Blackman);
+        let scale = window_overlap_add_scale::<f64>(WindowFunction::Blackman, npoints, hop_size);
+        for offset in 0..hop_size {
+            let sum: f64 = wnd.iter().skip(offset).step_by(hop_size).sum();
+            assert_abs_diff_eq!(sum * scale, 1.0, epsilon = 0.000001);
+        }
+    }
+
+    #[test]
+    fn test_overlap_add_scale_large_hop() {
+        // With a hop larger than half the window, the overlap-add gain varies,
+        // and the scaled copies should sum to unity on average.
+        let npoints = 1024;
+        let hop_size = 768;
+        let wnd = make_window::<f64>(npoints, WindowFunction::Hann);
+        let scale = window_overlap_add_scale::<f64>(WindowFunction::Hann, npoints, hop_size);
+        let total: f64 = (0..hop_size)
+            .map(|offset| wnd.iter().skip(offset).step_by(hop_size).sum::<f64>())
+            .sum();
+        assert_abs_diff_eq!(total * scale / hop_size as f64, 1.0, epsilon = 0.000001);
+        assert!(scale > 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at most the window length")]
+    fn test_overlap_add_scale_hop_too_large() {
+        window_overlap_add_scale::<f64>(WindowFunction::Hann, 1024, 1025);
+    }
+
     #[test]
     fn test_cutoff() {
         let cutoff = calculate_cutoff::<f64>(128, WindowFunction::Blackman);
@@ -222,5 +885,223 @@ mod tests {
         assert_abs_diff_eq!(cutoff, 0.958, epsilon = 0.001);
         let cutoff = calculate_cutoff::<f64>(256, WindowFunction::Hann2);
         assert_abs_diff_eq!(cutoff, 0.979, epsilon = 0.001);
+        let cutoff = calculate_cutoff::<f64>(128, WindowFunction::HannPoisson(2.0));
+        assert_abs_diff_eq!(cutoff, 0.966, epsilon = 0.001);
+        let cutoff = calculate_cutoff::<f64>(256, WindowFunction::HannPoisson(2.0));
+        assert_abs_diff_eq!(cutoff, 0.983, epsilon = 0.001);
+    }
+
+    #[test]
+    fn test_cutoff_monotonic() {
+        for window in all_window_functions().iter().copied() {
+            for npoints in (32..2048).step_by(2) {
+                let cutoff = calculate_cutoff::<f64>(npoints, window);
+                let next = calculate_cutoff::<f64>(npoints + 2, window);
+                assert!(
+                    cutoff < next,
+                    "Cutoff for {:?} is not increasing, {} at sinc length {} but {} at {}. Check the polynomial coefficients.",
+                    window,
+                    cutoff,
+                    npoints,
+                    next,
+                    npoints + 2
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_window_functions() {
+        // The match is exhaustive, so adding a new window function fails to compile
+        // until it has been added here, as a reminder to also add it to the list.
+        let index = |window: &WindowFunction| match window {
+            WindowFunction::Blackman => 0,
+            WindowFunction::Blackman2 => 1,
+            WindowFunction::BlackmanHarris => 2,
+            WindowFunction::BlackmanHarris2 => 3,
+            WindowFunction::Hann => 4,
+            WindowFunction::Hann2 => 5,
+            WindowFunction::PlanckTaper { .. } => 6,
+            WindowFunction::HannPoisson(_) => 7,
+            WindowFunction::Product(..) => 8,
+            WindowFunction::CosineSum(_) => 9,
+        };
+        let mut found = [false; 10];
+        for window in all_window_functions() {
+            found[index(window)] = true;
+            let wnd = make_window::<f64>(64, *window);
+            assert_abs_diff_eq!(wnd[32], 1.0, epsilon = 0.000001);
+        }
+        assert!(
+            found.iter().all(|f| *f),
+            "Missing window functions: {:?}",
+            found
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fft_resampler")]
+    fn test_window_autocorrelation() {
+        let npoints = 64;
+        let wnd = make_window::<f64>(npoints, WindowFunction::Blackman);
+        let autocorr = window_autocorrelation(&wnd);
+        assert_eq!(autocorr.len(), npoints);
+        let sum_of_squares: f64 = wnd.iter().map(|value| value * value).sum();
+        assert_abs_diff_eq!(autocorr[0], sum_of_squares, epsilon = 1.0e-9);
+        let lag = 5;
+        let direct: f64 = (0..npoints)
+            .map(|n| wnd[n] * wnd[(n + lag) % npoints])
+            .sum();
+        assert_abs_diff_eq!(autocorr[lag], direct, epsilon = 1.0e-9);
+
+        // The spectrum of the periodic Hann window has three non-zero bins,
+        // with the values N/2 at DC and -N/4 at the first bins on either side.
+        // The autocorrelation is then N/4 + N/8 * cos(2*pi*k/N).
+        let hann = make_window::<f64>(npoints, WindowFunction::Hann);
+        let autocorr = window_autocorrelation(&hann);
+        for (k, value) in autocorr.iter().enumerate() {
+            let angle = 2.0 * std::f64::consts::PI * k as f64 / npoints as f64;
+            let expected = npoints as f64 * (0.25 + 0.125 * angle.cos());
+            assert_abs_diff_eq!(*value, expected, epsilon = 1.0e-9);
+        }
+    }
+
+    #[test]
+    fn test_window_from_csv() {
+        let csv = "0.0\n0.5,\n\n  1.0\n0.5\n";
+        let window = window_from_csv::<f64>(csv.as_bytes()).unwrap();
+        let hann = make_window::<f64>(4, WindowFunction::Hann);
+        assert_eq!(window.len(), hann.len());
+        for (value, expected) in window.iter().zip(hann.iter()) {
+            assert_abs_diff_eq!(value, expected, epsilon = 1.0e-12);
+        }
+        assert_eq!(
+            window_from_csv::<f32>("0.0\n0.5\nabc\n".as_bytes()),
+            Err(WindowError::InvalidCoefficient {
+                line: 3,
+                text: "abc".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_window_product() {
+        let hann = make_window::<f64>(64, WindowFunction::Hann);
+        let product = window_product(&hann, &hann, false).unwrap();
+        let hann2 = make_window::<f64>(64, WindowFunction::Hann2);
+        for (value, expected) in product.iter().zip(hann2.iter()) {
+            assert_abs_diff_eq!(value, expected, epsilon = 1.0e-12);
+        }
+        let halved = hann.iter().map(|value| 0.5 * value).collect::<Vec<_>>();
+        let normalized = window_product(&halved, &hann, true).unwrap();
+        assert_abs_diff_eq!(normalized[32], 1.0, epsilon = 1.0e-12);
+        assert_eq!(
+            window_product(&hann, &hann[..32], false),
+            Err(WindowError::LengthMismatch {
+                first: 64,
+                second: 32
+            })
+        );
+        let windowfunc = WindowFunction::Product(&WindowFunction::Hann, &WindowFunction::Hann);
+        let wnd = make_window::<f64>(64, windowfunc);
+        for (value, expected) in wnd.iter().zip(hann2.iter()) {
+            assert_abs_diff_eq!(value, expected, epsilon = 1.0e-12);
+        }
+        let cutoff = calculate_cutoff::<f64>(256, windowfunc);
+        assert!(cutoff < calculate_cutoff::<f64>(256, WindowFunction::Hann));
+    }
+
+    #[test]
+    fn test_cosine_sum() {
+        let windowfunc = WindowFunction::CosineSum(&[0.42, -0.5, 0.08]);
+        let wnd = make_window::<f64>(64, windowfunc);
+        let blackman = make_window::<f64>(64, WindowFunction::Blackman);
+        for (value, expected) in wnd.iter().zip(blackman.iter()) {
+            assert_abs_diff_eq!(value, expected, epsilon = 1.0e-12);
+        }
+        assert_abs_diff_eq!(approximate_attenuation(windowfunc), 58.1, epsilon = 0.5);
+        let hann = WindowFunction::CosineSum(&[0.5, -0.5]);
+        assert_abs_diff_eq!(approximate_attenuation(hann), 31.5, epsilon = 0.5);
+        let blackman_harris = WindowFunction::CosineSum(&[0.35875, -0.48829, 0.14128, -0.01168]);
+        assert_abs_diff_eq!(
+            approximate_attenuation(blackman_harris),
+            92.0,
+            epsilon = 0.5
+        );
+
+        // The main lobes are 3 and 4 bins wide on each side.
+        assert_abs_diff_eq!(cutoff_coefficients(windowfunc).0, 6.0, epsilon = 0.02);
+        assert_abs_diff_eq!(cutoff_coefficients(blackman_harris).0, 8.0, epsilon = 0.02);
+        for npoints in [128, 256, 1024] {
+            for (cosine_sum, fitted) in [
+                (windowfunc, WindowFunction::Blackman),
+                (blackman_harris, WindowFunction::BlackmanHarris),
+            ] {
+                let cutoff = calculate_cutoff::<f64>(npoints, cosine_sum);
+                let expected = calculate_cutoff::<f64>(npoints, fitted);
+                assert_abs_diff_eq!(cutoff, expected, epsilon = 0.005);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_coefficients() {
+        // Check the fitted constants against the criterion used by utils/cutoff_fit_cubic.py.
+        // The sinc is oversampled 10 times like in the script, and the cutoff should place
+        // the first minimum of the response at the Nyquist frequency,
+        // or the -20 dB point for HannPoisson.
+        let windows = [
+            WindowFunction::Blackman,
+            WindowFunction::Blackman2,
+            WindowFunction::BlackmanHarris,
+            WindowFunction::BlackmanHarris2,
+            WindowFunction::Hann,
+            WindowFunction::Hann2,
+            WindowFunction::HannPoisson(2.0),
+        ];
+        for windowfunc in windows {
+            for sinc_len in [64, 256, 1024] {
+                let cutoff = calculate_cutoff::<f64>(sinc_len, windowfunc);
+                let edge = stopband_edge(sinc_len, windowfunc, cutoff).unwrap();
+                assert!(
+                    (edge - 1.0).abs() < 1.0e-3,
+                    "The edge for {:?} with length {} is at {}",
+                    windowfunc,
+                    sinc_len,
+                    edge
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn short_sinc_edge_error() {
+        // Below the fitted range, the edge moves away from the Nyquist frequency,
+        // which is the error reported by the warning in calculate_cutoff.
+        let cutoff = calculate_cutoff::<f64>(12, WindowFunction::Blackman);
+        let edge = stopband_edge(12, WindowFunction::Blackman, cutoff).unwrap();
+        assert!((edge - 1.0).abs() > 1.0e-3, "The edge is at {}", edge);
+        // For very short sincs, the edge is beyond the search range.
+        let cutoff = calculate_cutoff::<f64>(8, WindowFunction::BlackmanHarris2);
+        assert!(stopband_edge(8, WindowFunction::BlackmanHarris2, cutoff).is_none());
+    }
+
+    #[test]
+    fn hann_frequency_response() {
+        let window = hann::<f64>(64);
+        let response = window_frequency_response(&window, 2049);
+        assert_eq!(response.len(), 2049);
+        assert_eq!(response[0], (0.0, 0.0));
+        assert_abs_diff_eq!(response[2048].0, 0.5);
+        // The main lobe ends at the first null, two bins from zero.
+        let null = (1..response.len() - 1)
+            .find(|&n| response[n].1 < response[n - 1].1 && response[n].1 < response[n + 1].1)
+            .unwrap();
+        assert_abs_diff_eq!(response[null].0, 2.0 / 64.0, epsilon = 1.0e-3);
+        // The highest side lobe is at -31.5 dB.
+        let sidelobe = response[null..]
+            .iter()
+            .fold(f64::NEG_INFINITY, |max, (_, db)| max.max(*db));
+        assert_abs_diff_eq!(sidelobe, -31.5, epsilon = 0.3);
     }
 }
@@ -32,6 +32,11 @@ def hann(npoints):
     a0=0.5
     return raised_cosine(npoints, a0)
 
+def hann_poisson(npoints, alpha):
+    x=np.arange(0,npoints)
+    y= hann(npoints) * np.exp(-alpha*np.abs(npoints - 2*x)/npoints)
+    return y
+
 def rect(npoints):
     a0=1.0
     return raised_cosine(npoints, a0)
@@ -90,6 +95,15 @@ def get_first_min(sinc):
     #print("min at", f[minima[0]])
     return f[minima[0]]
 
+def get_level_crossing(sinc, level):
+    # For windows without side lobe minima, find the frequency where the response falls below `level` dB.
+    npoints = len(sinc)
+    f = np.linspace(0, FACTOR*FS/2.0, math.floor(npoints/2))
+    valfft = fft.fft(sinc)
+    cut = valfft[0:math.floor(npoints/2)]
+    ampl = 20*np.log10(np.abs(cut)/np.abs(cut[0]))
+    return f[np.argmax(ampl < level)]
+
 def plot_sinc_fft(cutoff, window, power):
     sinc_len = len(window)/FACTOR
     sinc = pad_vec(make_sinc(sinc_len, cutoff, FACTOR, power, window), 2**16)
@@ -157,12 +171,25 @@ if __name__ == "__main__":
     print("]")
     print("")
 
+    # Hann-Poisson with alpha=2 has no side lobe minima.
+    # Instead place the -20 dB point, where the steep part of the transition ends, at the Nyquist frequency.
+    hp_cutoffs = []
+    for sinclen in SINCLENGTHS:
+        wind = hann_poisson(sinclen*FACTOR, 2.0)
+        def get_offset_hp(cutoff):
+            sinc = pad_vec(make_sinc(sinclen, cutoff, FACTOR, 1, wind), 2**16)
+            diff = get_level_crossing(sinc, -20.0) - FS/2
+            return abs(diff)
+        res = minimize(get_offset_hp, [1.0], method='Nelder-Mead', tol=1e-7)
+        hp_cutoffs.append(res.x[0])
+    popt_hp, _ = curve_fit(func, SINCLENGTHS, hp_cutoffs)
+
     print("\nCopy to windows.rs:")
     for name, values in constants.items():
         for power in range(2):
-            print(f"    WindowFunction::{name}{power + 1 if power>0 else ''} => (")
-            for val in values[power]:
-                print(f"        T::coerce({val}),")
-            print("    ),")
+            vals = ", ".join(str(v) for v in values[power])
+            print(f"        WindowFunction::{name}{power + 1 if power>0 else ''} => ({vals}),")
+    vals = ", ".join(str(v) for v in popt_hp[0:3])
+    print(f"        WindowFunction::HannPoisson(_) => ({vals}),")
 
     plt.show()